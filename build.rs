@@ -1,8 +1,14 @@
+use std::env;
 use std::fs;
 use std::path::Path;
 use std::process::Command;
 
 fn main() {
+    // Only the browser build needs bindings, e.g. `rugl-render` builds for the host
+    if env::var("CARGO_CFG_TARGET_ARCH").map_or(true, |arch| arch != "wasm32") {
+        return;
+    }
+
     let dest_path = Path::new("pkg");
 
     if !dest_path.exists() {
@@ -0,0 +1,69 @@
+/*!
+`#[derive(Std140)]` for the structs packed into rugl's uniform blocks.
+
+Every member is written at the offset the std140 rules give it and recorded under its name, so
+the layout can be checked against the one reflected from the linked program.
+ */
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericParam};
+
+#[proc_macro_derive(Std140)]
+pub fn derive_std140(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .filter_map(|field| field.ident.clone())
+                .collect::<Vec<_>>(),
+            _ => {
+                return error(
+                    &input,
+                    "Std140 can only be derived for structs with named fields",
+                )
+            }
+        },
+        _ => return error(&input, "Std140 can only be derived for structs"),
+    };
+
+    // GLSL names members without the `r#` of raw identifiers
+    let names = fields
+        .iter()
+        .map(|field| field.to_string().trim_start_matches("r#").to_owned());
+
+    // Every type parameter has to be packable itself
+    for param in &mut input.generics.params {
+        if let GenericParam::Type(param) = param {
+            param.bounds.push(parse_quote!(::rugl::prelude::Std140));
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let expanded = quote! {
+        impl #impl_generics ::rugl::prelude::Std140 for #name #ty_generics #where_clause {
+            // Structs are aligned like a vec4 and padded up to it at their end
+            const ALIGNMENT: usize = 16;
+
+            fn write_std140(&self, writer: &mut ::rugl::prelude::Std140Writer) {
+                #( writer.member(#names, &self.#fields); )*
+                writer.align(16);
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+fn error(input: &DeriveInput, message: &str) -> TokenStream {
+    syn::Error::new_spanned(&input.ident, message)
+        .to_compile_error()
+        .into()
+}
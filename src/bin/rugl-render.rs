@@ -0,0 +1,165 @@
+//! Render a scene file to PNG without a GPU or a browser.
+//!
+//! ```text
+//! rugl-render triangle.rugl --size 512x512 --ticks 0..120 --output triangle.gif
+//! ```
+
+use std::env;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use rugl::prelude::{CpuRenderer, FrameSequence, FrameSource, Scene};
+
+const USAGE: &str = "\
+Usage: rugl-render <scene> [options]
+
+Options:
+    --size <WxH>      Size of the image in pixels, up to 16384 on each side, defaults to 512x512
+    --ticks <N|A..B>  Tick to render, or a range of ticks, defaults to 0
+    --output <path>   Where to write the image, defaults to the scene with a .png extension.
+                      Ranges are written as numbered PNG files, or as one file when the path
+                      ends in .gif or .zip
+    --rate <FPS>      Frames per second of a GIF, defaults to 30";
+
+/// Largest width or height rendered, as much as a WebGL canvas usually allows
+const MAX_SIZE: u32 = 16384;
+
+struct Options {
+    scene: PathBuf,
+    width: u32,
+    height: u32,
+    ticks: Option<Range<u32>>,
+    tick: u32,
+    output: Option<PathBuf>,
+    rate: f64,
+}
+
+fn parse_options(mut args: env::Args) -> Result<Options, String> {
+    args.next();
+    let mut options = Options {
+        scene: PathBuf::new(),
+        width: 512,
+        height: 512,
+        ticks: None,
+        tick: 0,
+        output: None,
+        rate: 30.0,
+    };
+
+    let mut scene = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value!", arg));
+        match arg.as_str() {
+            "--size" => {
+                let size = value()?;
+                let mut parts = size.splitn(2, 'x').map(str::parse::<u32>);
+                match (parts.next(), parts.next()) {
+                    (Some(Ok(width)), Some(Ok(height)))
+                        if (1..=MAX_SIZE).contains(&width) && (1..=MAX_SIZE).contains(&height) =>
+                    {
+                        options.width = width;
+                        options.height = height;
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Invalid size {}, expected e.g. 512x512 and at most {}x{}!",
+                            size, MAX_SIZE, MAX_SIZE
+                        ))
+                    }
+                }
+            }
+            "--ticks" => {
+                let ticks = value()?;
+                let invalid = || format!("Invalid ticks {}, expected e.g. 0 or 0..120!", ticks);
+                if let Some(split) = ticks.find("..") {
+                    let start = ticks[..split].parse().map_err(|_| invalid())?;
+                    let end = ticks[split + 2..].parse().map_err(|_| invalid())?;
+                    if start >= end {
+                        return Err(invalid());
+                    }
+                    options.ticks = Some(start..end);
+                } else {
+                    options.tick = ticks.parse().map_err(|_| invalid())?;
+                }
+            }
+            "--output" => options.output = Some(PathBuf::from(value()?)),
+            "--rate" => {
+                let rate = value()?;
+                options.rate = rate
+                    .parse()
+                    .map_err(|_| format!("Invalid rate {}!", rate))?;
+            }
+            "--help" | "-h" => return Err(String::from(USAGE)),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}!", arg)),
+            _ if scene.is_none() => scene = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument {}!", arg)),
+        }
+    }
+
+    options.scene = scene.ok_or_else(|| String::from(USAGE))?;
+    Ok(options)
+}
+
+fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+    let error = |err| format!("Unable to write {}: {}!", path.display(), err);
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(error)?;
+    }
+    fs::write(path, data).map_err(error)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let scene = Scene::open(&options.scene)?;
+    let mut renderer = CpuRenderer::new(&scene, options.width, options.height)?;
+    let output = options
+        .output
+        .clone()
+        .unwrap_or_else(|| options.scene.with_extension("png"));
+
+    let ticks = match options.ticks {
+        Some(ticks) => ticks,
+        None => {
+            let image = renderer.render_frame(f64::from(options.tick))?;
+            return write(&output, &image.to_png());
+        }
+    };
+
+    let extension = output.extension().and_then(|extension| extension.to_str());
+    let stem = output
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("frame")
+        .to_owned();
+    match extension {
+        Some("gif") => {
+            let frames = FrameSequence::capture(&mut renderer, ticks)?;
+            write(&output, &frames.to_gif(options.rate)?)
+        }
+        Some("zip") => {
+            let frames = FrameSequence::capture(&mut renderer, ticks)?;
+            write(&output, &frames.to_png_zip(&format!("{}-", stem)))
+        }
+        _ => {
+            // Frames are numbered by their tick
+            let digits = ticks.end.saturating_sub(1).to_string().len().max(4);
+            for tick in ticks {
+                let name = format!("{}-{:0width$}.png", stem, tick, width = digits);
+                let png = renderer.render_frame(f64::from(tick))?.to_png();
+                write(&output.with_file_name(name), &png)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    let result = parse_options(env::args()).and_then(run);
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
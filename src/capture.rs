@@ -0,0 +1,148 @@
+/*!
+Capturing a design frame by frame for a range of ticks, e.g. to publish it as an animated GIF.
+
+```ignore
+let mut design = Rugl::new(rugl_description!(...))?;
+let frames = FrameSequence::capture(&mut design, 0..120)?;
+download("design.gif", "image/gif", &frames.to_gif(30.0)?)?;
+```
+ */
+
+use std::ops::Range;
+
+use crate::image::{gif, zip, Image};
+use crate::rugl::Rugl;
+
+/// Draws the frame of a tick and reads it back, on WebGL or without a GPU
+pub trait FrameSource {
+    fn render_frame(&mut self, tick: f64) -> Result<Image, String>;
+}
+
+impl<'a> FrameSource for Rugl<'a> {
+    fn render_frame(&mut self, tick: f64) -> Result<Image, String> {
+        self.draw_tick(tick)?;
+        self.screenshot()
+    }
+}
+
+impl<F: FnMut(f64) -> Result<Image, String>> FrameSource for F {
+    fn render_frame(&mut self, tick: f64) -> Result<Image, String> {
+        self(tick)
+    }
+}
+
+/// Frames of the same size, in the order they are shown
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameSequence {
+    frames: Vec<Image>,
+}
+
+impl FrameSequence {
+    pub fn new(frames: Vec<Image>) -> Self {
+        FrameSequence { frames }
+    }
+
+    /// Render the frame of every tick in `ticks`
+    pub fn capture<S: FrameSource + ?Sized>(
+        source: &mut S,
+        ticks: Range<u32>,
+    ) -> Result<Self, String> {
+        let frames = ticks
+            .map(|tick| source.render_frame(f64::from(tick)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(FrameSequence { frames })
+    }
+
+    pub fn push(&mut self, frame: Image) {
+        self.frames.push(frame);
+    }
+
+    pub fn get_frames(&self) -> &[Image] {
+        &self.frames
+    }
+
+    /// Encode the frames as a GIF looping at `rate` frames per second, which GIF rounds to a
+    /// delay in hundredths of a second
+    pub fn to_gif(&self, rate: f64) -> Result<Vec<u8>, String> {
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(format!("Unable to show {} frames per second!", rate));
+        }
+
+        let delay = (100.0 / rate).round().max(1.0).min(f64::from(u16::MAX));
+        gif::encode(&self.frames, delay as u16)
+    }
+
+    /// Encode the frames as PNG files in a zip archive, numbered from `{prefix}0000.png`
+    pub fn to_png_zip(&self, prefix: &str) -> Vec<u8> {
+        let digits = self.frames.len().saturating_sub(1).to_string().len().max(4);
+        let files: Vec<(String, Vec<u8>)> = self
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| {
+                let name = format!("{}{:0width$}.png", prefix, index, width = digits);
+                (name, frame.to_png())
+            })
+            .collect();
+
+        zip::encode(
+            files
+                .iter()
+                .map(|(name, png)| (name.clone(), png.as_slice())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::zip::tests::files;
+
+    /// One pixel frames whose red channel is the tick
+    fn ticks(tick: f64) -> Result<Image, String> {
+        Image::new(1, 1, vec![tick as u8, 0, 0, 255])
+    }
+
+    #[test]
+    fn frames_are_captured_in_order() {
+        let frames = FrameSequence::capture(&mut ticks, 3..6).unwrap();
+        let red: Vec<u8> = frames
+            .get_frames()
+            .iter()
+            .map(|f| f.get_pixels()[0])
+            .collect();
+        assert_eq!(red, [3, 4, 5]);
+
+        let mut failing = |tick: f64| match tick as u32 {
+            4 => Err(String::from("Unable to draw tick 4!")),
+            _ => ticks(tick),
+        };
+        let err = FrameSequence::capture(&mut failing, 3..6).unwrap_err();
+        assert_eq!(err, "Unable to draw tick 4!");
+    }
+
+    #[test]
+    fn png_zips_number_their_frames() {
+        let frames = FrameSequence::capture(&mut ticks, 0..3).unwrap();
+        let files = files(&frames.to_png_zip("frame-"));
+
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["frame-0000.png", "frame-0001.png", "frame-0002.png"]
+        );
+        for ((_, png), frame) in files.iter().zip(frames.get_frames()) {
+            assert_eq!(png, &frame.to_png());
+        }
+    }
+
+    #[test]
+    fn gif_rates_must_be_positive() {
+        let frames = FrameSequence::capture(&mut ticks, 0..2).unwrap();
+        assert!(frames.to_gif(30.0).is_ok());
+        for rate in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(frames.to_gif(*rate).is_err(), "{}", rate);
+        }
+    }
+}
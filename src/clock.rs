@@ -0,0 +1,192 @@
+/*!
+Clocks driving the simulation time of a design, swapping the clock makes the frames of a design
+reproducible.
+ */
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// A source of time in seconds
+pub trait Clock {
+    fn now(&mut self) -> f64;
+}
+
+/// The browser's high resolution clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PerformanceClock;
+
+impl Clock for PerformanceClock {
+    fn now(&mut self) -> f64 {
+        web_sys::window()
+            .and_then(|window| window.performance())
+            .map_or(0.0, |performance| performance.now() / 1000.0)
+    }
+}
+
+/// A clock that only moves when told to, clones share the same time
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    time: Rc<Cell<f64>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, seconds: f64) {
+        self.time.set(self.time.get() + seconds);
+    }
+
+    pub fn set(&self, seconds: f64) {
+        self.time.set(seconds);
+    }
+
+    pub fn get(&self) -> f64 {
+        self.time.get()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&mut self) -> f64 {
+        self.time.get()
+    }
+}
+
+/// How simulation ticks relate to drawn frames
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Timestep {
+    /// Advance one tick for every drawn frame
+    #[default]
+    PerFrame,
+    /// Advance ticks at a fixed rate in Hz regardless of how often frames are drawn
+    Fixed(f64),
+}
+
+impl Timestep {
+    /// Check that a fixed rate is a positive number of ticks per second
+    pub fn validate(self) -> Result<(), String> {
+        match self {
+            Timestep::Fixed(rate) if !rate.is_finite() || rate <= 0.0 => Err(format!(
+                "A fixed timestep needs a positive rate but got {}!",
+                rate
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Never simulate more than this many seconds in one frame, so a stall does not turn into
+/// a burst of catch up ticks
+const MAX_FRAME_TIME: f64 = 0.25;
+
+/// Fraction of a step that time may fall short by and still count as a whole tick, as exact
+/// multiples of the step lose a little to rounding
+const TOLERANCE: f64 = 1e-9;
+
+/// Accumulates clock time into whole fixed ticks
+#[derive(Debug, Clone, Default)]
+pub struct Accumulator {
+    last: Option<f64>,
+    accumulated: f64,
+}
+
+impl Accumulator {
+    /// Add the time passed since the previous call, returning how many ticks to advance
+    /// and the interpolation alpha between the last tick and the next one
+    pub fn advance(&mut self, now: f64, rate: f64) -> (u32, f64) {
+        debug_assert!(Timestep::Fixed(rate).validate().is_ok());
+        let elapsed = self.last.map_or(0.0, |last| now - last);
+        self.last = Some(now);
+        if !rate.is_finite() || rate <= 0.0 {
+            return (0, 0.0);
+        }
+
+        let step = 1.0 / rate;
+        self.accumulated += elapsed.clamp(0.0, MAX_FRAME_TIME);
+
+        let mut ticks = 0;
+        while self.accumulated >= step * (1.0 - TOLERANCE) {
+            self.accumulated = (self.accumulated - step).max(0.0);
+            ticks += 1;
+        }
+
+        (ticks, self.accumulated / step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Advance `clock` by `seconds` and feed it to `accumulator` at 60 Hz
+    fn frame(clock: &mut ManualClock, accumulator: &mut Accumulator, seconds: f64) -> (u32, f64) {
+        clock.advance(seconds);
+        accumulator.advance(clock.now(), 60.0)
+    }
+
+    #[test]
+    fn first_frame_starts_the_clock() {
+        let mut clock = ManualClock::new();
+        clock.set(10.0);
+        assert_eq!(Accumulator::default().advance(clock.now(), 60.0), (0, 0.0));
+    }
+
+    #[test]
+    fn exact_multiples_of_the_step_tick_every_frame() {
+        let (mut clock, mut accumulator) = (ManualClock::new(), Accumulator::default());
+        frame(&mut clock, &mut accumulator, 0.0);
+
+        for _ in 0..600 {
+            let (ticks, alpha) = frame(&mut clock, &mut accumulator, 1.0 / 60.0);
+            assert_eq!(ticks, 1);
+            assert!(alpha < 1e-6, "{}", alpha);
+        }
+        let (ticks, _) = frame(&mut clock, &mut accumulator, 3.0 / 60.0);
+        assert_eq!(ticks, 3);
+    }
+
+    #[test]
+    fn partial_steps_carry_over_as_alpha() {
+        let (mut clock, mut accumulator) = (ManualClock::new(), Accumulator::default());
+        frame(&mut clock, &mut accumulator, 0.0);
+
+        let (ticks, alpha) = frame(&mut clock, &mut accumulator, 0.5 / 60.0);
+        assert_eq!(ticks, 0);
+        assert!((alpha - 0.5).abs() < 1e-9, "{}", alpha);
+
+        let (ticks, alpha) = frame(&mut clock, &mut accumulator, 0.75 / 60.0);
+        assert_eq!(ticks, 1);
+        assert!((alpha - 0.25).abs() < 1e-9, "{}", alpha);
+    }
+
+    #[test]
+    fn stalls_are_clamped() {
+        let (mut clock, mut accumulator) = (ManualClock::new(), Accumulator::default());
+        frame(&mut clock, &mut accumulator, 0.0);
+
+        let (ticks, alpha) = frame(&mut clock, &mut accumulator, 10.0);
+        assert_eq!(ticks, (MAX_FRAME_TIME * 60.0) as u32);
+        assert!(alpha < 1e-6, "{}", alpha);
+    }
+
+    #[test]
+    fn clocks_going_backwards_do_not_tick() {
+        let (mut clock, mut accumulator) = (ManualClock::new(), Accumulator::default());
+        frame(&mut clock, &mut accumulator, 1.0);
+
+        assert_eq!(frame(&mut clock, &mut accumulator, -0.5), (0, 0.0));
+        // Time is counted again from where the clock went back to
+        let (ticks, _) = frame(&mut clock, &mut accumulator, 2.0 / 60.0);
+        assert_eq!(ticks, 2);
+    }
+
+    #[test]
+    fn invalid_rates_are_rejected() {
+        assert!(Timestep::Fixed(60.0).validate().is_ok());
+        assert!(Timestep::PerFrame.validate().is_ok());
+        for rate in &[0.0, -60.0, f64::NAN, f64::INFINITY] {
+            assert!(Timestep::Fixed(*rate).validate().is_err(), "{}", rate);
+        }
+    }
+}
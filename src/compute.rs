@@ -0,0 +1,243 @@
+/*!
+GPGPU kernels written as fragment shaders, run over every texel of a float output.
+
+The kernel gets the position of its texel in `[0, 1]` through `uv`, its inputs as float
+textures and uniforms, and writes four floats. The output is read back row by row from the
+bottom, with four floats per texel.
+
+```ignore
+let mut pass = ComputePass::new("
+    precision highp float;
+    uniform sampler2D values;
+    uniform float scale;
+    varying vec2 uv;
+
+    void main() {
+        gl_FragColor = texture2D(values, uv) * scale;
+    }
+", 256, 1)?;
+
+pass.set_input("values", 256, 1, &values)?;
+pass.set_uniform("scale", 2.0)?;
+let scaled = pass.run_and_read()?;
+```
+
+Kernels starting with `#version 300 es` take `in vec2 uv` instead and need WebGL 2.
+ */
+
+use std::marker::PhantomData;
+
+use crate::rugl::js_error;
+use crate::webgl::{
+    CanvasTarget, ContextAttributes, Extension, FramebufferHandle, FramebufferOptions, ResizeMode,
+    Sampler2D, ShaderType, TextureFormat, TextureHandle, UniformInner, Version, VertexArrayHandle,
+    WebGlContext,
+};
+
+const VERTEX_SHADER: &str = "
+    attribute vec2 rugl_position;
+    varying vec2 uv;
+
+    void main() {
+        uv = rugl_position * 0.5 + 0.5;
+        gl_Position = vec4(rugl_position, 0.0, 1.0);
+    }
+";
+
+const VERTEX_SHADER_300: &str = "#version 300 es
+    in vec2 rugl_position;
+    out vec2 uv;
+
+    void main() {
+        uv = rugl_position * 0.5 + 0.5;
+        gl_Position = vec4(rugl_position, 0.0, 1.0);
+    }
+";
+
+/// A single triangle covering the whole output
+const TRIANGLE: [f32; 6] = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+
+pub struct ComputePass {
+    context: WebGlContext,
+    vertex_array: VertexArrayHandle,
+    framebuffer: FramebufferHandle,
+    inputs: Vec<(String, TextureHandle)>,
+    width: i32,
+    height: i32,
+}
+
+impl ComputePass {
+    /// Compile a kernel computing a `width` by `height` output, on a context of its own which
+    /// is not shown on the page
+    pub fn new(kernel: &str, width: i32, height: i32) -> Result<Self, String> {
+        let attributes = ContextAttributes {
+            antialias: false,
+            depth: false,
+            version: Version::WebGl2,
+            ..Default::default()
+        };
+        let mut context =
+            WebGlContext::with_target(&CanvasTarget::Detached, &attributes).map_err(js_error)?;
+        context
+            .set_resize_mode(ResizeMode::Fixed(1, 1))
+            .map_err(js_error)?;
+
+        let version = Version::of_source(kernel);
+        if version == Version::WebGl2 && context.version() == Version::WebGl1 {
+            return Err(String::from(
+                "Kernels written for `#version 300 es` need WebGL 2, which is not supported!",
+            ));
+        }
+
+        let vertex = match version {
+            Version::WebGl1 => VERTEX_SHADER,
+            Version::WebGl2 => VERTEX_SHADER_300,
+        };
+        let vertex = context.compile_shader(ShaderType::Vertex(vertex, PhantomData))?;
+        let fragment = context.compile_shader(ShaderType::Fragment(kernel, PhantomData))?;
+        let program = context.link_and_add_program(&[vertex, fragment])?;
+        context.use_program(program)?;
+
+        let format = context.float_render_format();
+        if !format.is_float() {
+            return Err(String::from(
+                "Drawing into float textures is not supported!",
+            ));
+        }
+        let framebuffer = context.create_framebuffer(&FramebufferOptions {
+            width,
+            height,
+            color: vec![format],
+        })?;
+
+        let position = context.create_buffer_with_data("rugl_position", &TRIANGLE[..], 2)?;
+        context.bind_buffer(position)?;
+        context.enable_attribute(position)?;
+        if !context
+            .get_extensions()
+            .is_enabled(Extension::OesVertexArrayObject)
+        {
+            let _ = context.enable_extension(Extension::OesVertexArrayObject);
+        }
+        let vertex_array = context.create_vertex_array(&[position])?;
+
+        Ok(ComputePass {
+            context,
+            vertex_array,
+            framebuffer,
+            inputs: Vec::new(),
+            width,
+            height,
+        })
+    }
+
+    /// Give the kernel a `sampler2D` named `name` holding `width` by `height` texels of four
+    /// floats each, an input given again keeps its size and only has its data replaced
+    pub fn set_input(
+        &mut self,
+        name: &str,
+        width: i32,
+        height: i32,
+        data: &[f32],
+    ) -> Result<(), String> {
+        if data.len() != (width * height * 4) as usize {
+            return Err(format!(
+                "Input: {} needs {} floats for {} by {} texels but {} were given!",
+                name,
+                width * height * 4,
+                width,
+                height,
+                data.len()
+            ));
+        }
+
+        if let Some((_, texture)) = self.inputs.iter().find(|(input, _)| input == name) {
+            let texture = *texture;
+            let size = self
+                .context
+                .get_texture(texture)
+                .map(|texture| (texture.get_width(), texture.get_height()));
+            if size != Some((width, height)) {
+                return Err(format!("Input: {} was created with another size!", name));
+            }
+
+            return self.context.set_texture_data(texture, data);
+        }
+
+        let format = match self.context.version() {
+            Version::WebGl1 => TextureFormat::RgbaFloat,
+            Version::WebGl2 => TextureFormat::Rgba32f,
+        };
+        if !self.context.enable_texture_format(format) {
+            return Err(String::from("Float textures are not supported!"));
+        }
+        let texture = self
+            .context
+            .create_texture_with_data(width, height, format, data)?;
+
+        // Every input reads from a texture unit of its own
+        let unit = self.inputs.len() as i32;
+        let uniform = self
+            .context
+            .create_uniform(name, UniformInner::from(Sampler2D(unit)))?;
+        self.context.bind_uniform(uniform)?;
+        self.inputs.push((name.to_owned(), texture));
+
+        Ok(())
+    }
+
+    /// Set a uniform of the kernel
+    pub fn set_uniform<T: Into<UniformInner>>(
+        &mut self,
+        name: &str,
+        value: T,
+    ) -> Result<(), String> {
+        match self.context.get_uniform_handle(name) {
+            Some(handle) => self.context.set_uniform(handle, value.into()),
+            None => {
+                let handle = self.context.create_uniform(name, value.into())?;
+                self.context.bind_uniform(handle)
+            }
+        }
+    }
+
+    /// Run the kernel over every texel of the output
+    pub fn run(&mut self) -> Result<(), String> {
+        for (unit, (_, texture)) in self.inputs.iter().enumerate() {
+            self.context.bind_texture(*texture, unit as u32)?;
+        }
+
+        self.context.bind_framebuffer(Some(self.framebuffer))?;
+        self.context.bind_vertex_array(self.vertex_array)?;
+        self.context.draw_triangles(3);
+
+        Ok(())
+    }
+
+    /// Read the output of the last run, four floats per texel
+    pub fn read(&self) -> Result<Vec<f32>, String> {
+        self.context
+            .read_pixels_f32(self.framebuffer, 0, 0, self.width, self.height)
+    }
+
+    /// Run the kernel and read its output
+    pub fn run_and_read(&mut self) -> Result<Vec<f32>, String> {
+        self.run()?;
+        self.read()
+    }
+
+    /// The texture the output is written to, e.g. to feed it to another pass on this context
+    pub fn get_output(&self) -> Option<TextureHandle> {
+        self.context
+            .get_framebuffer(self.framebuffer)
+            .and_then(|framebuffer| framebuffer.get_colors().first().copied())
+    }
+
+    pub fn context(&self) -> &WebGlContext {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut WebGlContext {
+        &mut self.context
+    }
+}
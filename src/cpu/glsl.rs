@@ -0,0 +1,908 @@
+/*!
+Parsing the subset of GLSL ES 1.00 and 3.00 the CPU renderer runs: scalars, vectors and square
+matrices, functions, `if`, loops and `discard`. Names are resolved to slots while parsing, so
+running a shader never looks a name up.
+ */
+
+use std::collections::HashMap;
+
+use super::token::{tokenize, Token};
+use super::value::{Binary, Builtin, Type, Value};
+
+/// Where a variable lives, globals being shared by every function of a shader
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Slot {
+    Global(usize),
+    Local(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Callee {
+    Function(usize),
+    Builtin(Builtin),
+    Construct(Type),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Variable(Slot),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Binary, Box<Expr>, Box<Expr>),
+    /// An assignment, compound ones such as `+=` carrying their operator
+    Assign(Option<Binary>, Box<Expr>, Box<Expr>),
+    /// `++` or `--`, returning the value from before when used as a suffix
+    Step(f32, bool, Box<Expr>),
+    Select(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(Callee, Vec<Expr>),
+    Swizzle(Box<Expr>, Vec<usize>),
+    Index(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Declare(Vec<(usize, Option<Expr>)>),
+    Expr(Expr),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    For(Vec<Stmt>, Option<Expr>, Option<Expr>, Box<Stmt>),
+    While(Expr, Box<Stmt>),
+    DoWhile(Box<Stmt>, Expr),
+    Block(Vec<Stmt>),
+    Return(Option<Expr>),
+    Break,
+    Continue,
+    Discard,
+}
+
+/// Whether a parameter is copied into a function, out of it or both
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    In,
+    Out,
+    InOut,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub result: Type,
+    /// Parameters are the first locals
+    pub parameters: Vec<Direction>,
+    pub locals: Vec<Type>,
+    pub body: Option<Vec<Stmt>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Storage {
+    /// Attributes and varyings coming from the previous stage
+    Input,
+    Uniform,
+    /// Varyings and fragment outputs going to the next stage
+    Output,
+    /// Variables such as `gl_FragCoord` set for every invocation
+    Builtin,
+    Global,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub ty: Type,
+    pub storage: Storage,
+    pub init: Option<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Vertex,
+    Fragment,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Shader {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
+}
+
+impl Shader {
+    pub fn parse(source: &str, stage: Stage) -> Result<Shader, String> {
+        let mut parser = Parser::new(tokenize(source)?);
+        let builtins: &[(&str, Type, Storage)] = match stage {
+            Stage::Vertex => &[
+                ("gl_Position", Type::Vec(4), Storage::Output),
+                ("gl_PointSize", Type::Float, Storage::Output),
+                ("gl_VertexID", Type::Int, Storage::Builtin),
+                ("gl_InstanceID", Type::Int, Storage::Builtin),
+            ],
+            Stage::Fragment => &[
+                ("gl_FragCoord", Type::Vec(4), Storage::Builtin),
+                ("gl_FrontFacing", Type::Bool, Storage::Builtin),
+                ("gl_PointCoord", Type::Vec(2), Storage::Builtin),
+                ("gl_FragColor", Type::Vec(4), Storage::Output),
+            ],
+        };
+        for (name, ty, storage) in builtins {
+            parser.declare_global(name, *ty, *storage, None);
+        }
+
+        while !parser.is_done() {
+            parser.external(stage)?;
+        }
+
+        let shader = parser.shader;
+        match shader.find("main") {
+            Some(main) if shader.functions[main].body.is_some() => Ok(shader),
+            _ => Err(String::from("The shader has no main function!")),
+        }
+    }
+
+    pub fn find(&self, function: &str) -> Option<usize> {
+        self.functions
+            .iter()
+            .position(|candidate| candidate.name == function)
+    }
+
+    pub fn find_global(&self, name: &str) -> Option<usize> {
+        self.globals.iter().position(|global| global.name == name)
+    }
+}
+
+/// Swizzle letters, any of the three sets naming the same components
+fn swizzle_fields(name: &str) -> Option<Vec<usize>> {
+    let sets = ["xyzw", "rgba", "stpq"];
+    let fields: Option<Vec<usize>> = name
+        .chars()
+        .map(|letter| sets.iter().filter_map(|set| set.find(letter)).next())
+        .collect();
+    fields.filter(|fields| !fields.is_empty() && fields.len() <= 4)
+}
+
+/// Qualifiers which do not change how the CPU renderer runs a shader
+const IGNORED: [&str; 9] = [
+    "highp",
+    "mediump",
+    "lowp",
+    "invariant",
+    "flat",
+    "smooth",
+    "centroid",
+    "noperspective",
+    "precise",
+];
+
+pub struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    shader: Shader,
+    scopes: Vec<HashMap<String, Slot>>,
+    /// Types of the locals of the function being parsed
+    locals: Vec<Type>,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<(Token, usize)>) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+            shader: Shader::default(),
+            scopes: vec![HashMap::new()],
+            locals: Vec::new(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    pub fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|(token, _)| token)
+    }
+
+    pub fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    pub fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone())
+            .ok_or_else(|| String::from("Unexpected end of input!"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    pub fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("{} on line {}!", message, self.line()))
+    }
+
+    pub fn is_punct(&self, punct: &str) -> bool {
+        match self.peek() {
+            Some(Token::Punct(found)) => *found == punct,
+            _ => false,
+        }
+    }
+
+    pub fn is_ident(&self, ident: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(name)) => name == ident,
+            _ => false,
+        }
+    }
+
+    /// Skip the punctuation if it comes next
+    pub fn eat(&mut self, punct: &str) -> bool {
+        let found = self.is_punct(punct);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    pub fn expect(&mut self, punct: &str) -> Result<(), String> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            self.error(&format!("Expected `{}`", punct))
+        }
+    }
+
+    pub fn ident(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            _ => self.error("Expected a name"),
+        }
+    }
+
+    /// Skip tokens up to the closing bracket matching one already skipped
+    pub fn skip_group(&mut self) -> Result<(), String> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()? {
+                Token::Punct("(") | Token::Punct("[") | Token::Punct("{") => depth += 1,
+                Token::Punct(")") | Token::Punct("]") | Token::Punct("}") => depth -= 1,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn declare_global(&mut self, name: &str, ty: Type, storage: Storage, init: Option<Expr>) {
+        let slot = Slot::Global(self.shader.globals.len());
+        self.shader.globals.push(Global {
+            name: name.to_owned(),
+            ty,
+            storage,
+            init,
+        });
+        self.scopes[0].insert(name.to_owned(), slot);
+    }
+
+    /// Make a new local visible in the innermost scope
+    pub fn declare_local(&mut self, name: &str, ty: Type) -> usize {
+        let index = self.locals.len();
+        self.locals.push(ty);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_owned(), Slot::Local(index));
+        }
+        index
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    pub fn take_locals(&mut self) -> Vec<Type> {
+        std::mem::take(&mut self.locals)
+    }
+
+    fn lookup(&self, name: &str) -> Option<Slot> {
+        self.scopes
+            .iter()
+            .rev()
+            .filter_map(|scope| scope.get(name))
+            .next()
+            .cloned()
+    }
+
+    fn qualifiers(&mut self) -> Result<Vec<String>, String> {
+        let mut qualifiers = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Ident(name)) if name == "layout" => {
+                    self.position += 1;
+                    self.expect("(")?;
+                    self.skip_group()?;
+                }
+                Some(Token::Ident(name)) if IGNORED.contains(&name.as_str()) => {
+                    self.position += 1;
+                }
+                Some(Token::Ident(name))
+                    if [
+                        "attribute",
+                        "uniform",
+                        "varying",
+                        "in",
+                        "out",
+                        "inout",
+                        "const",
+                    ]
+                    .contains(&name.as_str()) =>
+                {
+                    qualifiers.push(name.clone());
+                    self.position += 1;
+                }
+                _ => return Ok(qualifiers),
+            }
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type, String> {
+        let name = self.ident()?;
+        match Type::by_name(&name) {
+            Some(ty) => Ok(ty),
+            None => {
+                self.position -= 1;
+                self.error(&format!("Unsupported type `{}`", name))
+            }
+        }
+    }
+
+    /// A declaration outside of functions
+    fn external(&mut self, stage: Stage) -> Result<(), String> {
+        if self.eat(";") {
+            return Ok(());
+        }
+        if self.is_ident("precision") {
+            while !self.eat(";") {
+                self.next()?;
+            }
+            return Ok(());
+        }
+        if self.is_ident("struct") {
+            return self.error("Structs are not supported by the CPU renderer");
+        }
+
+        let qualifiers = self.qualifiers()?;
+        let ty = self.ty()?;
+        let name = self.ident()?;
+        if self.is_punct("(") {
+            return self.function(ty, name);
+        }
+
+        let storage = match qualifiers.last().map(String::as_str) {
+            Some("attribute") | Some("in") => Storage::Input,
+            Some("varying") if stage == Stage::Vertex => Storage::Output,
+            Some("varying") => Storage::Input,
+            Some("out") => Storage::Output,
+            Some("uniform") => Storage::Uniform,
+            _ => Storage::Global,
+        };
+        let mut name = name;
+        loop {
+            if self.is_punct("[") {
+                return self.error("Arrays are not supported by the CPU renderer");
+            }
+            let init = if self.eat("=") {
+                Some(self.assignment()?)
+            } else {
+                None
+            };
+            self.declare_global(&name, ty, storage, init);
+
+            if self.eat(";") {
+                return Ok(());
+            }
+            self.expect(",")?;
+            name = self.ident()?;
+        }
+    }
+
+    fn function(&mut self, result: Type, name: String) -> Result<(), String> {
+        self.expect("(")?;
+        self.push_scope();
+        let mut parameters = Vec::new();
+        if self.is_ident("void") && self.peek_at(1) == Some(&Token::Punct(")")) {
+            self.position += 1;
+        }
+        while !self.eat(")") {
+            if !parameters.is_empty() {
+                self.expect(",")?;
+            }
+            let qualifiers = self.qualifiers()?;
+            let direction = match qualifiers.last().map(String::as_str) {
+                Some("out") => Direction::Out,
+                Some("inout") => Direction::InOut,
+                _ => Direction::In,
+            };
+            let ty = self.ty()?;
+            // Prototypes can leave parameters unnamed
+            let name = match self.peek() {
+                Some(Token::Ident(_)) => self.ident()?,
+                _ => String::new(),
+            };
+            self.declare_local(&name, ty);
+            parameters.push(direction);
+        }
+
+        // A prototype is completed by the definition with the same name and parameter count
+        let existing = self.shader.functions.iter().position(|function| {
+            function.name == name
+                && function.parameters.len() == parameters.len()
+                && function.body.is_none()
+        });
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                self.shader.functions.push(Function {
+                    name,
+                    result,
+                    parameters,
+                    locals: Vec::new(),
+                    body: None,
+                });
+                self.shader.functions.len() - 1
+            }
+        };
+
+        if self.eat(";") {
+            self.pop_scope();
+            self.take_locals();
+            return Ok(());
+        }
+
+        self.expect("{")?;
+        let body = self.block_body()?;
+        self.pop_scope();
+        let function = &mut self.shader.functions[index];
+        function.locals = std::mem::take(&mut self.locals);
+        function.body = Some(body);
+        Ok(())
+    }
+
+    /// Statements up to the closing brace, the opening one being already skipped
+    fn block_body(&mut self) -> Result<Vec<Stmt>, String> {
+        let mut statements = Vec::new();
+        while !self.eat("}") {
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn is_declaration(&self) -> bool {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                Type::by_name(name).is_some() && self.peek_at(1) != Some(&Token::Punct("("))
+                    || name == "const"
+                    || IGNORED.contains(&name.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    fn declaration(&mut self) -> Result<Stmt, String> {
+        self.qualifiers()?;
+        let ty = self.ty()?;
+        let mut declared = Vec::new();
+        loop {
+            let name = self.ident()?;
+            if self.is_punct("[") {
+                return self.error("Arrays are not supported by the CPU renderer");
+            }
+            // The initializer can not see the variable it initializes
+            let init = if self.eat("=") {
+                Some(self.assignment()?)
+            } else {
+                None
+            };
+            declared.push((self.declare_local(&name, ty), init));
+            if !self.eat(",") {
+                break;
+            }
+        }
+        Ok(Stmt::Declare(declared))
+    }
+
+    fn statement(&mut self) -> Result<Stmt, String> {
+        if self.eat("{") {
+            self.push_scope();
+            let body = self.block_body();
+            self.pop_scope();
+            return Ok(Stmt::Block(body?));
+        }
+        if self.eat(";") {
+            return Ok(Stmt::Block(Vec::new()));
+        }
+
+        let keyword = match self.peek() {
+            Some(Token::Ident(name)) => name.clone(),
+            _ => String::new(),
+        };
+        match keyword.as_str() {
+            "if" => {
+                self.position += 1;
+                self.expect("(")?;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                let then = self.scoped_statement()?;
+                let otherwise = if self.is_ident("else") {
+                    self.position += 1;
+                    Some(Box::new(self.scoped_statement()?))
+                } else {
+                    None
+                };
+                Ok(Stmt::If(condition, Box::new(then), otherwise))
+            }
+            "for" => {
+                self.position += 1;
+                self.expect("(")?;
+                self.push_scope();
+                let parsed = self.for_loop();
+                self.pop_scope();
+                parsed
+            }
+            "while" => {
+                self.position += 1;
+                self.expect("(")?;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                Ok(Stmt::While(condition, Box::new(self.scoped_statement()?)))
+            }
+            "do" => {
+                self.position += 1;
+                let body = self.scoped_statement()?;
+                if !self.is_ident("while") {
+                    return self.error("Expected `while`");
+                }
+                self.position += 1;
+                self.expect("(")?;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                self.expect(";")?;
+                Ok(Stmt::DoWhile(Box::new(body), condition))
+            }
+            "return" => {
+                self.position += 1;
+                let value = if self.is_punct(";") {
+                    None
+                } else {
+                    Some(self.expression()?)
+                };
+                self.expect(";")?;
+                Ok(Stmt::Return(value))
+            }
+            "break" | "continue" | "discard" => {
+                self.position += 1;
+                self.expect(";")?;
+                Ok(match keyword.as_str() {
+                    "break" => Stmt::Break,
+                    "continue" => Stmt::Continue,
+                    _ => Stmt::Discard,
+                })
+            }
+            _ if self.is_declaration() => {
+                let declaration = self.declaration()?;
+                self.expect(";")?;
+                Ok(declaration)
+            }
+            _ => {
+                let expression = self.expression()?;
+                self.expect(";")?;
+                Ok(Stmt::Expr(expression))
+            }
+        }
+    }
+
+    /// The body of an `if` or a loop, which gets a scope of its own even without braces
+    fn scoped_statement(&mut self) -> Result<Stmt, String> {
+        self.push_scope();
+        let statement = self.statement();
+        self.pop_scope();
+        statement
+    }
+
+    fn for_loop(&mut self) -> Result<Stmt, String> {
+        let init = if self.eat(";") {
+            Vec::new()
+        } else if self.is_declaration() {
+            let declaration = self.declaration()?;
+            self.expect(";")?;
+            vec![declaration]
+        } else {
+            let expression = self.expression()?;
+            self.expect(";")?;
+            vec![Stmt::Expr(expression)]
+        };
+        let condition = if self.is_punct(";") {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.expect(";")?;
+        let step = if self.is_punct(")") {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.expect(")")?;
+        let body = self.scoped_statement()?;
+        Ok(Stmt::For(init, condition, step, Box::new(body)))
+    }
+
+    pub fn expression(&mut self) -> Result<Expr, String> {
+        self.assignment()
+    }
+
+    fn assignment(&mut self) -> Result<Expr, String> {
+        let target = self.conditional()?;
+        let operator = match self.peek() {
+            Some(Token::Punct("=")) => None,
+            Some(Token::Punct("+=")) => Some(Binary::Add),
+            Some(Token::Punct("-=")) => Some(Binary::Subtract),
+            Some(Token::Punct("*=")) => Some(Binary::Multiply),
+            Some(Token::Punct("/=")) => Some(Binary::Divide),
+            Some(Token::Punct("%=")) => Some(Binary::Remainder),
+            _ => return Ok(target),
+        };
+        self.position += 1;
+        if !is_assignable(&target) {
+            return self.error("Unable to assign to this expression");
+        }
+        let value = self.assignment()?;
+        Ok(Expr::Assign(operator, Box::new(target), Box::new(value)))
+    }
+
+    fn conditional(&mut self) -> Result<Expr, String> {
+        let condition = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.assignment()?;
+        self.expect(":")?;
+        let otherwise = self.assignment()?;
+        Ok(Expr::Select(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    /// Binary operators by precedence, loosest first
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        const LEVELS: [&[(&str, Binary)]; 7] = [
+            &[("||", Binary::Or)],
+            &[("^^", Binary::Xor)],
+            &[("&&", Binary::And)],
+            &[("==", Binary::Equal), ("!=", Binary::NotEqual)],
+            &[
+                ("<", Binary::Less),
+                (">", Binary::Greater),
+                ("<=", Binary::LessEqual),
+                (">=", Binary::GreaterEqual),
+            ],
+            &[("+", Binary::Add), ("-", Binary::Subtract)],
+            &[
+                ("*", Binary::Multiply),
+                ("/", Binary::Divide),
+                ("%", Binary::Remainder),
+            ],
+        ];
+
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        loop {
+            let operator = LEVELS[level]
+                .iter()
+                .find(|(punct, _)| self.is_punct(punct))
+                .map(|(_, operator)| *operator);
+            match operator {
+                Some(operator) => {
+                    self.position += 1;
+                    let right = self.binary(level + 1)?;
+                    left = Expr::Binary(operator, Box::new(left), Box::new(right));
+                }
+                None => return Ok(left),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        for (punct, delta) in &[("++", 1.0), ("--", -1.0)] {
+            if self.eat(punct) {
+                let target = self.unary()?;
+                if !is_assignable(&target) {
+                    return self.error("Unable to assign to this expression");
+                }
+                return Ok(Expr::Step(*delta, true, Box::new(target)));
+            }
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expression = self.primary()?;
+        loop {
+            if self.eat(".") {
+                let name = self.ident()?;
+                if self.eat("(") {
+                    // A method call as written on Rust floats, e.g. `(0.02 * tick).sin()`
+                    let mut arguments = vec![expression];
+                    arguments.extend(self.arguments()?);
+                    let builtin = Builtin::by_name(&name);
+                    expression = match builtin {
+                        Some(builtin) => Expr::Call(Callee::Builtin(builtin), arguments),
+                        None => return self.error(&format!("Unknown method `{}`", name)),
+                    };
+                } else {
+                    match swizzle_fields(&name) {
+                        Some(fields) => expression = Expr::Swizzle(Box::new(expression), fields),
+                        None => return self.error(&format!("Unknown field `{}`", name)),
+                    }
+                }
+            } else if self.eat("[") {
+                let index = self.expression()?;
+                self.expect("]")?;
+                expression = Expr::Index(Box::new(expression), Box::new(index));
+            } else if self.is_punct("++") || self.is_punct("--") {
+                let delta = if self.is_punct("++") { 1.0 } else { -1.0 };
+                self.position += 1;
+                if !is_assignable(&expression) {
+                    return self.error("Unable to assign to this expression");
+                }
+                expression = Expr::Step(delta, false, Box::new(expression));
+            } else if self.is_ident("as") {
+                // A Rust cast, e.g. `tick as f32`
+                self.position += 1;
+                let ty = match self.ident()?.as_str() {
+                    "f32" | "f64" => Type::Float,
+                    "i32" | "u32" | "i64" | "u64" | "usize" | "isize" => Type::Int,
+                    other => return self.error(&format!("Unable to cast to `{}`", other)),
+                };
+                expression = Expr::Call(Callee::Construct(ty), vec![expression]);
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    /// Arguments up to the closing parenthesis, the opening one being already skipped
+    fn arguments(&mut self) -> Result<Vec<Expr>, String> {
+        let mut arguments = Vec::new();
+        if self.is_ident("void") && self.peek_at(1) == Some(&Token::Punct(")")) {
+            self.position += 1;
+        }
+        while !self.eat(")") {
+            if !arguments.is_empty() {
+                self.expect(",")?;
+            }
+            arguments.push(self.assignment()?);
+        }
+        Ok(arguments)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next()? {
+            Token::Int(value) => Ok(Expr::Literal(Value::Int(value as i32))),
+            Token::Float(value) => Ok(Expr::Literal(Value::Float(value as f32))),
+            Token::Punct("(") => {
+                let expression = self.expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            // A Rust block holding a single expression, e.g. the body of a uniform closure
+            Token::Punct("{") => {
+                let expression = self.expression()?;
+                self.expect("}")?;
+                Ok(expression)
+            }
+            // A Rust array, which becomes a vector
+            Token::Punct("[") => {
+                let mut values = Vec::new();
+                while !self.eat("]") {
+                    if !values.is_empty() {
+                        self.expect(",")?;
+                        if self.eat("]") {
+                            break;
+                        }
+                    }
+                    values.push(self.assignment()?);
+                }
+
+                // Nested arrays of as many vectors as they have components are the columns of
+                // a matrix
+                let columns = values.len();
+                let nested = values.iter().all(|value| match value {
+                    Expr::Call(Callee::Construct(Type::Vec(size)), _) => *size == columns,
+                    _ => false,
+                });
+                if nested && (2..=4).contains(&columns) {
+                    return Ok(Expr::Call(Callee::Construct(Type::Mat(columns)), values));
+                }
+
+                match values.len() {
+                    1 => Ok(Expr::Call(Callee::Construct(Type::Float), values)),
+                    2..=4 => Ok(Expr::Call(
+                        Callee::Construct(Type::Vec(values.len())),
+                        values,
+                    )),
+                    9 => Ok(Expr::Call(Callee::Construct(Type::Mat(3)), values)),
+                    16 => Ok(Expr::Call(Callee::Construct(Type::Mat(4)), values)),
+                    count => self.error(&format!("Unable to use {} values as a vector", count)),
+                }
+            }
+            Token::Ident(name) => self.name(name),
+            Token::Str(_) => self.error("Unexpected string"),
+            Token::Punct(punct) => self.error(&format!("Unexpected `{}`", punct)),
+        }
+    }
+
+    fn name(&mut self, name: String) -> Result<Expr, String> {
+        match name.as_str() {
+            "true" => return Ok(Expr::Literal(Value::Bool(true))),
+            "false" => return Ok(Expr::Literal(Value::Bool(false))),
+            _ => (),
+        }
+
+        if !self.eat("(") {
+            return match self.lookup(&name) {
+                Some(slot) => Ok(Expr::Variable(slot)),
+                None => self.error(&format!("Unknown variable `{}`", name)),
+            };
+        }
+
+        if name.starts_with("texture") {
+            return self.error("Textures are not supported by the CPU renderer");
+        }
+        let arguments = self.arguments()?;
+        if let Some(ty) = Type::by_name(&name) {
+            return Ok(Expr::Call(Callee::Construct(ty), arguments));
+        }
+        let function = self.shader.functions.iter().position(|function| {
+            function.name == name && function.parameters.len() == arguments.len()
+        });
+        if let Some(function) = function {
+            return Ok(Expr::Call(Callee::Function(function), arguments));
+        }
+        if let Some(builtin) = Builtin::by_name(&name) {
+            return Ok(Expr::Call(Callee::Builtin(builtin), arguments));
+        }
+        self.error(&format!("Unknown function `{}`", name))
+    }
+}
+
+fn is_assignable(expression: &Expr) -> bool {
+    match expression {
+        Expr::Variable(_) => true,
+        Expr::Swizzle(inner, _) | Expr::Index(inner, _) => is_assignable(inner),
+        _ => false,
+    }
+}
@@ -0,0 +1,334 @@
+/*!
+Running parsed shaders one invocation at a time.
+ */
+
+use super::glsl::{Callee, Direction, Expr, Shader, Slot, Stmt};
+use super::value::{binary, negate, Binary, Type, Value};
+
+/// Loops running longer than this are assumed to never end
+const MAX_ITERATIONS: usize = 1 << 20;
+/// GLSL has no recursion, this only stops runaway shaders from overflowing the stack
+const MAX_DEPTH: usize = 64;
+
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Value),
+    Discard,
+}
+
+/// The globals of one invocation of a shader
+pub struct Machine<'a> {
+    shader: &'a Shader,
+    pub globals: Vec<Value>,
+    depth: usize,
+    discarded: bool,
+}
+
+impl<'a> Machine<'a> {
+    pub fn new(shader: &'a Shader) -> Self {
+        Machine {
+            shader,
+            globals: shader
+                .globals
+                .iter()
+                .map(|global| Value::zero(global.ty))
+                .collect(),
+            depth: 0,
+            discarded: false,
+        }
+    }
+
+    /// Start from globals prepared for every invocation, e.g. with the uniforms set
+    pub fn with_globals(shader: &'a Shader, globals: Vec<Value>) -> Self {
+        Machine {
+            shader,
+            globals,
+            depth: 0,
+            discarded: false,
+        }
+    }
+
+    /// Set a global, converting e.g. an `int` to the `float` it is declared as
+    pub fn set(&mut self, global: usize, value: Value) -> Result<(), String> {
+        let ty = self.shader.globals[global].ty;
+        self.globals[global] = convert(value, ty)?;
+        Ok(())
+    }
+
+    /// Run the initializers of globals, in the order they are declared
+    pub fn initialize(&mut self) -> Result<(), String> {
+        let shader = self.shader;
+        for (index, global) in shader.globals.iter().enumerate() {
+            if let Some(init) = &global.init {
+                let value = self.evaluate(init, &mut Vec::new())?;
+                self.set(index, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `main`, returning whether the invocation was kept rather than discarded
+    pub fn run_main(&mut self) -> Result<bool, String> {
+        let main = self
+            .shader
+            .find("main")
+            .ok_or_else(|| String::from("The shader has no main function!"))?;
+        self.call(main, &[])?;
+        Ok(!self.discarded)
+    }
+
+    pub fn evaluate(&mut self, expr: &Expr, locals: &mut Vec<Value>) -> Result<Value, String> {
+        match expr {
+            Expr::Literal(value) => Ok(*value),
+            Expr::Variable(Slot::Global(index)) => Ok(self.globals[*index]),
+            Expr::Variable(Slot::Local(index)) => Ok(locals[*index]),
+            Expr::Negate(inner) => negate(self.evaluate(inner, locals)?),
+            Expr::Not(inner) => Ok(Value::Bool(!self.evaluate(inner, locals)?.as_bool()?)),
+            Expr::Binary(Binary::And, left, right) => Ok(Value::Bool(
+                self.evaluate(left, locals)?.as_bool()?
+                    && self.evaluate(right, locals)?.as_bool()?,
+            )),
+            Expr::Binary(Binary::Or, left, right) => Ok(Value::Bool(
+                self.evaluate(left, locals)?.as_bool()?
+                    || self.evaluate(right, locals)?.as_bool()?,
+            )),
+            Expr::Binary(operator, left, right) => {
+                let left = self.evaluate(left, locals)?;
+                let right = self.evaluate(right, locals)?;
+                binary(*operator, left, right)
+            }
+            Expr::Assign(operator, target, value) => {
+                let mut value = self.evaluate(value, locals)?;
+                if let Some(operator) = operator {
+                    value = binary(*operator, self.evaluate(target, locals)?, value)?;
+                }
+                self.assign(target, value, locals)?;
+                self.evaluate(target, locals)
+            }
+            Expr::Step(delta, prefix, target) => {
+                let before = self.evaluate(target, locals)?;
+                let delta = match before {
+                    Value::Int(_) => Value::Int(*delta as i32),
+                    _ => Value::Float(*delta),
+                };
+                self.assign(target, binary(Binary::Add, before, delta)?, locals)?;
+                if *prefix {
+                    self.evaluate(target, locals)
+                } else {
+                    Ok(before)
+                }
+            }
+            Expr::Select(condition, then, otherwise) => {
+                if self.evaluate(condition, locals)?.as_bool()? {
+                    self.evaluate(then, locals)
+                } else {
+                    self.evaluate(otherwise, locals)
+                }
+            }
+            Expr::Call(callee, arguments) => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    values.push(self.evaluate(argument, locals)?);
+                }
+                match callee {
+                    Callee::Builtin(builtin) => builtin.call(&values),
+                    Callee::Construct(ty) => Value::construct(*ty, &values),
+                    Callee::Function(function) => {
+                        let (result, parameters) = self.call(*function, &values)?;
+                        // Copy `out` and `inout` parameters back to the arguments
+                        let shader = self.shader;
+                        let directions = &shader.functions[*function].parameters;
+                        for ((direction, argument), value) in
+                            directions.iter().zip(arguments).zip(parameters)
+                        {
+                            if *direction != Direction::In {
+                                self.assign(argument, value, locals)?;
+                            }
+                        }
+                        Ok(result)
+                    }
+                }
+            }
+            Expr::Swizzle(inner, fields) => self.evaluate(inner, locals)?.swizzle(fields),
+            Expr::Index(inner, index) => {
+                let index = self.evaluate(index, locals)?.as_int()?;
+                self.evaluate(inner, locals)?.index(index)
+            }
+        }
+    }
+
+    fn assign(
+        &mut self,
+        target: &Expr,
+        value: Value,
+        locals: &mut Vec<Value>,
+    ) -> Result<(), String> {
+        match target {
+            Expr::Variable(Slot::Global(index)) => {
+                self.globals[*index] = convert(value, self.globals[*index].get_type())?;
+            }
+            Expr::Variable(Slot::Local(index)) => {
+                locals[*index] = convert(value, locals[*index].get_type())?;
+            }
+            Expr::Swizzle(inner, fields) => {
+                let updated = self.evaluate(inner, locals)?.with_swizzle(fields, value)?;
+                self.assign(inner, updated, locals)?;
+            }
+            Expr::Index(inner, index) => {
+                let index = self.evaluate(index, locals)?.as_int()?;
+                let updated = self.evaluate(inner, locals)?.with_index(index, value)?;
+                self.assign(inner, updated, locals)?;
+            }
+            _ => return Err(String::from("Unable to assign to this expression!")),
+        }
+        Ok(())
+    }
+
+    /// Call a function, returning its result along with its parameters as they ended up
+    fn call(
+        &mut self,
+        function: usize,
+        arguments: &[Value],
+    ) -> Result<(Value, Vec<Value>), String> {
+        let shader = self.shader;
+        let function = &shader.functions[function];
+        let body = function
+            .body
+            .as_ref()
+            .ok_or_else(|| format!("Function {} is declared but not defined!", function.name))?;
+        if self.depth == MAX_DEPTH {
+            return Err(format!("Function {} recursed too deeply!", function.name));
+        }
+
+        let mut locals: Vec<Value> = function.locals.iter().map(|ty| Value::zero(*ty)).collect();
+        for ((local, direction), argument) in
+            locals.iter_mut().zip(&function.parameters).zip(arguments)
+        {
+            if *direction != Direction::Out {
+                *local = convert(*argument, local.get_type())?;
+            }
+        }
+
+        self.depth += 1;
+        let flow = self.block(body, &mut locals);
+        self.depth -= 1;
+        let result = match flow? {
+            Flow::Return(value) => convert(value, function.result)?,
+            _ => Value::zero(function.result),
+        };
+
+        locals.truncate(function.parameters.len());
+        Ok((result, locals))
+    }
+
+    fn block(&mut self, statements: &[Stmt], locals: &mut Vec<Value>) -> Result<Flow, String> {
+        for statement in statements {
+            match self.execute(statement, locals)? {
+                Flow::Next => (),
+                flow => return Ok(flow),
+            }
+            if self.discarded {
+                return Ok(Flow::Discard);
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn execute(&mut self, statement: &Stmt, locals: &mut Vec<Value>) -> Result<Flow, String> {
+        match statement {
+            Stmt::Declare(declared) => {
+                for (local, init) in declared {
+                    let ty = locals[*local].get_type();
+                    locals[*local] = match init {
+                        Some(init) => convert(self.evaluate(init, locals)?, ty)?,
+                        None => Value::zero(ty),
+                    };
+                }
+                Ok(Flow::Next)
+            }
+            Stmt::Expr(expr) => {
+                self.evaluate(expr, locals)?;
+                Ok(Flow::Next)
+            }
+            Stmt::If(condition, then, otherwise) => {
+                if self.evaluate(condition, locals)?.as_bool()? {
+                    self.execute(then, locals)
+                } else if let Some(otherwise) = otherwise {
+                    self.execute(otherwise, locals)
+                } else {
+                    Ok(Flow::Next)
+                }
+            }
+            Stmt::For(init, condition, step, body) => {
+                self.block(init, locals)?;
+                for _ in 0..MAX_ITERATIONS {
+                    if let Some(condition) = condition {
+                        if !self.evaluate(condition, locals)?.as_bool()? {
+                            return Ok(Flow::Next);
+                        }
+                    }
+                    match self.execute(body, locals)? {
+                        Flow::Break => return Ok(Flow::Next),
+                        Flow::Next | Flow::Continue => (),
+                        flow => return Ok(flow),
+                    }
+                    if let Some(step) = step {
+                        self.evaluate(step, locals)?;
+                    }
+                }
+                Err(String::from("A loop ran too many times!"))
+            }
+            Stmt::While(condition, body) => {
+                for _ in 0..MAX_ITERATIONS {
+                    if !self.evaluate(condition, locals)?.as_bool()? {
+                        return Ok(Flow::Next);
+                    }
+                    match self.execute(body, locals)? {
+                        Flow::Break => return Ok(Flow::Next),
+                        Flow::Next | Flow::Continue => (),
+                        flow => return Ok(flow),
+                    }
+                }
+                Err(String::from("A loop ran too many times!"))
+            }
+            Stmt::DoWhile(body, condition) => {
+                for _ in 0..MAX_ITERATIONS {
+                    match self.execute(body, locals)? {
+                        Flow::Break => return Ok(Flow::Next),
+                        Flow::Next | Flow::Continue => (),
+                        flow => return Ok(flow),
+                    }
+                    if !self.evaluate(condition, locals)?.as_bool()? {
+                        return Ok(Flow::Next);
+                    }
+                }
+                Err(String::from("A loop ran too many times!"))
+            }
+            Stmt::Block(statements) => self.block(statements, locals),
+            Stmt::Return(value) => Ok(Flow::Return(match value {
+                Some(value) => self.evaluate(value, locals)?,
+                None => Value::Void,
+            })),
+            Stmt::Break => Ok(Flow::Break),
+            Stmt::Continue => Ok(Flow::Continue),
+            Stmt::Discard => {
+                self.discarded = true;
+                Ok(Flow::Discard)
+            }
+        }
+    }
+}
+
+/// Convert between types with as many components, which GLSL does for `int` to `float`
+pub fn convert(value: Value, ty: Type) -> Result<Value, String> {
+    let from = value.get_type();
+    if from == ty || ty == Type::Void {
+        Ok(value)
+    } else if from.components() == ty.components() {
+        Ok(Value::from_components(ty, &value.components()))
+    } else {
+        Err(format!("Unable to use a {} as a {}!", from, ty))
+    }
+}
@@ -0,0 +1,291 @@
+/*!
+Drawing scenes without a GPU or a browser, e.g. to review designs in continuous integration.
+
+The renderer runs the shaders of a scene on the CPU, following WebGL where it matters for the
+image: triangles are clipped and filled with the same rules, varyings are interpolated with
+perspective and nothing is depth tested or blended, just like a design drawn by `Rugl`.
+Textures, arrays and structs are not supported.
+
+```ignore
+let scene = Scene::open(Path::new("triangle.rugl"))?;
+let renderer = CpuRenderer::new(&scene, 512, 512)?;
+fs::write("triangle.png", renderer.render(0.0)?.to_png())?;
+```
+ */
+
+mod glsl;
+mod interpreter;
+mod raster;
+mod scene;
+mod token;
+mod value;
+
+pub use self::scene::Scene;
+
+use self::glsl::{Expr, Shader, Stage, Storage};
+use self::interpreter::Machine;
+use self::raster::{rasterize, Fragment, Vertex};
+use self::value::Value;
+use crate::capture::FrameSource;
+use crate::image::Image;
+
+/// Components of attributes the scene leaves out, as WebGL fills them in
+const ATTRIBUTE_DEFAULT: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+pub struct CpuRenderer {
+    vertex: Shader,
+    fragment: Shader,
+    /// Globals of the vertex shader which read each attribute
+    attributes: Vec<(usize, Vec<Vec<f32>>)>,
+    uniforms: Vec<(String, Expr)>,
+    /// Vertex shader outputs along with the fragment shader inputs they are interpolated into
+    varyings: Vec<(usize, usize)>,
+    position: usize,
+    vertex_id: usize,
+    frag_coord: usize,
+    front_facing: usize,
+    color: usize,
+    count: usize,
+    clear: [f64; 4],
+    width: u32,
+    height: u32,
+}
+
+impl CpuRenderer {
+    /// Parse the shaders of a scene and match up their inputs and outputs, to draw it at
+    /// `width` by `height` pixels
+    pub fn new(scene: &Scene, width: u32, height: u32) -> Result<Self, String> {
+        let bytes = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4));
+        if width == 0 || height == 0 || bytes.is_none() {
+            return Err(format!(
+                "Unable to render an image of {}x{} pixels!",
+                width, height
+            ));
+        }
+
+        let vertex = Shader::parse(&scene.vertex, Stage::Vertex)
+            .map_err(|err| format!("Vertex shader: {}", err))?;
+        let fragment = Shader::parse(&scene.fragment, Stage::Fragment)
+            .map_err(|err| format!("Fragment shader: {}", err))?;
+
+        // Attributes hold at most 4 components, wider matrices would lose their last columns
+        let matrix = vertex
+            .globals
+            .iter()
+            .find(|global| global.storage == Storage::Input && global.ty.components() > 4);
+        if let Some(global) = matrix {
+            return Err(format!(
+                "Attribute {} is declared as {}, which is not supported by the CPU renderer!",
+                global.name, global.ty
+            ));
+        }
+
+        let mut attributes = Vec::new();
+        for (name, data) in &scene.attributes {
+            let global = vertex
+                .globals
+                .iter()
+                .position(|global| global.name == *name && global.storage == Storage::Input)
+                .ok_or_else(|| format!("Attribute {} does not exist!", name))?;
+            let ty = vertex.globals[global].ty;
+            if let Some(vertex) = data.iter().find(|vertex| vertex.len() != ty.components()) {
+                return Err(format!(
+                    "Attribute {} is declared as {} but was given {} component(s)!",
+                    name,
+                    ty,
+                    vertex.len()
+                ));
+            }
+            if data.len() < scene.count {
+                return Err(format!(
+                    "Attribute {} has {} vertices but {} are drawn!",
+                    name,
+                    data.len(),
+                    scene.count
+                ));
+            }
+            attributes.push((global, data.clone()));
+        }
+
+        let mut varyings = Vec::new();
+        for (input, global) in fragment.globals.iter().enumerate() {
+            if global.storage != Storage::Input {
+                continue;
+            }
+            let output = vertex
+                .globals
+                .iter()
+                .position(|output| output.name == global.name && output.storage == Storage::Output)
+                .ok_or_else(|| {
+                    format!(
+                        "Varying {} is not written by the vertex shader!",
+                        global.name
+                    )
+                })?;
+            if vertex.globals[output].ty != global.ty {
+                return Err(format!(
+                    "Varying {} has different types in the shaders!",
+                    global.name
+                ));
+            }
+            varyings.push((output, input));
+        }
+
+        let builtin = |shader: &Shader, name: &str| {
+            shader
+                .find_global(name)
+                .ok_or_else(|| format!("{} does not exist!", name))
+        };
+        // Shaders write `gl_FragColor` or their own output
+        let color = fragment
+            .globals
+            .iter()
+            .position(|global| global.storage == Storage::Output && !global.name.starts_with("gl_"))
+            .map_or_else(|| builtin(&fragment, "gl_FragColor"), Ok)?;
+
+        Ok(CpuRenderer {
+            position: builtin(&vertex, "gl_Position")?,
+            vertex_id: builtin(&vertex, "gl_VertexID")?,
+            frag_coord: builtin(&fragment, "gl_FragCoord")?,
+            front_facing: builtin(&fragment, "gl_FrontFacing")?,
+            color,
+            vertex,
+            fragment,
+            attributes,
+            uniforms: scene.uniforms.clone(),
+            varyings,
+            count: scene.count,
+            clear: scene.clear,
+            width,
+            height,
+        })
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Draw the frame of the given tick
+    pub fn render(&self, tick: f64) -> Result<Image, String> {
+        let vertex_globals = self.prepare(&self.vertex, tick)?;
+        let fragment_globals = self.prepare(&self.fragment, tick)?;
+
+        let mut vertices = Vec::with_capacity(self.count);
+        for index in 0..self.count {
+            let mut machine = Machine::with_globals(&self.vertex, vertex_globals.clone());
+            machine.set(self.vertex_id, Value::Int(index as i32))?;
+            for (global, data) in &self.attributes {
+                let ty = self.vertex.globals[*global].ty;
+                let mut components = ATTRIBUTE_DEFAULT;
+                for (component, value) in components.iter_mut().zip(&data[index]) {
+                    *component = *value;
+                }
+                machine.globals[*global] =
+                    Value::from_components(ty, &components[..ty.components()]);
+            }
+            machine.run_main()?;
+
+            let mut position = [0.0; 4];
+            position.copy_from_slice(&machine.globals[self.position].components());
+            let mut varyings = Vec::new();
+            for (output, _) in &self.varyings {
+                varyings.extend_from_slice(&machine.globals[*output].components());
+            }
+            vertices.push(Vertex { position, varyings });
+        }
+
+        let clear: Vec<u8> = self
+            .clear
+            .iter()
+            .map(|value| to_byte(*value as f32))
+            .collect();
+        let mut pixels = clear.repeat(self.width as usize * self.height as usize);
+        for triangle in vertices.chunks_exact(3) {
+            let corners = [&triangle[0], &triangle[1], &triangle[2]];
+            rasterize(corners, self.width, self.height, |fragment| {
+                if let Some(color) = self.shade(&fragment_globals, &fragment)? {
+                    let (x, y) = (fragment.x as usize, fragment.y as usize);
+                    let start = (y * self.width as usize + x) * 4;
+                    pixels[start..start + 4].copy_from_slice(&color);
+                }
+                Ok(())
+            })?;
+        }
+
+        Image::from_bottom_up(self.width, self.height, pixels)
+    }
+
+    /// Globals shared by every invocation of a shader, with the uniforms set and the
+    /// initializers run
+    fn prepare(&self, shader: &Shader, tick: f64) -> Result<Vec<Value>, String> {
+        let mut machine = Machine::new(shader);
+        for (index, global) in shader.globals.iter().enumerate() {
+            if global.storage == Storage::Input {
+                machine.globals[index] = Value::from_components(global.ty, &ATTRIBUTE_DEFAULT);
+            }
+        }
+        for (name, expr) in &self.uniforms {
+            let global = shader
+                .globals
+                .iter()
+                .position(|global| global.name == *name && global.storage == Storage::Uniform);
+            if let Some(global) = global {
+                let value = Machine::new(&Shader::default())
+                    .evaluate(expr, &mut vec![Value::Float(tick as f32)])
+                    .map_err(|err| format!("Uniform {}: {}", name, err))?;
+                machine
+                    .set(global, value)
+                    .map_err(|err| format!("Uniform {}: {}", name, err))?;
+            }
+        }
+        machine.initialize()?;
+        Ok(machine.globals)
+    }
+
+    /// Run the fragment shader, returning the color unless the fragment is discarded
+    fn shade(&self, globals: &[Value], fragment: &Fragment) -> Result<Option<[u8; 4]>, String> {
+        let mut machine = Machine::with_globals(&self.fragment, globals.to_vec());
+        machine.globals[self.frag_coord] = Value::Vec(4, fragment.coord);
+        machine.globals[self.front_facing] = Value::Bool(fragment.front_facing);
+
+        let mut varyings = &fragment.varyings[..];
+        for (_, input) in &self.varyings {
+            let ty = self.fragment.globals[*input].ty;
+            let (values, rest) = varyings.split_at(ty.components());
+            machine.globals[*input] = Value::from_components(ty, values);
+            varyings = rest;
+        }
+
+        if !machine.run_main()? {
+            return Ok(None);
+        }
+        let mut color = [0; 4];
+        let components = machine.globals[self.color].components();
+        for (index, byte) in color.iter_mut().enumerate() {
+            *byte = to_byte(
+                components
+                    .get(index)
+                    .cloned()
+                    .unwrap_or(ATTRIBUTE_DEFAULT[index]),
+            );
+        }
+        Ok(Some(color))
+    }
+}
+
+impl FrameSource for CpuRenderer {
+    fn render_frame(&mut self, tick: f64) -> Result<Image, String> {
+        self.render(tick)
+    }
+}
+
+/// Convert a color component to a byte, as WebGL writes it into an 8 bit drawing buffer
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
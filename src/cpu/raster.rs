@@ -0,0 +1,191 @@
+/*!
+Turning triangles into fragments the way WebGL does: clipping, the viewport transform, a fill
+rule so shared edges are drawn once and perspective correct varyings.
+ */
+
+/// Clip space positions with w below this are clipped, so nothing divides by zero
+const MIN_W: f32 = 1e-5;
+
+/// A vertex as the vertex shader left it, varyings being flattened into floats
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 4],
+    pub varyings: Vec<f32>,
+}
+
+/// A pixel covered by a triangle, counting rows from the bottom like `gl_FragCoord`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    pub x: u32,
+    pub y: u32,
+    pub coord: [f32; 4],
+    pub front_facing: bool,
+    pub varyings: Vec<f32>,
+}
+
+/// A vertex in window coordinates, with its varyings divided by w
+struct Projected {
+    x: f32,
+    y: f32,
+    z: f32,
+    inverse_w: f32,
+    varyings: Vec<f32>,
+}
+
+/// Call `shade` for every pixel of the `width` by `height` viewport the triangle covers
+pub fn rasterize<F>(
+    triangle: [&Vertex; 3],
+    width: u32,
+    height: u32,
+    mut shade: F,
+) -> Result<(), String>
+where
+    F: FnMut(Fragment) -> Result<(), String>,
+{
+    let polygon = clip(triangle.iter().map(|vertex| (*vertex).clone()).collect());
+    let projected: Vec<Projected> = polygon
+        .into_iter()
+        .map(|vertex| {
+            let [x, y, z, w] = vertex.position;
+            let inverse_w = 1.0 / w;
+            Projected {
+                x: (x * inverse_w + 1.0) * 0.5 * width as f32,
+                y: (y * inverse_w + 1.0) * 0.5 * height as f32,
+                z: (z * inverse_w + 1.0) * 0.5,
+                inverse_w,
+                varyings: vertex
+                    .varyings
+                    .iter()
+                    .map(|value| value * inverse_w)
+                    .collect(),
+            }
+        })
+        .collect();
+
+    for index in 1..projected.len().saturating_sub(1) {
+        let corners = [&projected[0], &projected[index], &projected[index + 1]];
+        fill(corners, width, height, &mut shade)?;
+    }
+    Ok(())
+}
+
+/// Twice the signed area of `a`, `b` and `p`, positive when `p` is left of the edge from `a`
+fn edge(a: &Projected, b: &Projected, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+/// Whether pixels exactly on the edge from `a` to `b` belong to the triangle, true for the left
+/// and top edges of a counter-clockwise triangle
+fn owns_edge(a: &Projected, b: &Projected) -> bool {
+    b.y < a.y || (b.y == a.y && b.x < a.x)
+}
+
+fn fill<F>(corners: [&Projected; 3], width: u32, height: u32, shade: &mut F) -> Result<(), String>
+where
+    F: FnMut(Fragment) -> Result<(), String>,
+{
+    let [first, mut second, mut third] = corners;
+    let mut area = edge(first, second, third.x, third.y);
+    if area == 0.0 || !area.is_finite() {
+        return Ok(());
+    }
+    // Counter-clockwise triangles face the front, clockwise ones are flipped to share the fill
+    let front_facing = area > 0.0;
+    if !front_facing {
+        std::mem::swap(&mut second, &mut third);
+        area = -area;
+    }
+
+    let xs = [first.x, second.x, third.x];
+    let ys = [first.y, second.y, third.y];
+    let bound = |values: [f32; 3], limit: u32| {
+        let low = values.iter().cloned().fold(f32::INFINITY, f32::min);
+        let high = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let low = low.floor().max(0.0).min(limit as f32) as u32;
+        let high = high.ceil().max(0.0).min(limit as f32) as u32;
+        low..high
+    };
+
+    let edges = [(second, third), (third, first), (first, second)];
+    for y in bound(ys, height) {
+        for x in bound(xs, width) {
+            let (center_x, center_y) = (x as f32 + 0.5, y as f32 + 0.5);
+            let mut weights = [0.0; 3];
+            let mut inside = true;
+            for (weight, (a, b)) in weights.iter_mut().zip(&edges) {
+                *weight = edge(a, b, center_x, center_y);
+                inside &= *weight > 0.0 || (*weight == 0.0 && owns_edge(a, b));
+            }
+            if !inside {
+                continue;
+            }
+
+            let [a, b, c] = [weights[0] / area, weights[1] / area, weights[2] / area];
+            let inverse_w = a * first.inverse_w + b * second.inverse_w + c * third.inverse_w;
+            let varyings = first
+                .varyings
+                .iter()
+                .zip(&second.varyings)
+                .zip(&third.varyings)
+                .map(|((first, second), third)| (a * first + b * second + c * third) / inverse_w)
+                .collect();
+
+            shade(Fragment {
+                x,
+                y,
+                coord: [
+                    center_x,
+                    center_y,
+                    a * first.z + b * second.z + c * third.z,
+                    inverse_w,
+                ],
+                front_facing,
+                varyings,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Clip a polygon to the near and far planes, leaving the sides to the viewport
+fn clip(mut polygon: Vec<Vertex>) -> Vec<Vertex> {
+    let planes: [fn(&[f32; 4]) -> f32; 3] = [
+        |position| position[3] + position[2],
+        |position| position[3] - position[2],
+        |position| position[3] - MIN_W,
+    ];
+
+    for distance in planes.iter() {
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (index, current) in polygon.iter().enumerate() {
+            let next = &polygon[(index + 1) % polygon.len()];
+            let (from, to) = (distance(&current.position), distance(&next.position));
+            if from >= 0.0 {
+                clipped.push(current.clone());
+            }
+            if (from >= 0.0) != (to >= 0.0) {
+                clipped.push(lerp(current, next, from / (from - to)));
+            }
+        }
+        polygon = clipped;
+    }
+    polygon
+}
+
+fn lerp(from: &Vertex, to: &Vertex, amount: f32) -> Vertex {
+    let mix = |from: f32, to: f32| from + (to - from) * amount;
+    let mut position = [0.0; 4];
+    for (index, value) in position.iter_mut().enumerate() {
+        *value = mix(from.position[index], to.position[index]);
+    }
+
+    Vertex {
+        position,
+        varyings: from
+            .varyings
+            .iter()
+            .zip(&to.varyings)
+            .map(|(from, to)| mix(*from, *to))
+            .collect(),
+    }
+}
@@ -0,0 +1,274 @@
+/*!
+Scene files, which describe a design with the same fields as `rugl!`:
+
+```text
+rugl!(
+    vertex: { "..." },
+    fragment: { "..." },
+    attributes: { position: [[-0.7, -0.7], [0.7, -0.7], [0.0, 0.7]] },
+    uniforms: { angle: |tick: f64| { 0.01 * tick } },
+    clear: { [0.0, 0.0, 0.0, 1.0] },
+    count: { 3 }
+);
+```
+
+Uniforms are single expressions, using GLSL functions or the methods of Rust floats.
+ */
+
+use std::fs;
+use std::path::Path;
+
+use super::glsl::{Expr, Parser};
+use super::token::{tokenize, Token};
+use super::value::Type;
+
+/// Fields of `rugl!` which only change how a design is shown in the browser
+const IGNORED: [&str; 5] = ["canvas", "context", "resize", "timestep", "extensions"];
+
+/// Values of every vertex for each attribute
+pub type Attributes = Vec<(String, Vec<Vec<f32>>)>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scene {
+    pub vertex: String,
+    pub fragment: String,
+    pub attributes: Attributes,
+    /// Expressions of the uniforms, which can read the tick as their first local
+    pub(crate) uniforms: Vec<(String, Expr)>,
+    pub count: usize,
+    pub clear: [f64; 4],
+}
+
+impl Scene {
+    /// Read a scene file, `include_str!` being relative to the directory of the file
+    pub fn open(path: &Path) -> Result<Scene, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}!", path.display(), err))?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        Scene::parse_in(&source, directory).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn parse(source: &str) -> Result<Scene, String> {
+        Scene::parse_in(source, Path::new(""))
+    }
+
+    fn parse_in(source: &str, directory: &Path) -> Result<Scene, String> {
+        let mut parser = Parser::new(tokenize(source)?);
+        let mut scene = Scene::default();
+
+        // The fields can be wrapped in a macro call, to copy them from and to Rust as they are
+        let wrapped = parser.peek_at(1) == Some(&Token::Punct("!"));
+        if wrapped {
+            parser.ident()?;
+            parser.expect("!")?;
+            if !parser.eat("(") && !parser.eat("{") {
+                return parser.error("Expected `(` after the macro name");
+            }
+        }
+
+        while !parser.is_done() {
+            if wrapped && (parser.is_punct(")") || parser.is_punct("}")) {
+                parser.next()?;
+                parser.eat(";");
+                if !parser.is_done() {
+                    return parser.error("Unexpected tokens after the scene");
+                }
+                break;
+            }
+
+            let field = parser.ident()?;
+            parser.expect(":")?;
+            match field.as_str() {
+                "vertex" => scene.vertex = shader_source(&mut parser, directory)?,
+                "fragment" => scene.fragment = shader_source(&mut parser, directory)?,
+                "attributes" => scene.attributes = attributes(&mut parser)?,
+                "uniforms" => scene.uniforms = uniforms(&mut parser)?,
+                "count" => {
+                    let count = braced(&mut parser, number)?;
+                    if count < 0.0 || count.fract() != 0.0 {
+                        return parser.error("The count has to be a whole number");
+                    }
+                    scene.count = count as usize;
+                }
+                "clear" => {
+                    let color = braced(&mut parser, numbers)?;
+                    if color.len() != 4 {
+                        return parser.error("The clear color needs 4 values");
+                    }
+                    for (clear, value) in scene.clear.iter_mut().zip(color) {
+                        *clear = f64::from(value);
+                    }
+                }
+                "instances" | "feedback" | "discard" | "framebuffer" => {
+                    return parser
+                        .error(&format!("`{}` is not supported by the CPU renderer", field));
+                }
+                _ if IGNORED.contains(&field.as_str()) => skip_value(&mut parser)?,
+                _ => return parser.error(&format!("Unknown field `{}`", field)),
+            }
+
+            let end = parser.is_done() || parser.is_punct(")") || parser.is_punct("}");
+            if !parser.eat(",") && !end {
+                return parser.error("Expected `,` between fields");
+            }
+        }
+
+        if scene.vertex.is_empty() || scene.fragment.is_empty() {
+            return Err(String::from(
+                "A scene needs a vertex and a fragment shader!",
+            ));
+        }
+        Ok(scene)
+    }
+}
+
+/// A value optionally wrapped in braces, as `rugl!` writes them
+fn braced<T>(
+    parser: &mut Parser,
+    value: fn(&mut Parser) -> Result<T, String>,
+) -> Result<T, String> {
+    if parser.eat("{") {
+        let value = value(parser)?;
+        parser.expect("}")?;
+        Ok(value)
+    } else {
+        value(parser)
+    }
+}
+
+fn shader_source(parser: &mut Parser, directory: &Path) -> Result<String, String> {
+    let braces = parser.eat("{");
+    let source = match parser.next()? {
+        Token::Str(source) => source,
+        Token::Ident(ref name) if name == "include_str" => {
+            parser.expect("!")?;
+            parser.expect("(")?;
+            let path = match parser.next()? {
+                Token::Str(path) => directory.join(path),
+                _ => return parser.error("Expected the path of the shader"),
+            };
+            parser.expect(")")?;
+            fs::read_to_string(&path)
+                .map_err(|err| format!("Unable to read {}: {}!", path.display(), err))?
+        }
+        _ => return parser.error("Expected the source of the shader as a string"),
+    };
+    if braces {
+        parser.expect("}")?;
+    }
+    Ok(source)
+}
+
+fn number(parser: &mut Parser) -> Result<f32, String> {
+    let negative = parser.eat("-");
+    let value = match parser.next()? {
+        Token::Int(value) => value as f32,
+        Token::Float(value) => value as f32,
+        _ => return parser.error("Expected a number"),
+    };
+    Ok(if negative { -value } else { value })
+}
+
+/// A list of numbers such as `[0.0, 0.5, 1.0]`
+fn numbers(parser: &mut Parser) -> Result<Vec<f32>, String> {
+    parser.expect("[")?;
+    let mut values = Vec::new();
+    while !parser.eat("]") {
+        if !values.is_empty() {
+            parser.expect(",")?;
+            if parser.eat("]") {
+                break;
+            }
+        }
+        values.push(number(parser)?);
+    }
+    Ok(values)
+}
+
+fn attributes(parser: &mut Parser) -> Result<Attributes, String> {
+    parser.expect("{")?;
+    let mut attributes = Vec::new();
+    while !parser.eat("}") {
+        let name = parser.ident()?;
+        parser.expect(":")?;
+        if parser.is_punct("{") {
+            return parser.error("Instanced attributes are not supported by the CPU renderer");
+        }
+
+        parser.expect("[")?;
+        let mut vertices = Vec::new();
+        while !parser.eat("]") {
+            if !vertices.is_empty() {
+                parser.expect(",")?;
+                if parser.eat("]") {
+                    break;
+                }
+            }
+            // A single number per vertex can be written without brackets
+            if parser.is_punct("[") {
+                vertices.push(numbers(parser)?);
+            } else {
+                vertices.push(vec![number(parser)?]);
+            }
+        }
+        attributes.push((name, vertices));
+
+        if !parser.eat(",") && !parser.is_punct("}") {
+            return parser.error("Expected `,` between attributes");
+        }
+    }
+    Ok(attributes)
+}
+
+/// Uniforms given as a value or as a closure of the tick, e.g. `|tick: f64| 0.01 * tick`
+fn uniforms(parser: &mut Parser) -> Result<Vec<(String, Expr)>, String> {
+    parser.expect("{")?;
+    let mut uniforms = Vec::new();
+    while !parser.eat("}") {
+        let name = parser.ident()?;
+        parser.expect(":")?;
+
+        parser.push_scope();
+        if parser.is_ident("move") {
+            parser.next()?;
+        }
+        if parser.eat("|") {
+            let tick = parser.ident()?;
+            if parser.eat(":") {
+                parser.ident()?;
+            }
+            parser.expect("|")?;
+            parser.declare_local(&tick, Type::Float);
+        } else {
+            parser.eat("||");
+        }
+        let expression = parser.expression();
+        parser.pop_scope();
+        parser.take_locals();
+        uniforms.push((name, expression?));
+
+        if !parser.eat(",") && !parser.is_punct("}") {
+            return parser.error("Expected `,` between uniforms");
+        }
+    }
+    Ok(uniforms)
+}
+
+/// Skip the value of a field, which is a single token or a bracketed group
+fn skip_value(parser: &mut Parser) -> Result<(), String> {
+    match parser.next()? {
+        Token::Punct("{") | Token::Punct("(") | Token::Punct("[") => parser.skip_group(),
+        _ => {
+            // Paths such as `ResizeMode::Fixed(300, 150)`
+            while parser.is_punct(":") || parser.is_punct("(") {
+                if parser.eat("(") {
+                    parser.skip_group()?;
+                } else {
+                    parser.next()?;
+                    parser.next()?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
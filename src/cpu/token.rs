@@ -0,0 +1,250 @@
+/*!
+Tokens of GLSL shaders and of scene files, which share numbers, names and operators.
+ */
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Punct(&'static str),
+}
+
+/// Longest first, so `+=` is not read as `+` followed by `=`
+const PUNCTUATION: [&str; 40] = [
+    "++", "--", "+=", "-=", "*=", "/=", "%=", "==", "!=", "<=", ">=", "&&", "||", "^^", "<<", ">>",
+    "+", "-", "*", "/", "%", "<", ">", "=", "!", "?", ":", ";", ",", ".", "(", ")", "[", "]", "{",
+    "}", "&", "|", "^", "~",
+];
+
+/// Split `source` into tokens along with the line each starts on, skipping comments and
+/// preprocessor directives such as `#version 300 es`
+pub fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let mut line = 1;
+    // Whether only whitespace came before on this line, where directives can start
+    let mut line_start = true;
+
+    while index < chars.len() {
+        let current = chars[index];
+        let next = chars.get(index + 1).cloned().unwrap_or('\0');
+
+        if current == '\n' {
+            line += 1;
+            line_start = true;
+            index += 1;
+        } else if current.is_whitespace() {
+            index += 1;
+        } else if (current == '#' && line_start) || (current == '/' && next == '/') {
+            while index < chars.len() && chars[index] != '\n' {
+                index += 1;
+            }
+        } else if current == '/' && next == '*' {
+            let start = line;
+            index += 2;
+            loop {
+                match chars.get(index) {
+                    Some('*') if chars.get(index + 1) == Some(&'/') => break,
+                    Some('\n') => line += 1,
+                    Some(_) => (),
+                    None => return Err(format!("Unterminated comment on line {}!", start)),
+                }
+                index += 1;
+            }
+            index += 2;
+        } else {
+            line_start = false;
+            let start = line;
+            let token = if current.is_ascii_digit() || (current == '.' && next.is_ascii_digit()) {
+                number(&chars, &mut index, line)?
+            } else if current == '"' || (current == 'r' && (next == '"' || next == '#')) {
+                string(&chars, &mut index, &mut line)?
+            } else if current.is_alphabetic() || current == '_' {
+                let end = (index..chars.len())
+                    .find(|end| !(chars[*end].is_alphanumeric() || chars[*end] == '_'))
+                    .unwrap_or(chars.len());
+                let ident = chars[index..end].iter().collect();
+                index = end;
+                Token::Ident(ident)
+            } else {
+                let punct = PUNCTUATION
+                    .iter()
+                    .find(|punct| {
+                        punct
+                            .chars()
+                            .enumerate()
+                            .all(|(offset, char)| chars.get(index + offset) == Some(&char))
+                    })
+                    .ok_or_else(|| {
+                        format!("Unexpected character `{}` on line {}!", current, line)
+                    })?;
+                index += punct.len();
+                Token::Punct(punct)
+            };
+            tokens.push((token, start));
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn number(chars: &[char], index: &mut usize, line: usize) -> Result<Token, String> {
+    let start = *index;
+    let digits = |index: &mut usize| {
+        while *index < chars.len() && (chars[*index].is_ascii_digit() || chars[*index] == '_') {
+            *index += 1;
+        }
+    };
+
+    if chars[start] == '0'
+        && (chars.get(start + 1) == Some(&'x') || chars.get(start + 1) == Some(&'X'))
+    {
+        *index += 2;
+        while *index < chars.len() && chars[*index].is_ascii_hexdigit() {
+            *index += 1;
+        }
+        let text: String = chars[start + 2..*index].iter().collect();
+        let value = i64::from_str_radix(&text, 16)
+            .map_err(|_| format!("Invalid number on line {}!", line))?;
+        suffix(chars, index);
+        return Ok(Token::Int(value));
+    }
+
+    let mut float = false;
+    digits(index);
+    // A dot followed by a name is a method call on an integer instead
+    if chars.get(*index) == Some(&'.')
+        && !chars
+            .get(*index + 1)
+            .is_some_and(|next| next.is_alphabetic() || *next == '_' || *next == '.')
+    {
+        float = true;
+        *index += 1;
+        digits(index);
+    }
+    if let Some('e') | Some('E') = chars.get(*index) {
+        let sign = match chars.get(*index + 1) {
+            Some('+') | Some('-') => 1,
+            _ => 0,
+        };
+        if chars
+            .get(*index + 1 + sign)
+            .is_some_and(char::is_ascii_digit)
+        {
+            float = true;
+            *index += 1 + sign;
+            digits(index);
+        }
+    }
+
+    let text: String = chars[start..*index]
+        .iter()
+        .filter(|char| **char != '_')
+        .collect();
+    if let Some('f') | Some('F') = suffix(chars, index).chars().next() {
+        float = true;
+    }
+
+    if float {
+        text.parse()
+            .map(Token::Float)
+            .map_err(|_| format!("Invalid number on line {}!", line))
+    } else {
+        text.parse()
+            .map(Token::Int)
+            .map_err(|_| format!("Invalid number on line {}!", line))
+    }
+}
+
+/// Type suffixes such as `1.0f`, `2u` or `0.5_f32`, which only tell ints and floats apart here
+fn suffix(chars: &[char], index: &mut usize) -> String {
+    let start = *index;
+    while *index < chars.len() && (chars[*index].is_alphanumeric() || chars[*index] == '_') {
+        *index += 1;
+    }
+    chars[start..*index]
+        .iter()
+        .filter(|char| **char != '_')
+        .collect()
+}
+
+/// A string literal, `"..."` with escapes or raw `r#"..."#`
+fn string(chars: &[char], index: &mut usize, line: &mut usize) -> Result<Token, String> {
+    let start = *line;
+    let unterminated = || format!("Unterminated string on line {}!", start);
+
+    if chars[*index] == 'r' {
+        *index += 1;
+        let hashes = (*index..chars.len())
+            .take_while(|at| chars[*at] == '#')
+            .count();
+        *index += hashes;
+        if chars.get(*index) != Some(&'"') {
+            return Err(format!("Invalid raw string on line {}!", start));
+        }
+        *index += 1;
+
+        let mut text = String::new();
+        loop {
+            match chars.get(*index) {
+                Some('"')
+                    if (1..=hashes).all(|offset| chars.get(*index + offset) == Some(&'#')) =>
+                {
+                    *index += 1 + hashes;
+                    return Ok(Token::Str(text));
+                }
+                Some(char) => {
+                    if *char == '\n' {
+                        *line += 1;
+                    }
+                    text.push(*char);
+                    *index += 1;
+                }
+                None => return Err(unterminated()),
+            }
+        }
+    }
+
+    *index += 1;
+    let mut text = String::new();
+    loop {
+        match chars.get(*index) {
+            Some('"') => {
+                *index += 1;
+                return Ok(Token::Str(text));
+            }
+            Some('\\') => {
+                let escaped = chars.get(*index + 1).ok_or_else(unterminated)?;
+                *index += 2;
+                match escaped {
+                    'n' => text.push('\n'),
+                    't' => text.push('\t'),
+                    'r' => text.push('\r'),
+                    '0' => text.push('\0'),
+                    '\n' => {
+                        // A line continuation also skips the indentation of the next line
+                        *line += 1;
+                        while chars.get(*index).is_some_and(|char| char.is_whitespace()) {
+                            if chars[*index] == '\n' {
+                                *line += 1;
+                            }
+                            *index += 1;
+                        }
+                    }
+                    other => text.push(*other),
+                }
+            }
+            Some(char) => {
+                if *char == '\n' {
+                    *line += 1;
+                }
+                text.push(*char);
+                *index += 1;
+            }
+            None => return Err(unterminated()),
+        }
+    }
+}
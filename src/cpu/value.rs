@@ -0,0 +1,671 @@
+/*!
+Values of the GLSL subset the CPU renderer runs, with the arithmetic and built-in functions
+shaders use on them.
+ */
+
+use std::fmt;
+use std::ops::Deref;
+
+/// Types a value can be declared with, `ivec` and `bvec` are kept as `vec`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+    Void,
+    Bool,
+    Int,
+    Float,
+    Vec(usize),
+    Mat(usize),
+    Sampler,
+}
+
+impl Type {
+    pub fn by_name(name: &str) -> Option<Type> {
+        let ty = match name {
+            "void" => Type::Void,
+            "bool" => Type::Bool,
+            "int" | "uint" => Type::Int,
+            "float" => Type::Float,
+            "vec2" | "ivec2" | "uvec2" | "bvec2" => Type::Vec(2),
+            "vec3" | "ivec3" | "uvec3" | "bvec3" => Type::Vec(3),
+            "vec4" | "ivec4" | "uvec4" | "bvec4" => Type::Vec(4),
+            "mat2" | "mat2x2" => Type::Mat(2),
+            "mat3" | "mat3x3" => Type::Mat(3),
+            "mat4" | "mat4x4" => Type::Mat(4),
+            "sampler2D" | "samplerCube" | "sampler3D" | "sampler2DArray" | "isampler2D"
+            | "usampler2D" => Type::Sampler,
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    /// How many floats a value of this type holds
+    pub fn components(self) -> usize {
+        match self {
+            Type::Void | Type::Sampler => 0,
+            Type::Bool | Type::Int | Type::Float => 1,
+            Type::Vec(size) => size,
+            Type::Mat(size) => size * size,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Void => write!(formatter, "void"),
+            Type::Bool => write!(formatter, "bool"),
+            Type::Int => write!(formatter, "int"),
+            Type::Float => write!(formatter, "float"),
+            Type::Vec(size) => write!(formatter, "vec{}", size),
+            Type::Mat(size) => write!(formatter, "mat{}", size),
+            Type::Sampler => write!(formatter, "sampler"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Void,
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    Vec(usize, [f32; 4]),
+    /// Columns one after the other
+    Mat(usize, [f32; 16]),
+}
+
+impl Value {
+    /// The value a variable of the type holds before it is assigned
+    pub fn zero(ty: Type) -> Value {
+        match ty {
+            Type::Void | Type::Sampler => Value::Void,
+            Type::Bool => Value::Bool(false),
+            Type::Int => Value::Int(0),
+            Type::Float => Value::Float(0.0),
+            Type::Vec(size) => Value::Vec(size, [0.0; 4]),
+            Type::Mat(size) => Value::Mat(size, [0.0; 16]),
+        }
+    }
+
+    pub fn get_type(&self) -> Type {
+        match self {
+            Value::Void => Type::Void,
+            Value::Bool(_) => Type::Bool,
+            Value::Int(_) => Type::Int,
+            Value::Float(_) => Type::Float,
+            Value::Vec(size, _) => Type::Vec(*size),
+            Value::Mat(size, _) => Type::Mat(*size),
+        }
+    }
+
+    /// Every component as a float, booleans being 0 or 1
+    pub fn components(&self) -> Components {
+        let mut components = Components {
+            values: [0.0; 16],
+            len: self.get_type().components(),
+        };
+        match self {
+            Value::Void => (),
+            Value::Bool(value) => components.values[0] = if *value { 1.0 } else { 0.0 },
+            Value::Int(value) => components.values[0] = *value as f32,
+            Value::Float(value) => components.values[0] = *value,
+            Value::Vec(size, values) => {
+                components.values[..*size].copy_from_slice(&values[..*size])
+            }
+            Value::Mat(_, values) => components.values = *values,
+        }
+        components
+    }
+
+    /// Build a value of the given type from its components
+    pub fn from_components(ty: Type, components: &[f32]) -> Value {
+        let get = |index: usize| components.get(index).cloned().unwrap_or(0.0);
+        match ty {
+            Type::Void | Type::Sampler => Value::Void,
+            Type::Bool => Value::Bool(get(0) != 0.0),
+            Type::Int => Value::Int(get(0) as i32),
+            Type::Float => Value::Float(get(0)),
+            Type::Vec(size) => {
+                let mut values = [0.0; 4];
+                for (index, value) in values.iter_mut().enumerate().take(size) {
+                    *value = get(index);
+                }
+                Value::Vec(size, values)
+            }
+            Type::Mat(size) => {
+                let mut values = [0.0; 16];
+                for (index, value) in values.iter_mut().enumerate().take(size * size) {
+                    *value = get(index);
+                }
+                Value::Mat(size, values)
+            }
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            Value::Bool(value) => Ok(*value),
+            other => Err(format!("Expected a bool but got {}!", other.get_type())),
+        }
+    }
+
+    pub fn as_float(&self) -> Result<f32, String> {
+        match self {
+            Value::Int(value) => Ok(*value as f32),
+            Value::Float(value) => Ok(*value),
+            other => Err(format!("Expected a number but got {}!", other.get_type())),
+        }
+    }
+
+    pub fn as_int(&self) -> Result<i32, String> {
+        match self {
+            Value::Int(value) => Ok(*value),
+            Value::Float(value) => Ok(*value as i32),
+            other => Err(format!("Expected a number but got {}!", other.get_type())),
+        }
+    }
+
+    /// Call a constructor such as `vec4(color, 1.0)` or `mat3(1.0)`
+    pub fn construct(ty: Type, arguments: &[Value]) -> Result<Value, String> {
+        // A single scalar fills a vector, or the diagonal of a matrix
+        if let [single] = arguments {
+            if single.get_type().components() == 1 {
+                let value = single.components()[0];
+                match ty {
+                    Type::Vec(size) => return Ok(Value::Vec(size, [value; 4])),
+                    Type::Mat(size) => {
+                        let mut values = [0.0; 16];
+                        for diagonal in 0..size {
+                            values[diagonal * size + diagonal] = value;
+                        }
+                        return Ok(Value::Mat(size, values));
+                    }
+                    _ => (),
+                }
+            }
+            // A matrix from a matrix of another size keeps the overlapping part
+            if let (Type::Mat(size), Value::Mat(from, values)) = (ty, single) {
+                let mut result = [0.0; 16];
+                for column in 0..size {
+                    for row in 0..size {
+                        result[column * size + row] = if column < *from && row < *from {
+                            values[column * from + row]
+                        } else if column == row {
+                            1.0
+                        } else {
+                            0.0
+                        };
+                    }
+                }
+                return Ok(Value::Mat(size, result));
+            }
+        }
+
+        let mut components = Vec::with_capacity(16);
+        for argument in arguments {
+            components.extend_from_slice(&argument.components());
+        }
+        if components.len() < ty.components() {
+            return Err(format!(
+                "Not enough values to construct a {}, got {}!",
+                ty,
+                components.len()
+            ));
+        }
+        Ok(Value::from_components(ty, &components))
+    }
+
+    /// Components picked by a swizzle such as `.xy` or `.bgr`
+    pub fn swizzle(&self, fields: &[usize]) -> Result<Value, String> {
+        let (size, values) = match self {
+            Value::Vec(size, values) => (*size, values),
+            other => return Err(format!("Unable to swizzle {}!", other.get_type())),
+        };
+        if let Some(field) = fields.iter().find(|field| **field >= size) {
+            return Err(format!(
+                "Component {} is out of range for a vec{}!",
+                field, size
+            ));
+        }
+
+        Ok(match fields {
+            [field] => Value::Float(values[*field]),
+            _ => {
+                let mut result = [0.0; 4];
+                for (index, field) in fields.iter().enumerate() {
+                    result[index] = values[*field];
+                }
+                Value::Vec(fields.len(), result)
+            }
+        })
+    }
+
+    /// Replace the components picked by a swizzle
+    pub fn with_swizzle(self, fields: &[usize], value: Value) -> Result<Value, String> {
+        let (size, mut values) = match self {
+            Value::Vec(size, values) => (size, values),
+            other => return Err(format!("Unable to swizzle {}!", other.get_type())),
+        };
+        let components = value.components();
+        if components.len() != fields.len() {
+            return Err(format!(
+                "Unable to assign {} to a swizzle!",
+                value.get_type()
+            ));
+        }
+        for (field, component) in fields.iter().zip(components.iter()) {
+            if *field >= size {
+                return Err(format!(
+                    "Component {} is out of range for a vec{}!",
+                    field, size
+                ));
+            }
+            values[*field] = *component;
+        }
+
+        Ok(Value::Vec(size, values))
+    }
+
+    /// A component of a vector or a column of a matrix
+    pub fn index(&self, index: i32) -> Result<Value, String> {
+        match self {
+            Value::Vec(size, values) if index >= 0 && (index as usize) < *size => {
+                Ok(Value::Float(values[index as usize]))
+            }
+            Value::Mat(size, values) if index >= 0 && (index as usize) < *size => {
+                let start = index as usize * size;
+                Ok(Value::from_components(
+                    Type::Vec(*size),
+                    &values[start..start + size],
+                ))
+            }
+            other => Err(format!(
+                "Index {} is out of range for {}!",
+                index,
+                other.get_type()
+            )),
+        }
+    }
+
+    pub fn with_index(self, index: i32, value: Value) -> Result<Value, String> {
+        let components = value.components();
+        match self {
+            Value::Vec(size, mut values) if index >= 0 && (index as usize) < size => {
+                values[index as usize] = value.as_float()?;
+                Ok(Value::Vec(size, values))
+            }
+            Value::Mat(size, mut values)
+                if index >= 0 && (index as usize) < size && components.len() == size =>
+            {
+                let start = index as usize * size;
+                values[start..start + size].copy_from_slice(&components);
+                Ok(Value::Mat(size, values))
+            }
+            other => Err(format!(
+                "Index {} is out of range for {}!",
+                index,
+                other.get_type()
+            )),
+        }
+    }
+}
+
+/// The components of a value, without allocating
+pub struct Components {
+    values: [f32; 16],
+    len: usize,
+}
+
+impl Deref for Components {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        &self.values[..self.len]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binary {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+    Xor,
+}
+
+/// Apply `operation` to every component, pairing scalars with every component of the other side
+fn zip(left: Value, right: Value, operation: impl Fn(f32, f32) -> f32) -> Result<Value, String> {
+    let ty = match (left.get_type(), right.get_type()) {
+        (left, right) if left == right => left,
+        (Type::Int, other) | (other, Type::Int) | (Type::Float, other) | (other, Type::Float)
+            if other.components() > 0 =>
+        {
+            match other {
+                Type::Int => Type::Float,
+                other => other,
+            }
+        }
+        (left, right) => return Err(format!("Unable to combine {} and {}!", left, right)),
+    };
+
+    let (left, right) = (left.components(), right.components());
+    let at = |values: &[f32], index: usize| values[if values.len() == 1 { 0 } else { index }];
+    let mut results = [0.0; 16];
+    for (index, result) in results.iter_mut().enumerate().take(ty.components()) {
+        *result = operation(at(&left, index), at(&right, index));
+    }
+    Ok(Value::from_components(ty, &results))
+}
+
+/// Apply `operation` to every component
+fn map(value: Value, operation: impl Fn(f32) -> f32) -> Result<Value, String> {
+    match value {
+        Value::Int(value) => Ok(Value::Float(operation(value as f32))),
+        Value::Float(_) | Value::Vec(..) | Value::Mat(..) => {
+            let mut results = value.components();
+            for result in results.values.iter_mut() {
+                *result = operation(*result);
+            }
+            Ok(Value::from_components(value.get_type(), &results))
+        }
+        other => Err(format!(
+            "Expected a number or vector but got {}!",
+            other.get_type()
+        )),
+    }
+}
+
+fn multiply_matrices(size: usize, left: &[f32; 16], right: &[f32; 16]) -> [f32; 16] {
+    let mut result = [0.0; 16];
+    for column in 0..size {
+        for row in 0..size {
+            result[column * size + row] = (0..size)
+                .map(|inner| left[inner * size + row] * right[column * size + inner])
+                .sum();
+        }
+    }
+    result
+}
+
+pub fn binary(operator: Binary, left: Value, right: Value) -> Result<Value, String> {
+    use self::Binary::*;
+
+    match (operator, left, right) {
+        (Equal, left, right) => return Ok(Value::Bool(left == right)),
+        (NotEqual, left, right) => return Ok(Value::Bool(left != right)),
+        (And, left, right) => return Ok(Value::Bool(left.as_bool()? && right.as_bool()?)),
+        (Or, left, right) => return Ok(Value::Bool(left.as_bool()? || right.as_bool()?)),
+        (Xor, left, right) => return Ok(Value::Bool(left.as_bool()? != right.as_bool()?)),
+        (Less, left, right) => return Ok(Value::Bool(left.as_float()? < right.as_float()?)),
+        (Greater, left, right) => return Ok(Value::Bool(left.as_float()? > right.as_float()?)),
+        (LessEqual, left, right) => return Ok(Value::Bool(left.as_float()? <= right.as_float()?)),
+        (GreaterEqual, left, right) => {
+            return Ok(Value::Bool(left.as_float()? >= right.as_float()?))
+        }
+        _ => (),
+    }
+
+    match (operator, left, right) {
+        (_, Value::Int(left), Value::Int(right)) => Ok(Value::Int(match operator {
+            Add => left.wrapping_add(right),
+            Subtract => left.wrapping_sub(right),
+            Multiply => left.wrapping_mul(right),
+            Divide => left.checked_div(right).unwrap_or(0),
+            _ => left.checked_rem(right).unwrap_or(0),
+        })),
+        (Multiply, Value::Mat(size, left), Value::Mat(other, right)) if size == other => {
+            Ok(Value::Mat(size, multiply_matrices(size, &left, &right)))
+        }
+        (Multiply, Value::Mat(size, matrix), Value::Vec(other, vector)) if size == other => {
+            let mut result = [0.0; 4];
+            for (row, value) in result.iter_mut().enumerate().take(size) {
+                *value = (0..size)
+                    .map(|column| matrix[column * size + row] * vector[column])
+                    .sum();
+            }
+            Ok(Value::Vec(size, result))
+        }
+        (Multiply, Value::Vec(size, vector), Value::Mat(other, matrix)) if size == other => {
+            let mut result = [0.0; 4];
+            for (column, value) in result.iter_mut().enumerate().take(size) {
+                *value = (0..size)
+                    .map(|row| matrix[column * size + row] * vector[row])
+                    .sum();
+            }
+            Ok(Value::Vec(size, result))
+        }
+        (Add, left, right) => zip(left, right, |left, right| left + right),
+        (Subtract, left, right) => zip(left, right, |left, right| left - right),
+        (Multiply, left, right) => zip(left, right, |left, right| left * right),
+        (Divide, left, right) => zip(left, right, |left, right| left / right),
+        (_, left, right) => zip(left, right, |left, right| left % right),
+    }
+}
+
+pub fn negate(value: Value) -> Result<Value, String> {
+    match value {
+        Value::Int(value) => Ok(Value::Int(value.wrapping_neg())),
+        other => map(other, |value| -value),
+    }
+}
+
+/// Functions built into GLSL which the CPU renderer knows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    Radians,
+    Degrees,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Pow,
+    Exp,
+    Log,
+    Exp2,
+    Log2,
+    Sqrt,
+    InverseSqrt,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Fract,
+    Round,
+    Trunc,
+    Mod,
+    Min,
+    Max,
+    Clamp,
+    Mix,
+    Step,
+    SmoothStep,
+    Length,
+    Distance,
+    Dot,
+    Cross,
+    Normalize,
+    Reflect,
+}
+
+impl Builtin {
+    /// Find a built-in function by its GLSL name, or by the name of the matching method on
+    /// Rust floats such as `powf` or `signum`
+    pub fn by_name(name: &str) -> Option<Builtin> {
+        use self::Builtin::*;
+
+        let builtin = match name {
+            "radians" | "to_radians" => Radians,
+            "degrees" | "to_degrees" => Degrees,
+            "sin" => Sin,
+            "cos" => Cos,
+            "tan" => Tan,
+            "asin" => Asin,
+            "acos" => Acos,
+            "atan" | "atan2" => Atan,
+            "pow" | "powf" | "powi" => Pow,
+            "exp" => Exp,
+            "log" | "ln" => Log,
+            "exp2" => Exp2,
+            "log2" => Log2,
+            "sqrt" => Sqrt,
+            "inversesqrt" => InverseSqrt,
+            "abs" => Abs,
+            "sign" | "signum" => Sign,
+            "floor" => Floor,
+            "ceil" => Ceil,
+            "fract" => Fract,
+            "round" => Round,
+            "trunc" => Trunc,
+            "mod" | "rem_euclid" => Mod,
+            "min" => Min,
+            "max" => Max,
+            "clamp" => Clamp,
+            "mix" => Mix,
+            "step" => Step,
+            "smoothstep" => SmoothStep,
+            "length" => Length,
+            "distance" => Distance,
+            "dot" => Dot,
+            "cross" => Cross,
+            "normalize" => Normalize,
+            "reflect" => Reflect,
+            _ => return None,
+        };
+        Some(builtin)
+    }
+
+    pub fn call(self, arguments: &[Value]) -> Result<Value, String> {
+        use self::Builtin::*;
+
+        let argument = |index: usize| {
+            arguments
+                .get(index)
+                .cloned()
+                .ok_or_else(|| format!("{:?} needs {} arguments!", self, index + 1))
+        };
+        let dot = |left: Value, right: Value| -> Result<f32, String> {
+            Ok(zip(left, right, |left, right| left * right)?
+                .components()
+                .iter()
+                .sum())
+        };
+
+        match self {
+            Radians => map(argument(0)?, f32::to_radians),
+            Degrees => map(argument(0)?, f32::to_degrees),
+            Sin => map(argument(0)?, f32::sin),
+            Cos => map(argument(0)?, f32::cos),
+            Tan => map(argument(0)?, f32::tan),
+            Asin => map(argument(0)?, f32::asin),
+            Acos => map(argument(0)?, f32::acos),
+            Atan if arguments.len() == 2 => zip(argument(0)?, argument(1)?, f32::atan2),
+            Atan => map(argument(0)?, f32::atan),
+            Pow => zip(argument(0)?, argument(1)?, f32::powf),
+            Exp => map(argument(0)?, f32::exp),
+            Log => map(argument(0)?, f32::ln),
+            Exp2 => map(argument(0)?, f32::exp2),
+            Log2 => map(argument(0)?, f32::log2),
+            Sqrt => map(argument(0)?, f32::sqrt),
+            InverseSqrt => map(argument(0)?, |value| 1.0 / value.sqrt()),
+            Abs => match argument(0)? {
+                Value::Int(value) => Ok(Value::Int(value.wrapping_abs())),
+                other => map(other, f32::abs),
+            },
+            Sign => map(argument(0)?, |value| {
+                if value > 0.0 {
+                    1.0
+                } else if value < 0.0 {
+                    -1.0
+                } else {
+                    0.0
+                }
+            }),
+            Floor => map(argument(0)?, f32::floor),
+            Ceil => map(argument(0)?, f32::ceil),
+            Fract => map(argument(0)?, |value| value - value.floor()),
+            Round => map(argument(0)?, f32::round),
+            Trunc => map(argument(0)?, f32::trunc),
+            Mod => zip(argument(0)?, argument(1)?, |left, right| {
+                left - right * (left / right).floor()
+            }),
+            Min => match (argument(0)?, argument(1)?) {
+                (Value::Int(left), Value::Int(right)) => Ok(Value::Int(left.min(right))),
+                (left, right) => zip(left, right, f32::min),
+            },
+            Max => match (argument(0)?, argument(1)?) {
+                (Value::Int(left), Value::Int(right)) => Ok(Value::Int(left.max(right))),
+                (left, right) => zip(left, right, f32::max),
+            },
+            Clamp => {
+                let low = zip(argument(0)?, argument(1)?, f32::max)?;
+                zip(low, argument(2)?, f32::min)
+            }
+            Mix => {
+                let (from, to, amount) = (argument(0)?, argument(1)?, argument(2)?);
+                let difference = zip(to, from, |to, from| to - from)?;
+                let scaled = zip(difference, amount, |difference, amount| difference * amount)?;
+                zip(from, scaled, |from, scaled| from + scaled)
+            }
+            Step => zip(argument(0)?, argument(1)?, |edge, value| {
+                if value < edge {
+                    0.0
+                } else {
+                    1.0
+                }
+            }),
+            SmoothStep => {
+                let (from, to, value) = (argument(0)?, argument(1)?, argument(2)?);
+                let range = zip(to, from, |to, from| to - from)?;
+                let offset = zip(value, from, |value, from| value - from)?;
+                zip(offset, range, |offset, range| {
+                    let amount = (offset / range).clamp(0.0, 1.0);
+                    amount * amount * (3.0 - 2.0 * amount)
+                })
+            }
+            Length => {
+                let value = argument(0)?;
+                Ok(Value::Float(dot(value, value)?.sqrt()))
+            }
+            Distance => {
+                let difference = zip(argument(0)?, argument(1)?, |left, right| left - right)?;
+                Ok(Value::Float(dot(difference, difference)?.sqrt()))
+            }
+            Dot => Ok(Value::Float(dot(argument(0)?, argument(1)?)?)),
+            Cross => match (argument(0)?, argument(1)?) {
+                (Value::Vec(3, left), Value::Vec(3, right)) => Ok(Value::Vec(
+                    3,
+                    [
+                        left[1] * right[2] - left[2] * right[1],
+                        left[2] * right[0] - left[0] * right[2],
+                        left[0] * right[1] - left[1] * right[0],
+                        0.0,
+                    ],
+                )),
+                _ => Err(String::from("Cross needs two vec3 arguments!")),
+            },
+            Normalize => {
+                let value = argument(0)?;
+                let length = dot(value, value)?.sqrt();
+                map(value, |component| component / length)
+            }
+            Reflect => {
+                let (incident, normal) = (argument(0)?, argument(1)?);
+                let scale = 2.0 * dot(normal, incident)?;
+                zip(incident, normal, |incident, normal| {
+                    incident - scale * normal
+                })
+            }
+        }
+    }
+}
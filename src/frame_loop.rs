@@ -0,0 +1,403 @@
+/*!
+The animation loop driving a design, built on `requestAnimationFrame`.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, IntersectionObserver, IntersectionObserverEntry};
+
+use crate::clock::Clock;
+
+/// Milliseconds a frame may arrive early and still count for the target rate, as animation
+/// frames drift around the display's refresh interval
+const FRAME_TOLERANCE: f64 = 1.0;
+
+pub(crate) fn window() -> web_sys::Window {
+    web_sys::window().expect("no global `window` exists")
+}
+
+pub(crate) fn request_animation_frame(f: &Closure<FnMut(f64)>) -> Result<i32, JsValue> {
+    window().request_animation_frame(f.as_ref().unchecked_ref())
+}
+
+type FrameCallback = Closure<FnMut(f64)>;
+type ErrorCallback = Box<FnMut(&str)>;
+
+/// When the loop draws a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Draw on every animation frame
+    Continuous,
+    /// Only draw on the animation frame following a call to `FrameLoop::invalidate`
+    OnDemand,
+}
+
+/// Listeners keeping track of whether the canvas can be seen
+struct Watchers {
+    visibility: Closure<FnMut()>,
+    // Only kept alive for the observer
+    _intersection: Closure<FnMut(Array)>,
+    observer: IntersectionObserver,
+}
+
+impl Watchers {
+    fn disconnect(&self) {
+        self.observer.disconnect();
+        if let Some(document) = window().document() {
+            let _ = document.remove_event_listener_with_callback(
+                "visibilitychange",
+                self.visibility.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+struct Shared {
+    frame: RefCell<Option<FrameCallback>>,
+    request: Cell<Option<i32>>,
+    mode: Cell<RenderMode>,
+    paused: Cell<bool>,
+    hidden: Cell<bool>,
+    offscreen: Cell<bool>,
+    context_lost: Cell<bool>,
+    cancelled: Cell<bool>,
+    /// Set while suspended, the next frame does not count the time spent suspended
+    gap: Cell<bool>,
+    last_time: Cell<Option<f64>>,
+    time_offset: Cell<f64>,
+    watchers: RefCell<Option<Watchers>>,
+    invalid: Cell<bool>,
+    target_fps: Cell<Option<f64>>,
+    last_frame: Cell<Option<f64>>,
+    error: RefCell<Option<String>>,
+    on_error: RefCell<Option<ErrorCallback>>,
+}
+
+impl Shared {
+    /// A loop without a callback, which `FrameLoop::new` sets before any frame is requested
+    fn new(mode: RenderMode) -> Self {
+        Shared {
+            frame: RefCell::new(None),
+            request: Cell::new(None),
+            mode: Cell::new(mode),
+            paused: Cell::new(false),
+            hidden: Cell::new(false),
+            offscreen: Cell::new(false),
+            context_lost: Cell::new(false),
+            cancelled: Cell::new(false),
+            gap: Cell::new(false),
+            last_time: Cell::new(None),
+            time_offset: Cell::new(0.0),
+            watchers: RefCell::new(None),
+            invalid: Cell::new(false),
+            target_fps: Cell::new(None),
+            last_frame: Cell::new(None),
+            error: RefCell::new(None),
+            on_error: RefCell::new(None),
+        }
+    }
+
+    fn is_suspended(&self) -> bool {
+        self.paused.get()
+            || self.hidden.get()
+            || self.offscreen.get()
+            || self.context_lost.get()
+            || self.cancelled.get()
+    }
+
+    /// Start or stop requesting frames after the suspended state changed
+    fn update(&self) {
+        if self.is_suspended() {
+            self.unschedule();
+            self.gap.set(true);
+        } else if self.mode.get() == RenderMode::Continuous || self.invalid.get() {
+            self.schedule();
+        }
+    }
+
+    fn schedule(&self) {
+        if self.request.get().is_some() || self.is_suspended() {
+            return;
+        }
+
+        if let Some(frame) = self.frame.borrow().as_ref() {
+            self.request.set(request_animation_frame(frame).ok());
+        }
+    }
+
+    fn unschedule(&self) {
+        if let Some(request) = self.request.take() {
+            let _ = window().cancel_animation_frame(request);
+        }
+    }
+
+    fn frame(&self, time: f64, step: &mut FnMut() -> Result<(), String>) {
+        self.request.set(None);
+
+        if self.is_suspended() {
+            return;
+        }
+
+        // Freeze the loop's time across suspensions so animations pick up where they left off
+        if self.gap.replace(false) {
+            if let Some(last) = self.last_time.get() {
+                self.time_offset.set(self.time_offset.get() + time - last);
+            }
+            self.last_frame.set(None);
+        }
+        self.last_time.set(Some(time));
+
+        // Skip frames arriving sooner than the target rate allows
+        if let (Some(fps), Some(last)) = (self.target_fps.get(), self.last_frame.get()) {
+            if time - last < 1000.0 / fps - FRAME_TOLERANCE {
+                self.schedule();
+                return;
+            }
+        }
+
+        let draw = match self.mode.get() {
+            RenderMode::Continuous => true,
+            RenderMode::OnDemand => self.invalid.replace(false),
+        };
+
+        if draw {
+            // Keep to the target rate's schedule instead of the frame's time so early and late
+            // frames even out, starting over after falling more than a frame behind
+            let next = match (self.target_fps.get(), self.last_frame.get()) {
+                (Some(fps), Some(last)) if time - last < 2000.0 / fps => last + 1000.0 / fps,
+                _ => time,
+            };
+            self.last_frame.set(Some(next));
+
+            if let Err(err) = step() {
+                self.fail(err);
+                return;
+            }
+        }
+
+        if self.mode.get() == RenderMode::Continuous {
+            self.schedule();
+        }
+    }
+
+    fn fail(&self, err: String) {
+        log!("rugl stopped drawing: {}", err);
+        self.cancelled.set(true);
+
+        // Take the callback out while it runs, it may use the loop itself
+        let callback = self.on_error.borrow_mut().take();
+        if let Some(mut callback) = callback {
+            callback(&err);
+
+            // Unless it registered another callback while running
+            let mut on_error = self.on_error.borrow_mut();
+            if on_error.is_none() {
+                *on_error = Some(callback);
+            }
+        }
+
+        self.error.replace(Some(err));
+    }
+}
+
+/// A clock following the time of a frame loop, which stands still while it is suspended
+pub struct LoopClock {
+    shared: Weak<Shared>,
+}
+
+impl Clock for LoopClock {
+    fn now(&mut self) -> f64 {
+        self.shared.upgrade().map_or(0.0, |shared| {
+            (shared.last_time.get().unwrap_or(0.0) - shared.time_offset.get()) / 1000.0
+        })
+    }
+}
+
+/// Handle to an animation loop, cloning it gives another handle to the same loop
+#[derive(Clone)]
+pub struct FrameLoop {
+    shared: Rc<Shared>,
+}
+
+impl FrameLoop {
+    /// Create a loop calling `step` for every frame it draws, it starts on the first
+    /// call to `invalidate` or `resume`
+    pub fn new<F: FnMut() -> Result<(), String> + 'static>(mode: RenderMode, mut step: F) -> Self {
+        let shared = Rc::new(Shared::new(mode));
+
+        // Only a weak reference, so the closure goes away together with the last handle
+        let weak: Weak<Shared> = Rc::downgrade(&shared);
+        shared.frame.replace(Some(Closure::wrap(Box::new(move |time: f64| {
+            if let Some(shared) = weak.upgrade() {
+                shared.frame(time, &mut step);
+            }
+        }) as Box<FnMut(f64)>)));
+
+        FrameLoop { shared }
+    }
+
+    /// Stop drawing until `resume` is called
+    pub fn pause(&self) {
+        self.shared.paused.set(true);
+        self.shared.update();
+    }
+
+    pub fn resume(&self) {
+        self.shared.paused.set(false);
+        self.shared.update();
+    }
+
+    /// Stop drawing for good
+    pub fn cancel(&self) {
+        self.shared.cancelled.set(true);
+        self.shared.update();
+
+        if let Some(watchers) = self.shared.watchers.borrow_mut().take() {
+            watchers.disconnect();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.shared.paused.get()
+    }
+
+    /// Whether frames are being drawn, false while paused, cancelled or not visible
+    pub fn is_running(&self) -> bool {
+        !self.shared.is_suspended()
+    }
+
+    /// Suspend drawing while the WebGL context is lost
+    pub fn set_context_lost(&self, lost: bool) {
+        self.shared.context_lost.set(lost);
+        self.shared.update();
+    }
+
+    /// Time in milliseconds of the last frame, not counting the time spent suspended
+    pub fn time(&self) -> f64 {
+        self.shared.last_time.get().unwrap_or(0.0) - self.shared.time_offset.get()
+    }
+
+    /// A clock in seconds following `time`, which does not keep the loop alive
+    pub fn clock(&self) -> LoopClock {
+        LoopClock {
+            shared: Rc::downgrade(&self.shared),
+        }
+    }
+
+    /// Suspend drawing while the page is hidden or the canvas is scrolled out of view
+    pub fn watch_visibility(&self, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+        let document = window()
+            .document()
+            .ok_or_else(|| String::from("Unable to get the document!"))?;
+
+        let weak = Rc::downgrade(&self.shared);
+        let visibility = Closure::wrap(Box::new(move || {
+            if let Some(shared) = weak.upgrade() {
+                let hidden = window().document().is_some_and(|document| document.hidden());
+                shared.hidden.set(hidden);
+                shared.update();
+            }
+        }) as Box<FnMut()>);
+        document.add_event_listener_with_callback(
+            "visibilitychange",
+            visibility.as_ref().unchecked_ref(),
+        )?;
+
+        let weak = Rc::downgrade(&self.shared);
+        let intersection = Closure::wrap(Box::new(move |entries: Array| {
+            if let Some(shared) = weak.upgrade() {
+                // Entries are in order, the last one holds the current state
+                let entry = entries
+                    .iter()
+                    .filter_map(|entry| entry.dyn_into::<IntersectionObserverEntry>().ok())
+                    .next_back();
+                if let Some(entry) = entry {
+                    shared.offscreen.set(!entry.is_intersecting());
+                    shared.update();
+                }
+            }
+        }) as Box<FnMut(Array)>);
+        let observer = IntersectionObserver::new(intersection.as_ref().unchecked_ref())?;
+        observer.observe(canvas);
+
+        self.shared.hidden.set(document.hidden());
+        self.shared.update();
+
+        let previous = self.shared.watchers.replace(Some(Watchers {
+            visibility,
+            _intersection: intersection,
+            observer,
+        }));
+        if let Some(previous) = previous {
+            previous.disconnect();
+        }
+
+        Ok(())
+    }
+
+    /// Request a frame to be drawn, the only way frames are drawn in `RenderMode::OnDemand`
+    pub fn invalidate(&self) {
+        self.shared.invalid.set(true);
+        self.shared.schedule();
+    }
+
+    pub fn set_mode(&self, mode: RenderMode) {
+        self.shared.mode.set(mode);
+        self.shared.update();
+    }
+
+    pub fn get_mode(&self) -> RenderMode {
+        self.shared.mode.get()
+    }
+
+    /// Limit how many frames are drawn per second, `None` draws on every animation frame
+    pub fn set_target_fps(&self, fps: Option<f64>) {
+        self.shared.target_fps.set(fps.filter(|fps| *fps > 0.0));
+    }
+
+    /// The error that stopped the loop, if any
+    pub fn error(&self) -> Option<String> {
+        self.shared.error.borrow().clone()
+    }
+
+    /// Be notified when a frame fails and the loop stops
+    pub fn on_error<F: FnMut(&str) + 'static>(&self, callback: F) {
+        self.shared.on_error.replace(Some(Box::new(callback)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_frames_are_drawn_while_the_context_is_lost() {
+        let frame_loop = FrameLoop {
+            shared: Rc::new(Shared::new(RenderMode::Continuous)),
+        };
+        let frames = Cell::new(0);
+        let mut step = || {
+            frames.set(frames.get() + 1);
+            Ok(())
+        };
+
+        frame_loop.shared.frame(0.0, &mut step);
+        assert_eq!(frames.get(), 1);
+
+        frame_loop.set_context_lost(true);
+        assert!(!frame_loop.is_running());
+        frame_loop.shared.frame(16.0, &mut step);
+        assert_eq!(frames.get(), 1);
+
+        // Drawing picks up where it left off, without counting the time the context was lost
+        frame_loop.set_context_lost(false);
+        assert!(frame_loop.is_running());
+        frame_loop.shared.frame(1000.0, &mut step);
+        assert_eq!(frames.get(), 2);
+        assert_eq!(frame_loop.time(), 0.0);
+    }
+}
@@ -0,0 +1,324 @@
+/*!
+Zlib streams compressed with fixed Huffman codes, enough for PNG without pulling in a
+compression crate.
+ */
+
+const WINDOW: usize = 1 << 15;
+const HASH_BITS: u32 = 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash are tried for a match
+const MAX_CHAIN: usize = 64;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Writes bits from the least significant one, as deflate and GIF pack them
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        BitWriter {
+            bytes: Vec::new(),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    pub fn write(&mut self, bits: u32, count: u32) {
+        self.buffer |= bits << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are packed from their most significant bit
+    fn write_code(&mut self, code: u32, count: u32) {
+        let reversed = code.reverse_bits() >> (32 - count);
+        self.write(reversed, count);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+fn write_literal(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE
+        .iter()
+        .rposition(|base| *base as usize <= length)
+        .unwrap_or(0);
+    write_literal(writer, 257 + code as u32);
+    writer.write(
+        (length - LENGTH_BASE[code] as usize) as u32,
+        u32::from(LENGTH_EXTRA[code]),
+    );
+
+    let code = DISTANCE_BASE
+        .iter()
+        .rposition(|base| *base as usize <= distance)
+        .unwrap_or(0);
+    writer.write_code(code as u32, 5);
+    writer.write(
+        (distance - DISTANCE_BASE[code] as usize) as u32,
+        u32::from(DISTANCE_EXTRA[code]),
+    );
+}
+
+fn hash(data: &[u8]) -> usize {
+    let value = u32::from(data[0]) << 16 | u32::from(data[1]) << 8 | u32::from(data[2]);
+    (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Earlier positions of every three byte sequence, newest first
+struct Chains {
+    head: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl Chains {
+    fn new() -> Self {
+        Chains {
+            head: vec![usize::MAX; 1 << HASH_BITS],
+            previous: vec![usize::MAX; WINDOW],
+        }
+    }
+
+    fn insert(&mut self, data: &[u8], position: usize) {
+        if position + MIN_MATCH <= data.len() {
+            let hash = hash(&data[position..]);
+            self.previous[position % WINDOW] = self.head[hash];
+            self.head[hash] = position;
+        }
+    }
+
+    /// The longest earlier match of the bytes at `position` as a length and distance
+    fn longest_match(&self, data: &[u8], position: usize) -> (usize, usize) {
+        let mut best = (0, 0);
+        if position + MIN_MATCH > data.len() {
+            return best;
+        }
+
+        let limit = (data.len() - position).min(MAX_MATCH);
+        let mut candidate = self.head[hash(&data[position..])];
+        for _ in 0..MAX_CHAIN {
+            // Positions are only overwritten by later ones, which ends the chain
+            if candidate >= position || position - candidate > WINDOW {
+                break;
+            }
+
+            let length = (0..limit)
+                .take_while(|offset| data[candidate + offset] == data[position + offset])
+                .count();
+            if length > best.0 {
+                best = (length, position - candidate);
+                if length == limit {
+                    break;
+                }
+            }
+            candidate = self.previous[candidate % WINDOW];
+        }
+
+        best
+    }
+}
+
+/// Compress `data` into a raw deflate stream of a single block
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    // Final block, fixed Huffman codes
+    writer.write(1, 1);
+    writer.write(1, 2);
+
+    let mut chains = Chains::new();
+    let mut position = 0;
+    while position < data.len() {
+        let (length, distance) = chains.longest_match(data, position);
+        if length >= MIN_MATCH {
+            write_match(&mut writer, length, distance);
+            for skipped in position..position + length {
+                chains.insert(data, skipped);
+            }
+            position += length;
+        } else {
+            write_literal(&mut writer, u32::from(data[position]));
+            chains.insert(data, position);
+            position += 1;
+        }
+    }
+
+    write_literal(&mut writer, 256);
+    writer.finish()
+}
+
+/// Wrap a deflate stream of `data` in a zlib header and checksum
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    stream.extend(deflate(data));
+    stream.extend(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads bits from the least significant one, the counterpart of `BitWriter`
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        position: usize,
+    }
+
+    impl<'a> BitReader<'a> {
+        fn bit(&mut self) -> u32 {
+            let bit = self.bytes[self.position / 8] >> (self.position % 8) & 1;
+            self.position += 1;
+            u32::from(bit)
+        }
+
+        fn bits(&mut self, count: u8) -> usize {
+            (0..count).fold(0, |value, index| value | (self.bit() as usize) << index)
+        }
+
+        /// Huffman codes start from their most significant bit
+        fn code(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |code, _| code << 1 | self.bit())
+        }
+
+        fn literal(&mut self) -> u32 {
+            let code = self.code(7);
+            if code < 24 {
+                return 256 + code;
+            }
+            let code = code << 1 | self.bit();
+            match code {
+                0x30..=0xbf => code - 0x30,
+                0xc0..=0xc7 => 280 + code - 0xc0,
+                _ => 144 + (code << 1 | self.bit()) - 0x190,
+            }
+        }
+    }
+
+    /// Decompress a zlib stream holding a single block with fixed Huffman codes
+    fn inflate(stream: &[u8]) -> Vec<u8> {
+        assert_eq!(u16::from_be_bytes([stream[0], stream[1]]) % 31, 0);
+        let mut reader = BitReader {
+            bytes: &stream[2..stream.len() - 4],
+            position: 0,
+        };
+        assert_eq!((reader.bits(1), reader.bits(2)), (1, 1));
+
+        let mut data: Vec<u8> = Vec::new();
+        loop {
+            let symbol = reader.literal();
+            match symbol {
+                0..=255 => data.push(symbol as u8),
+                256 => break,
+                _ => {
+                    let code = (symbol - 257) as usize;
+                    let length = LENGTH_BASE[code] as usize + reader.bits(LENGTH_EXTRA[code]);
+                    let code = reader.code(5) as usize;
+                    let distance = DISTANCE_BASE[code] as usize + reader.bits(DISTANCE_EXTRA[code]);
+                    for _ in 0..length {
+                        data.push(data[data.len() - distance]);
+                    }
+                }
+            }
+        }
+
+        let checksum = &stream[stream.len() - 4..];
+        assert_eq!(checksum, &adler32(&data).to_be_bytes()[..]);
+        data
+    }
+
+    #[test]
+    fn adler32_matches_known_values() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn adler32_wraps_long_inputs() {
+        // Enough bytes for the sums to be reduced several times over
+        let data = vec![0xff; 100_000];
+        let (mut a, mut b) = (1u64, 0u64);
+        for byte in &data {
+            a = (a + u64::from(*byte)) % 65521;
+            b = (b + a) % 65521;
+        }
+        assert_eq!(adler32(&data), (b << 16 | a) as u32);
+    }
+
+    #[test]
+    fn zlib_streams_inflate_to_their_input() {
+        // Noise which barely compresses, and runs and repeats covering every match length
+        let mut seed = 1u32;
+        let noise: Vec<u8> = (0..70_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        let mut repeats = Vec::new();
+        for length in 1..300 {
+            repeats.extend(noise[..length].iter().rev());
+            repeats.extend(vec![length as u8; length]);
+        }
+
+        let inputs: Vec<&[u8]> = vec![b"", b"a", b"hello hello hello hello", &noise, &repeats];
+        for input in inputs {
+            assert_eq!(inflate(&zlib(input)), input);
+        }
+    }
+
+    #[test]
+    fn repeats_are_compressed() {
+        let data = b"rugl ".repeat(1000);
+        assert!(zlib(&data).len() < 100);
+    }
+}
@@ -0,0 +1,433 @@
+/*!
+Animated GIF encoding, reducing every frame to a palette of its own.
+ */
+
+use std::collections::HashMap;
+
+use super::deflate::BitWriter;
+use super::Image;
+
+const MAX_CODE: u16 = 4095;
+
+/// Encode `frames` of the same size as a GIF looping forever, showing each frame for `delay`
+/// hundredths of a second. Alpha is dropped, GIF only knows fully transparent pixels
+pub fn encode(frames: &[Image], delay: u16) -> Result<Vec<u8>, String> {
+    let (width, height) = match frames.first() {
+        Some(frame) => (frame.get_width(), frame.get_height()),
+        None => return Err(String::from("A GIF needs at least one frame!")),
+    };
+    if width > u32::from(u16::MAX) || height > u32::from(u16::MAX) {
+        return Err(format!(
+            "A {} by {} image is too large for a GIF!",
+            width, height
+        ));
+    }
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend(&(width as u16).to_le_bytes());
+    gif.extend(&(height as u16).to_le_bytes());
+    // No global color table, every frame brings its own
+    gif.extend(&[0, 0, 0]);
+
+    // Loop forever
+    gif.extend(&[0x21, 0xff, 0x0b]);
+    gif.extend(b"NETSCAPE2.0");
+    gif.extend(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for frame in frames {
+        if (frame.get_width(), frame.get_height()) != (width, height) {
+            return Err(String::from("Every frame of a GIF needs the same size!"));
+        }
+
+        let (palette, indices) = quantize(frame.get_pixels());
+        let bits = (1..=8).find(|bits| palette.len() <= 1 << bits).unwrap_or(8);
+
+        // Graphic control, leaving the frame in place until the next one is drawn over it
+        gif.extend(&[0x21, 0xf9, 0x04, 0x04]);
+        gif.extend(&delay.to_le_bytes());
+        gif.extend(&[0x00, 0x00]);
+
+        // Image descriptor with a local color table of 2^bits colors
+        gif.push(0x2c);
+        gif.extend(&[0, 0, 0, 0]);
+        gif.extend(&(width as u16).to_le_bytes());
+        gif.extend(&(height as u16).to_le_bytes());
+        gif.push(0x80 | (bits - 1) as u8);
+        for index in 0..1 << bits {
+            gif.extend(&palette.get(index).cloned().unwrap_or([0, 0, 0]));
+        }
+
+        let minimum = bits.max(2);
+        gif.push(minimum as u8);
+        for block in lzw(&indices, minimum).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend(block);
+        }
+        gif.push(0);
+    }
+
+    gif.push(0x3b);
+    Ok(gif)
+}
+
+/// Pick at most 256 colors for RGBA pixels, returning the palette and the index of every pixel
+fn quantize(pixels: &[u8]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    let mut exact = HashMap::new();
+    for pixel in pixels.chunks(4) {
+        let color = [pixel[0], pixel[1], pixel[2]];
+        let next = exact.len();
+        exact.entry(color).or_insert(next);
+        if exact.len() > 256 {
+            break;
+        }
+    }
+
+    if exact.len() <= 256 {
+        let mut palette = vec![[0; 3]; exact.len()];
+        for (color, index) in &exact {
+            palette[*index] = *color;
+        }
+        let indices = pixels
+            .chunks(4)
+            .map(|pixel| exact[&[pixel[0], pixel[1], pixel[2]]] as u8)
+            .collect();
+        return (palette, indices);
+    }
+
+    median_cut(pixels)
+}
+
+/// Colors reduced to 5 bits per channel, with how many pixels have them
+struct Bucket {
+    color: [u8; 3],
+    count: u32,
+    sum: [u64; 3],
+}
+
+fn bucket_of(pixel: &[u8]) -> usize {
+    (usize::from(pixel[0] >> 3) << 10)
+        | (usize::from(pixel[1] >> 3) << 5)
+        | usize::from(pixel[2] >> 3)
+}
+
+/// Split the colors of the image into 256 boxes along their widest channel, each box giving the
+/// average color of its pixels
+fn median_cut(pixels: &[u8]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    let mut histogram: Vec<Bucket> = (0..1 << 15)
+        .map(|bucket: usize| Bucket {
+            color: [
+                (bucket >> 10) as u8,
+                (bucket >> 5 & 31) as u8,
+                (bucket & 31) as u8,
+            ],
+            count: 0,
+            sum: [0; 3],
+        })
+        .collect();
+    for pixel in pixels.chunks(4) {
+        let bucket = &mut histogram[bucket_of(pixel)];
+        bucket.count += 1;
+        for (sum, value) in bucket.sum.iter_mut().zip(pixel) {
+            *sum += u64::from(*value);
+        }
+    }
+    histogram.retain(|bucket| bucket.count > 0);
+
+    let mut boxes: Vec<Vec<Bucket>> = vec![histogram];
+    while boxes.len() < 256 {
+        // The box spanning the widest range of any channel
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(index, colors)| {
+                let (channel, range) = (0..3)
+                    .map(|channel| {
+                        let values = colors.iter().map(|bucket| bucket.color[channel]);
+                        let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+                        (channel, range)
+                    })
+                    .max_by_key(|(_, range)| *range)
+                    .unwrap_or((0, 0));
+                (index, channel, range)
+            })
+            .max_by_key(|(_, _, range)| *range);
+        let (index, channel) = match widest {
+            Some((index, channel, _)) => (index, channel),
+            None => break,
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|bucket| bucket.color[channel]);
+        let total: u32 = colors.iter().map(|bucket| bucket.count).sum();
+        let mut seen = 0;
+        let median = colors
+            .iter()
+            .position(|bucket| {
+                seen += bucket.count;
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+        let upper = colors.split_off((median + 1).min(colors.len() - 1));
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut lookup = vec![0u8; 1 << 15];
+    let palette = boxes
+        .iter()
+        .enumerate()
+        .map(|(index, colors)| {
+            let count: u64 = colors.iter().map(|bucket| u64::from(bucket.count)).sum();
+            let mut color = [0; 3];
+            for (channel, value) in color.iter_mut().enumerate() {
+                let sum: u64 = colors.iter().map(|bucket| bucket.sum[channel]).sum();
+                *value = (sum / count.max(1)) as u8;
+            }
+            for bucket in colors {
+                let [red, green, blue] = bucket.color;
+                lookup[usize::from(red) << 10 | usize::from(green) << 5 | usize::from(blue)] =
+                    index as u8;
+            }
+            color
+        })
+        .collect();
+    let indices = pixels
+        .chunks(4)
+        .map(|pixel| lookup[bucket_of(pixel)])
+        .collect();
+
+    (palette, indices)
+}
+
+/// Compress palette indices with the variable width LZW codes of GIF
+fn lzw(indices: &[u8], minimum: u32) -> Vec<u8> {
+    let clear = 1u16 << minimum;
+    let end = clear + 1;
+
+    let mut writer = BitWriter::new();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut width = minimum + 1;
+    let mut last = end;
+    writer.write(u32::from(clear), width);
+
+    let (first, rest) = match indices.split_first() {
+        Some(split) => split,
+        None => {
+            writer.write(u32::from(end), width);
+            return writer.finish();
+        }
+    };
+    let mut current = u16::from(*first);
+    for index in rest {
+        if let Some(code) = table.get(&(current, *index)) {
+            current = *code;
+            continue;
+        }
+
+        writer.write(u32::from(current), width);
+        last += 1;
+        if last == 1 << width {
+            width += 1;
+        }
+        if last == MAX_CODE {
+            // The table is full, start over
+            writer.write(u32::from(clear), width);
+            table.clear();
+            width = minimum + 1;
+            last = end;
+        } else {
+            table.insert((current, *index), last);
+        }
+        current = u16::from(*index);
+    }
+
+    writer.write(u32::from(current), width);
+    if last + 1 == 1 << width {
+        width += 1;
+    }
+    writer.write(u32::from(end), width);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pseudo random bytes, the same on every run
+    fn noise(count: usize) -> Vec<u8> {
+        let mut seed = 7u32;
+        (0..count)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect()
+    }
+
+    /// Decode LZW codes as a GIF reader would, returning the indices and how many clear codes
+    /// and which code widths were read
+    fn unlzw(data: &[u8], minimum: u32) -> (Vec<u8>, usize, u32) {
+        let (clear, end) = (1usize << minimum, (1usize << minimum) + 1);
+        let mut position = 0;
+        let mut read = |width: u32| {
+            let code = (0..width).fold(0, |code, bit| {
+                let index = position + bit as usize;
+                code | usize::from(data[index / 8] >> (index % 8) & 1) << bit
+            });
+            position += width as usize;
+            code
+        };
+
+        let (mut indices, mut clears, mut widest) = (Vec::new(), 0, 0);
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut previous: Option<Vec<u8>> = None;
+        let mut width = minimum + 1;
+        loop {
+            widest = widest.max(width);
+            let code = read(width);
+            if code == clear {
+                table = (0..clear).map(|index| vec![index as u8]).collect();
+                table.extend(vec![Vec::new(), Vec::new()]);
+                width = minimum + 1;
+                previous = None;
+                clears += 1;
+                continue;
+            }
+            if code == end {
+                break;
+            }
+
+            let entry = match previous {
+                None => table[code].clone(),
+                Some(ref previous) if code < table.len() => {
+                    let entry = table[code].clone();
+                    table.push([&previous[..], &entry[..1]].concat());
+                    entry
+                }
+                Some(ref previous) => {
+                    assert_eq!(code, table.len(), "code {} is not in the table", code);
+                    let entry = [&previous[..], &previous[..1]].concat();
+                    table.push(entry.clone());
+                    entry
+                }
+            };
+            indices.extend(&entry);
+            previous = Some(entry);
+            if table.len() == 1 << width && width < 12 {
+                width += 1;
+            }
+        }
+
+        (indices, clears, widest)
+    }
+
+    /// The palette and indices of the only frame of a GIF
+    fn decode(gif: &[u8]) -> (Vec<[u8; 3]>, Vec<u8>) {
+        assert_eq!(&gif[..6], b"GIF89a");
+        let mut position = 13;
+        // Extensions come as sub-blocks until an empty one
+        while gif[position] == 0x21 {
+            position += 2;
+            while gif[position] != 0 {
+                position += usize::from(gif[position]) + 1;
+            }
+            position += 1;
+        }
+
+        assert_eq!(gif[position], 0x2c);
+        let colors = 1 << ((gif[position + 9] & 7) + 1);
+        position += 10;
+        let palette = gif[position..position + colors * 3]
+            .chunks(3)
+            .map(|color| [color[0], color[1], color[2]])
+            .collect();
+        position += colors * 3;
+
+        let minimum = u32::from(gif[position]);
+        position += 1;
+        let mut data = Vec::new();
+        while gif[position] != 0 {
+            let length = usize::from(gif[position]);
+            data.extend(&gif[position + 1..position + 1 + length]);
+            position += length + 1;
+        }
+        assert_eq!(gif[position + 1], 0x3b);
+
+        (palette, unlzw(&data, minimum).0)
+    }
+
+    #[test]
+    fn lzw_codes_grow_and_start_over() {
+        let indices = noise(20_000);
+        let (decoded, clears, widest) = unlzw(&lzw(&indices, 8), 8);
+        assert_eq!(decoded, indices);
+        assert!(clears > 1, "the table was never cleared");
+        assert_eq!(widest, 12);
+    }
+
+    #[test]
+    fn lzw_keeps_runs_and_small_palettes() {
+        for minimum in 2..=8 {
+            let mask = (1u16 << minimum) - 1;
+            let mut indices: Vec<u8> = noise(5000).iter().map(|i| *i & mask as u8).collect();
+            indices.extend(vec![1; 5000]);
+            assert_eq!(unlzw(&lzw(&indices, minimum), minimum).0, indices);
+        }
+        assert_eq!(unlzw(&lzw(&[], 2), 2).0, Vec::<u8>::new());
+        assert_eq!(unlzw(&lzw(&[3], 2), 2).0, vec![3]);
+    }
+
+    #[test]
+    fn frames_with_few_colors_are_exact() {
+        // 200 colors over more than 4096 pixels
+        let colors: Vec<[u8; 3]> = noise(600).chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+        let pixels: Vec<u8> = noise(80 * 80)
+            .iter()
+            .flat_map(|index| {
+                let [red, green, blue] = colors[usize::from(*index) % colors.len()];
+                vec![red, green, blue, 255]
+            })
+            .collect();
+        let frame = Image::new(80, 80, pixels.clone()).unwrap();
+
+        let (palette, indices) = decode(&encode(&[frame], 4).unwrap());
+        assert_eq!(indices.len(), 80 * 80);
+        for (pixel, index) in pixels.chunks(4).zip(indices) {
+            assert_eq!(&palette[usize::from(index)][..], &pixel[..3]);
+        }
+    }
+
+    #[test]
+    fn frames_with_many_colors_are_reduced() {
+        // A gradient of 4096 colors
+        let pixels: Vec<u8> = (0..64 * 64)
+            .flat_map(|index| vec![(index % 64 * 4) as u8, (index / 64 * 4) as u8, 128, 255])
+            .collect();
+        let (palette, indices) = quantize(&pixels);
+        assert_eq!(palette.len(), 256);
+        assert!(indices
+            .iter()
+            .all(|index| usize::from(*index) < palette.len()));
+
+        let mut error = 0;
+        for (pixel, index) in pixels.chunks(4).zip(&indices) {
+            let color = palette[usize::from(*index)];
+            for channel in 0..3 {
+                error += (i32::from(color[channel]) - i32::from(pixel[channel])).abs();
+            }
+        }
+        let average = error as f64 / (pixels.len() / 4 * 3) as f64;
+        assert!(average < 4.0, "average error {}", average);
+    }
+
+    #[test]
+    fn frames_need_the_same_size() {
+        let frames = [
+            Image::new(1, 1, vec![0; 4]).unwrap(),
+            Image::new(2, 1, vec![0; 8]).unwrap(),
+        ];
+        assert!(encode(&frames, 4).is_err());
+        assert!(encode(&[], 4).is_err());
+    }
+}
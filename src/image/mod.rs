@@ -0,0 +1,191 @@
+/*!
+Images read back from a context, and encoding them into files without leaving Rust.
+ */
+
+mod deflate;
+pub(crate) mod gif;
+mod png;
+pub(crate) mod zip;
+
+use js_sys::{Array, Reflect, Uint8Array};
+use wasm_bindgen::JsCast;
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+use crate::rugl::js_error;
+
+/// An 8 bit RGBA image, rows of four bytes per pixel from the top
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, String> {
+        let bytes = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4));
+        if bytes != Some(pixels.len()) {
+            return Err(format!(
+                "A {} by {} image needs {} bytes but {} were given!",
+                width,
+                height,
+                u128::from(width) * u128::from(height) * 4,
+                pixels.len()
+            ));
+        }
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Create an image from rows starting at the bottom, as WebGL reads them
+    pub fn from_bottom_up(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, String> {
+        let mut image = Image::new(width, height, pixels)?;
+        let stride = width as usize * 4;
+        let rows = height as usize;
+        for row in 0..rows / 2 {
+            let (top, bottom) = image.pixels.split_at_mut((rows - row - 1) * stride);
+            top[row * stride..(row + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+        }
+
+        Ok(image)
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self.width, self.height, &self.pixels)
+    }
+
+    /// A `data:image/png;base64,` URL, e.g. for the `src` of an `<img>`
+    pub fn to_data_url(&self) -> String {
+        data_url("image/png", &self.to_png())
+    }
+
+    /// Have the browser save the image as a PNG file with the given name
+    pub fn download(&self, name: &str) -> Result<(), String> {
+        download(name, "image/png", &self.to_png())
+    }
+}
+
+/// Encode `bytes` in a data URL of the given MIME type
+pub fn data_url(mime: &str, bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut url = format!("data:{};base64,", mime);
+    url.reserve(bytes.len() / 3 * 4 + 4);
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (index, byte)| {
+            value | u32::from(*byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                url.push(ALPHABET[(value >> (18 - 6 * index) & 63) as usize] as char);
+            } else {
+                url.push('=');
+            }
+        }
+    }
+
+    url
+}
+
+/// Have the browser save `bytes` as a file with the given name and MIME type
+pub fn download(name: &str, mime: &str, bytes: &[u8]) -> Result<(), String> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| String::from("Unable to get the document!"))?;
+
+    let parts = Array::of1(&Uint8Array::from(bytes));
+    let options = BlobPropertyBag::new();
+    Reflect::set(&options, &"type".into(), &mime.into()).map_err(js_error)?;
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options).map_err(js_error)?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+    let anchor = document
+        .create_element("a")
+        .map_err(js_error)?
+        .dyn_into::<HtmlAnchorElement>()
+        .map_err(|_| String::from("Unable to create a link!"))?;
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+
+    Url::revoke_object_url(&url).map_err(js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_urls_pad_to_whole_groups() {
+        assert_eq!(data_url("text/plain", b""), "data:text/plain;base64,");
+        assert_eq!(data_url("text/plain", b"f"), "data:text/plain;base64,Zg==");
+        assert_eq!(data_url("text/plain", b"fo"), "data:text/plain;base64,Zm8=");
+        assert_eq!(
+            data_url("text/plain", b"foo"),
+            "data:text/plain;base64,Zm9v"
+        );
+        assert_eq!(
+            data_url("text/plain", b"foob"),
+            "data:text/plain;base64,Zm9vYg=="
+        );
+        assert_eq!(
+            data_url("image/png", &[0xfb, 0xff]),
+            "data:image/png;base64,+/8="
+        );
+    }
+
+    /// A one pixel wide image whose rows are numbered from the bottom
+    fn numbered_rows(height: u32) -> Vec<u8> {
+        (0..height as u8).flat_map(|row| vec![row; 4]).collect()
+    }
+
+    #[test]
+    fn bottom_up_rows_are_flipped() {
+        for height in &[0, 1, 2, 3, 4, 5] {
+            let image = Image::from_bottom_up(1, *height, numbered_rows(*height)).unwrap();
+            let mut expected = numbered_rows(*height);
+            expected.reverse();
+            assert_eq!(image.get_pixels(), &expected[..], "height {}", height);
+        }
+    }
+
+    #[test]
+    fn bottom_up_rows_keep_their_pixels_in_order() {
+        let pixels: Vec<u8> = (0..24).collect();
+        let image = Image::from_bottom_up(2, 3, pixels).unwrap();
+        let expected: Vec<u8> = (16..24).chain(8..16).chain(0..8).collect();
+        assert_eq!(image.into_pixels(), expected);
+    }
+
+    #[test]
+    fn images_need_four_bytes_per_pixel() {
+        let err = Image::from_bottom_up(2, 2, vec![0; 15]).unwrap_err();
+        assert_eq!(err, "A 2 by 2 image needs 16 bytes but 15 were given!");
+
+        // Sizes beyond what fits in memory are an error instead of an overflow
+        let err = Image::new(u32::MAX, u32::MAX, Vec::new()).unwrap_err();
+        assert!(err.ends_with("bytes but 0 were given!"), "{}", err);
+    }
+}
@@ -0,0 +1,147 @@
+/*!
+PNG encoding of 8 bit RGBA images.
+ */
+
+use super::deflate::zlib;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Encode `pixels`, rows of four bytes per pixel from the top, as a PNG file
+pub fn encode(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend(&width.to_be_bytes());
+    header.extend(&height.to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, not interlaced
+    header.extend(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib(&filter(width, pixels)));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(&crc.to_be_bytes());
+}
+
+/// Prefix every row with the filter that leaves the smallest differences, which compress best
+fn filter(width: u32, pixels: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 4;
+    if stride == 0 {
+        return Vec::new();
+    }
+
+    let zero = vec![0; stride];
+    let mut filtered = Vec::with_capacity(pixels.len() + pixels.len() / stride);
+    let mut candidate = vec![0; stride];
+    let mut best = vec![0; stride];
+    let mut above: &[u8] = &zero;
+    for row in pixels.chunks(stride) {
+        let mut best_kind = 0;
+        let mut best_cost = u64::MAX;
+        for kind in 0..5 {
+            for index in 0..stride {
+                let left = if index >= 4 { row[index - 4] } else { 0 };
+                let up_left = if index >= 4 { above[index - 4] } else { 0 };
+                let predicted = match kind {
+                    0 => 0,
+                    1 => left,
+                    2 => above[index],
+                    3 => ((u16::from(left) + u16::from(above[index])) / 2) as u8,
+                    _ => paeth(left, above[index], up_left),
+                };
+                candidate[index] = row[index].wrapping_sub(predicted);
+            }
+
+            // Differences are scored as signed bytes
+            let cost = candidate
+                .iter()
+                .map(|byte| u64::from((*byte).min(byte.wrapping_neg())))
+                .sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_kind = kind;
+                best.copy_from_slice(&candidate);
+            }
+        }
+
+        filtered.push(best_kind);
+        filtered.extend(&best);
+        above = row;
+    }
+
+    filtered
+}
+
+fn paeth(left: u8, above: u8, up_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(above) - i16::from(up_left);
+    let distance_left = (estimate - i16::from(left)).abs();
+    let distance_above = (estimate - i16::from(above)).abs();
+    let distance_up_left = (estimate - i16::from(up_left)).abs();
+    if distance_left <= distance_above && distance_left <= distance_up_left {
+        left
+    } else if distance_above <= distance_up_left {
+        above
+    } else {
+        up_left
+    }
+}
+
+/// The CRC-32 used by PNG chunks and zip entries
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (index, entry) in table.iter_mut().enumerate() {
+        let mut value = index as u32;
+        for _ in 0..8 {
+            value = if value & 1 == 1 {
+                0xedb8_8320 ^ (value >> 1)
+            } else {
+                value >> 1
+            };
+        }
+        *entry = value;
+    }
+
+    !data.iter().fold(!0, |crc, byte| {
+        table[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn chunks_end_with_the_crc_of_kind_and_data() {
+        let mut png = Vec::new();
+        write_chunk(&mut png, b"IEND", &[]);
+        assert_eq!(
+            png,
+            [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    fn rows_are_prefixed_with_their_filter() {
+        // A gradient along the row is best predicted from the left
+        let pixels: Vec<u8> = (0..16).collect();
+        let filtered = filter(4, &pixels);
+        assert_eq!(filtered[0], 1);
+        assert_eq!(
+            &filtered[1..],
+            &[0, 1, 2, 3, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4][..]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+/*!
+Zip archives storing files as they are, for files which are already compressed such as PNG.
+ */
+
+use super::png::crc32;
+
+/// 1980-01-01 00:00, the earliest time a zip entry can have
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = 1 << 5 | 1;
+
+/// Store `files`, pairs of names and contents, in a zip archive
+pub fn encode<'a, Files: IntoIterator<Item = (String, &'a [u8])>>(files: Files) -> Vec<u8> {
+    let mut zip = Vec::new();
+    let mut directory = Vec::new();
+    let mut entries = 0u16;
+
+    for (name, data) in files {
+        let offset = zip.len() as u32;
+        let crc = crc32(data);
+
+        // Fields shared by the local header and the central directory
+        let mut fields = Vec::with_capacity(26);
+        fields.extend(&20u16.to_le_bytes());
+        fields.extend(&0u16.to_le_bytes());
+        fields.extend(&0u16.to_le_bytes());
+        fields.extend(&DOS_TIME.to_le_bytes());
+        fields.extend(&DOS_DATE.to_le_bytes());
+        fields.extend(&crc.to_le_bytes());
+        fields.extend(&(data.len() as u32).to_le_bytes());
+        fields.extend(&(data.len() as u32).to_le_bytes());
+        fields.extend(&(name.len() as u16).to_le_bytes());
+        fields.extend(&0u16.to_le_bytes());
+
+        zip.extend(&0x0403_4b50u32.to_le_bytes());
+        zip.extend(&fields);
+        zip.extend(name.as_bytes());
+        zip.extend(data);
+
+        directory.extend(&0x0201_4b50u32.to_le_bytes());
+        directory.extend(&20u16.to_le_bytes());
+        directory.extend(&fields);
+        // No comment, first disk, no attributes
+        directory.extend(&[0; 10]);
+        directory.extend(&offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+
+        entries += 1;
+    }
+
+    let start = zip.len() as u32;
+    zip.extend(&directory);
+    zip.extend(&0x0605_4b50u32.to_le_bytes());
+    zip.extend(&[0; 4]);
+    zip.extend(&entries.to_le_bytes());
+    zip.extend(&entries.to_le_bytes());
+    zip.extend(&(directory.len() as u32).to_le_bytes());
+    zip.extend(&start.to_le_bytes());
+    zip.extend(&[0; 2]);
+    zip
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], offset: usize) -> usize {
+        usize::from(u16::from_le_bytes([bytes[offset], bytes[offset + 1]]))
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> usize {
+        let mut value = [0; 4];
+        value.copy_from_slice(&bytes[offset..offset + 4]);
+        u32::from_le_bytes(value) as usize
+    }
+
+    /// Names and contents of the files in an archive, found through its central directory
+    pub(crate) fn files(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(zip, end), 0x0605_4b50);
+        let entries = u16_at(zip, end + 10);
+        assert_eq!(u16_at(zip, end + 8), entries);
+        let (size, start) = (u32_at(zip, end + 12), u32_at(zip, end + 16));
+        assert_eq!(start + size, end);
+
+        let mut files = Vec::new();
+        let mut entry = start;
+        for _ in 0..entries {
+            assert_eq!(u32_at(zip, entry), 0x0201_4b50);
+            let name_length = u16_at(zip, entry + 28);
+            let name = &zip[entry + 46..entry + 46 + name_length];
+            let (crc, length) = (u32_at(zip, entry + 16), u32_at(zip, entry + 24));
+
+            // The local header repeats the fields of the central directory
+            let local = u32_at(zip, entry + 42);
+            assert_eq!(u32_at(zip, local), 0x0403_4b50);
+            assert_eq!(&zip[local + 4..local + 30], &zip[entry + 6..entry + 32]);
+            assert_eq!(&zip[local + 30..local + 30 + name_length], name);
+
+            let data = &zip[local + 30 + name_length..local + 30 + name_length + length];
+            assert_eq!(crc32(data) as usize, crc);
+            files.push((String::from_utf8(name.to_vec()).unwrap(), data.to_vec()));
+            entry += 46 + name_length;
+        }
+        assert_eq!(entry, end);
+
+        files
+    }
+
+    #[test]
+    fn files_are_stored_with_a_central_directory() {
+        let first = b"first file".to_vec();
+        let second = vec![7; 300];
+        let zip = encode(vec![
+            (String::from("a.txt"), &first[..]),
+            (String::from("folder/b.bin"), &second[..]),
+        ]);
+
+        assert_eq!(
+            files(&zip),
+            vec![
+                (String::from("a.txt"), first),
+                (String::from("folder/b.bin"), second)
+            ]
+        );
+        // The second local header follows the first file
+        assert_eq!(u32_at(&zip, 30 + 5 + 10), 0x0403_4b50);
+    }
+
+    #[test]
+    fn empty_archives_only_hold_the_end_record() {
+        let zip = encode(Vec::new());
+        assert_eq!(zip.len(), 22);
+        assert!(files(&zip).is_empty());
+    }
+}
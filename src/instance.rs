@@ -0,0 +1,129 @@
+/*!
+A mounted design, owning its `Rugl` and the animation loop drawing it.
+ */
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Event;
+
+use crate::frame_loop::{FrameLoop, RenderMode};
+use crate::rugl::Rugl;
+
+/// Handle to a design drawn on the page, dropping it stops the design from being drawn
+pub struct Instance {
+    rugl: Rc<RefCell<Rugl<'static>>>,
+    frame_loop: FrameLoop,
+    context_lost: Option<Closure<FnMut(Event)>>,
+    context_restored: Option<Closure<FnMut(Event)>>,
+}
+
+impl Instance {
+    pub(crate) fn start(rugl: Rugl<'static>) -> Self {
+        // Static designs only need to be drawn again when something changes
+        let mode = if rugl.is_dynamic() {
+            RenderMode::Continuous
+        } else {
+            RenderMode::OnDemand
+        };
+
+        let rugl = Rc::new(RefCell::new(rugl));
+        let frame_rugl = rugl.clone();
+        let frame_loop = FrameLoop::new(mode, move || frame_rugl.borrow_mut().step());
+        rugl.borrow_mut().set_clock(frame_loop.clock());
+        if let Err(err) = frame_loop.watch_visibility(rugl.borrow().context.canvas()) {
+            log!("Drawing even while the canvas is hidden: {:?}", err);
+        }
+        frame_loop.invalidate();
+
+        // Cleared again on drop, the loop keeps the design and with it the listener alive
+        let resize_loop = frame_loop.clone();
+        rugl.borrow()
+            .context
+            .on_resize(Some(Box::new(move || resize_loop.invalidate())));
+
+        // Stop drawing while the context is lost and recreate everything once it is back
+        let lost_loop = frame_loop.clone();
+        let context_lost = Closure::wrap(Box::new(move |event: Event| {
+            // Without this the browser will never restore the context
+            event.prevent_default();
+            lost_loop.set_context_lost(true);
+        }) as Box<FnMut(Event)>);
+
+        let restored_loop = frame_loop.clone();
+        let restored_rugl = Rc::downgrade(&rugl);
+        let context_restored = Closure::wrap(Box::new(move |_: Event| {
+            if let Some(rugl) = restored_rugl.upgrade() {
+                if let Err(err) = rugl.borrow_mut().context.restore() {
+                    log!("Unable to restore the WebGL context: {}", err);
+                    restored_loop.cancel();
+                    return;
+                }
+            }
+            restored_loop.set_context_lost(false);
+            restored_loop.invalidate();
+        }) as Box<FnMut(Event)>);
+
+        let canvas = rugl.borrow().context.canvas().clone();
+        let context_lost = canvas
+            .add_event_listener_with_callback(
+                "webglcontextlost",
+                context_lost.as_ref().unchecked_ref(),
+            )
+            .ok()
+            .map(|_| context_lost);
+        let context_restored = canvas
+            .add_event_listener_with_callback(
+                "webglcontextrestored",
+                context_restored.as_ref().unchecked_ref(),
+            )
+            .ok()
+            .map(|_| context_restored);
+
+        Instance {
+            rugl,
+            frame_loop,
+            context_lost,
+            context_restored,
+        }
+    }
+
+    /// The design drawn by this instance
+    pub fn rugl(&self) -> &Rc<RefCell<Rugl<'static>>> {
+        &self.rugl
+    }
+
+    /// The loop drawing the design, to pause, resume or throttle it
+    pub fn frame_loop(&self) -> &FrameLoop {
+        &self.frame_loop
+    }
+
+    /// Keep drawing the design for the rest of the page's life
+    pub fn forget(self) {
+        std::mem::forget(self)
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        self.frame_loop.cancel();
+
+        self.rugl.borrow().context.on_resize(None);
+
+        let canvas = self.rugl.borrow().context.canvas().clone();
+        if let Some(context_lost) = self.context_lost.take() {
+            let _ = canvas.remove_event_listener_with_callback(
+                "webglcontextlost",
+                context_lost.as_ref().unchecked_ref(),
+            );
+        }
+        if let Some(context_restored) = self.context_restored.take() {
+            let _ = canvas.remove_event_listener_with_callback(
+                "webglcontextrestored",
+                context_restored.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
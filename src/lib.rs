@@ -1,6 +1,13 @@
-mod rugl;
 #[macro_use]
 mod macros;
+mod capture;
+mod clock;
+mod compute;
+mod cpu;
+mod frame_loop;
+mod image;
+mod instance;
+mod rugl;
 mod webgl;
 
 #[macro_export]
@@ -10,55 +17,26 @@ macro_rules! rugl_main {
 
         #[wasm_bindgen(start)]
         pub fn start() -> Result<(), JsValue> {
-            let mut rugl = rugl_inner!($($tt)*)?;
-
-            let dynamic = rugl.is_dynamic();
-            if dynamic {
-                // Define an empty function and clone it
-                let dynamic_fn_empty = Rc::new(RefCell::new(None));
-                let dynamic_fn = dynamic_fn_empty.clone();
-
-                // Overwrite dynamic function
-                *dynamic_fn.borrow_mut() = Some(Closure::wrap(Box::new( move || {
-                    let _ = rugl.step();
-                    request_animation_frame(
-                        dynamic_fn_empty
-                            .borrow()
-                            .as_ref()
-                            .unwrap()
-                    );
-                }) as Box<FnMut()>));
-
-                request_animation_frame(
-                    dynamic_fn
-                        .borrow()
-                        .as_ref()
-                        .unwrap()
-                );
-            } else {
-                let _ = rugl.step();
-            }
+            rugl_inner!($($tt)*)?.run().forget();
 
             Ok(())
         }
-
-        fn window() -> web_sys::Window {
-            web_sys::window().expect("no global `window` exists")
-        }
-
-        pub fn request_animation_frame(f: &Closure<FnMut()>) {
-            window()
-                .request_animation_frame(f.as_ref().unchecked_ref())
-                .expect("should register `requestAnimationFrame` OK");
-        }
     }
 }
 
 #[macro_use]
 pub mod prelude {
     pub use super::*;
+    pub use crate::capture::{FrameSequence, FrameSource};
+    pub use crate::clock::{Clock, ManualClock, PerformanceClock, Timestep};
+    pub use crate::compute::ComputePass;
+    pub use crate::cpu::{CpuRenderer, Scene};
+    pub use crate::frame_loop::{FrameLoop, LoopClock, RenderMode};
+    pub use crate::image::{data_url, download, Image};
+    pub use crate::instance::Instance;
     pub use crate::rugl::{Rugl, RuglInner};
     pub use crate::webgl::*;
+    pub use rugl_derive::Std140;
     pub use rugl_main as rugl;
     pub use std::rc::Rc;
     pub use std::cell::RefCell;
@@ -22,10 +22,7 @@ macro_rules! __impl_from_for_type_vec {
                     v.push(variant)
                 }
 
-                Self {
-                    name: items.0,
-                    data: v,
-                }
+                Self::new(items.0, v)
             }
         }
     };
@@ -52,3 +49,73 @@ macro_rules! __impl_from_repeat_vec {
         __impl_from_for_type_vec!($type, 16, $impl_type, $impl_subtype::$impl_subtype_variant);
     };
 }
+
+#[doc(hidden)]
+macro_rules! __impl_from_matrix {
+    ($type:ty, $len:expr, $variant:ident, $($flatten:tt)*) => {
+        impl From<$type> for UniformInner {
+            fn from(item: $type) -> Self {
+                let mut data = [0.0; $len];
+                copy_matrix(item.iter()$($flatten)*, &mut data);
+                UniformInner::$variant(data, false, None)
+            }
+        }
+
+        impl From<Rc<Fn(f64) -> $type>> for UniformInner {
+            fn from(func: Rc<Fn(f64) -> $type>) -> Self {
+                UniformInner::$variant(
+                    [0.0; $len],
+                    false,
+                    Some(Rc::new(move |tick| {
+                        let mut data = [0.0; $len];
+                        copy_matrix(func(tick).iter()$($flatten)*, &mut data);
+                        data
+                    })),
+                )
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+macro_rules! __impl_from_array {
+    ($type:ty, $variant:ident) => {
+        impl From<Vec<$type>> for UniformInner {
+            fn from(item: Vec<$type>) -> Self {
+                UniformInner::$variant(flatten_array(&item[..]), None)
+            }
+        }
+
+        impl From<Rc<Fn(f64) -> Vec<$type>>> for UniformInner {
+            fn from(func: Rc<Fn(f64) -> Vec<$type>>) -> Self {
+                UniformInner::$variant(
+                    Vec::new(),
+                    Some(Rc::new(move |tick| flatten_array(&func(tick)[..]))),
+                )
+            }
+        }
+    };
+    ($type:ty, $num:expr, $variant:ident) => {
+        impl From<[$type; $num]> for UniformInner {
+            fn from(item: [$type; $num]) -> Self {
+                UniformInner::$variant(flatten_array(&item[..]), None)
+            }
+        }
+
+        impl From<Rc<Fn(f64) -> [$type; $num]>> for UniformInner {
+            fn from(func: Rc<Fn(f64) -> [$type; $num]>) -> Self {
+                UniformInner::$variant(
+                    Vec::new(),
+                    Some(Rc::new(move |tick| flatten_array(&func(tick)[..]))),
+                )
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+macro_rules! __impl_from_repeat_array {
+    ($type:ty, $variant:ident, [$($num:expr),*]) => {
+        $( __impl_from_array!($type, $num, $variant); )*
+    };
+}
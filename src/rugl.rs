@@ -32,41 +32,335 @@ rugl_main! {
         color: [1, 0, 0, 1]
     },
 
-    count: 3
+    count: 3,
+
+    // Optional, the color each frame starts from, defaults to transparent black
+    clear: [0.0, 0.0, 0.0, 1.0],
+
+    // Optional, draw this many instances, attributes with a divisor advance per instance
+    // instead of per vertex, e.g. `offset: { divisor: 1, data: vec![[0.0, 0.5], [0.5, 0.0]] }`
+    instances: 2,
+
+    // Optional, needs WebGL 2, capture vertex shader outputs into attributes which the next
+    // frame reads, e.g. the `v_position` output becomes the next `position`
+    feedback: {
+        position: "v_position"
+    },
+
+    // Optional, only capture the outputs without rasterizing anything
+    discard: true,
+
+    // Optional, draw into a texture per fragment shader output instead of the canvas, the
+    // outputs being `gl_FragData[n]` with `WEBGL_draw_buffers` or `layout(location = n) out`
+    // variables in WebGL 2
+    framebuffer: {
+        width: 512,
+        height: 512,
+        color: vec![TextureFormat::Rgba, TextureFormat::Rgba]
+    },
+
+    // Optional, defaults to the element with the id `canvas`
+    canvas: "canvas",
+
+    // Optional, how the drawing buffer is sized, defaults to filling the window
+    resize: ResizeMode::Letterbox(16, 9),
+
+    // Optional, advance the tick 60 times a second instead of once per frame
+    timestep: Timestep::Fixed(60.0),
+
+    // Optional, extensions to enable, the design fails to start without the required ones
+    extensions: {
+        required: [Extension::AngleInstancedArrays],
+        optional: [Extension::OesVertexArrayObject]
+    },
+
+    // Optional WebGL context creation attributes, `version: Version::WebGl2` asks for WebGL 2
+    // falling back to WebGL 1, shaders starting with `#version 300 es` always ask for it
+    context: {
+        antialias: false,
+        power_preference: PowerPreference::HighPerformance
+    }
 }
 */
 
 use std::borrow::Cow;
+use std::marker::PhantomData;
 
-use crate::webgl::{Attribute, Uniform, WebGlContext};
+use wasm_bindgen::JsValue;
+
+use crate::clock::{Accumulator, Clock, PerformanceClock, Timestep};
+use crate::image::Image;
+use crate::instance::Instance;
+use crate::webgl::{
+    Attribute, AttributeHandle, CanvasTarget, ContextAttributes, Extension, ExtensionRequest,
+    FramebufferHandle, FramebufferOptions, GlslType, ResizeMode, Scalar, ShaderType,
+    TransformFeedbackHandle, Uniform, UniformHandle, Version, VertexArrayHandle, WebGlContext,
+};
 
 pub struct Rugl<'a> {
     pub inner: RuglInner<'a>,
     pub context: WebGlContext,
+    clock: Box<Clock>,
+    accumulator: Accumulator,
 }
 
-impl Rugl<'_> {
+impl<'a> Rugl<'a> {
+    /// Create the context for a description and set up its program, attributes and uniforms
+    pub fn new(mut inner: RuglInner<'a>) -> Result<Self, String> {
+        inner.timestep.validate()?;
+
+        // Shaders written for GLSL ES 3.00 ask for WebGL 2 on their own
+        let version = Version::of_sources(inner.get_vertex_shader(), inner.get_fragment_shader())?;
+        if version == Version::WebGl2 {
+            inner.context.version = Version::WebGl2;
+        }
+
+        let mut context =
+            WebGlContext::with_target(&inner.canvas, &inner.context).map_err(js_error)?;
+        if version == Version::WebGl2 && context.version() == Version::WebGl1 {
+            return Err(String::from(
+                "Shaders written for `#version 300 es` need WebGL 2, which is not supported!",
+            ));
+        }
+        context.set_resize_mode(inner.resize).map_err(js_error)?;
+        context.request_extensions(&inner.extensions)?;
+
+        let vertex = context.compile_shader(ShaderType::Vertex(
+            inner.get_vertex_shader(),
+            PhantomData,
+        ))?;
+
+        let fragment = context.compile_shader(ShaderType::Fragment(
+            inner.get_fragment_shader(),
+            PhantomData,
+        ))?;
+
+        // Captured outputs have to be known before linking
+        let varyings: Vec<&str> = inner
+            .feedback
+            .iter()
+            .map(|(_, varying)| varying.as_str())
+            .collect();
+        let program = if varyings.is_empty() {
+            context.link_and_add_program(&[vertex, fragment])?
+        } else {
+            if inner.instances > 0 {
+                return Err(String::from(
+                    "Transform feedback can not be used with instances!",
+                ));
+            }
+            context.link_and_add_program_with_varyings(&[vertex, fragment], &varyings)?
+        };
+        context.use_program(program)?;
+
+        // WebGL 1 needs an extension to draw instances, WebGL 2 has it built in
+        let instanced =
+            inner.instances > 0 || inner.attributes.iter().any(|attr| attr.get_divisor() > 0);
+        if instanced && !context.get_extensions().is_enabled(Extension::AngleInstancedArrays) {
+            context.enable_extension(Extension::AngleInstancedArrays)?;
+        }
+
+        if let Some(options) = &inner.framebuffer {
+            // WebGL 1 needs an extension to write several outputs, WebGL 2 has it built in
+            let draw_buffers = Extension::WebglDrawBuffers;
+            if options.color.len() > 1 && !context.get_extensions().is_enabled(draw_buffers) {
+                context.enable_extension(draw_buffers)?;
+            }
+
+            // Float formats need extensions to be drawn into
+            for format in &options.color {
+                context.enable_render_format(*format);
+            }
+
+            let framebuffer = context.create_framebuffer(options)?;
+            context.bind_framebuffer(Some(framebuffer))?;
+            inner.framebuffer_handle = Some(framebuffer);
+        }
+
+        let mut attribute_handles = Vec::new();
+        for attribute in inner.get_mut_attributes() {
+            let name = attribute.get_name().as_str();
+            let size = attribute.components();
+            let kind = context.get_attribute_info(name).map(|info| info.kind);
+            if let Some(kind) = kind {
+                if kind.components() != size as usize {
+                    return Err(format!(
+                        "Attribute: {} is declared as {} but was given {} component(s)!",
+                        name, kind, size
+                    ));
+                }
+            }
+            let scalar = kind.map(GlslType::scalar);
+
+            // Unpack Vec<Qualifer>, keeping integers for integer attributes
+            let handle = match scalar {
+                Some(Scalar::Int) | Some(Scalar::UnsignedInt) => {
+                    let mut attr_data = Vec::new();
+                    for layer in attribute.get_qualifiers() {
+                        attr_data.extend_from_slice(&layer.to_int_vec());
+                    }
+
+                    if scalar == Some(Scalar::UnsignedInt) {
+                        let attr_data: Vec<u32> =
+                            attr_data.into_iter().map(|value| value as u32).collect();
+                        context.create_buffer_with_data(name, &attr_data[..], size)?
+                    } else {
+                        context.create_buffer_with_data(name, &attr_data[..], size)?
+                    }
+                }
+                _ => {
+                    let mut attr_data = Vec::new();
+                    for layer in attribute.get_qualifiers() {
+                        attr_data.extend_from_slice(&layer.to_vec());
+                    }
+
+                    context.create_buffer_with_data(name, &attr_data[..], size)?
+                }
+            };
+            context.set_attribute_divisor(handle, attribute.get_divisor())?;
+            context.bind_buffer(handle)?;
+            context.enable_attribute(handle)?;
+            attribute_handles.push(handle);
+        }
+
+        let mut dynamic = false;
+        let mut uniform_handles = Vec::new();
+        for uniform in inner.get_mut_uniforms() {
+            if uniform.get_data().is_dynamic() {
+                dynamic = true;
+            }
+            let handle =
+                context.create_uniform(uniform.get_name().as_str(), uniform.get_data())?;
+            context.bind_uniform(handle)?;
+            uniform_handles.push(handle);
+        }
+
+        if !inner.feedback.is_empty() {
+            let mut captures = Vec::new();
+            for (name, _) in &inner.feedback {
+                let handle = context
+                    .get_attribute_handle(name.as_str())
+                    .ok_or_else(|| format!("Feedback attribute: {} does not exist!", name))?;
+                captures.push(handle);
+            }
+            inner.transform_feedback = Some(context.create_transform_feedback(&captures)?);
+        }
+
+        // Capture the attribute bindings once if the context can, otherwise they are enabled
+        // again on every frame
+        if !context.get_extensions().is_enabled(Extension::OesVertexArrayObject) {
+            let _ = context.enable_extension(Extension::OesVertexArrayObject);
+        }
+        let vertex_array = context.create_vertex_array(&attribute_handles)?;
+
+        inner.vertex_array = Some(vertex_array);
+        inner.attribute_handles = attribute_handles;
+        inner.uniform_handles = uniform_handles;
+        // Captured attributes change on every frame
+        inner.dynamic = dynamic || inner.transform_feedback.is_some();
+
+        Ok(Rugl {
+            inner,
+            context,
+            clock: Box::new(PerformanceClock),
+            accumulator: Accumulator::default(),
+        })
+    }
+
+    /// Replace the clock driving a fixed timestep, e.g. with a `ManualClock` to get the same
+    /// frames for the same sequence of times
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+        self.accumulator = Accumulator::default();
+    }
+
     pub fn step(&mut self) -> Result<(), String> {
+        self.context
+            .resize_if_needed()
+            .map_err(|err| format!("Unable to resize the canvas: {:?}", err))?;
         self.context.clear_with_color(self.inner.clear);
 
-        for attribute in self.inner.get_attributes() {
-            self.context.enable_attribute(attribute.get_name())?;
+        let alpha = match self.inner.timestep {
+            Timestep::PerFrame => 0.0,
+            Timestep::Fixed(rate) => {
+                let (ticks, alpha) = self.accumulator.advance(self.clock.now(), rate);
+                self.inner.tick += f64::from(ticks);
+                alpha
+            }
+        };
+        self.context.set_interpolation(alpha);
+        self.draw()?;
+
+        if self.inner.timestep == Timestep::PerFrame {
+            self.inner.tick += 1.0;
         }
 
-        let tick = self.inner.tick;
-        for uniform in self.inner.get_mut_uniforms() {
-            self.context.update_uniform(uniform.get_name(), tick)?;
+        Ok(())
+    }
+
+    /// Draw the frame of the given tick without advancing the clock, e.g. to capture frames
+    pub fn draw_tick(&mut self, tick: f64) -> Result<(), String> {
+        self.context
+            .resize()
+            .map_err(|err| format!("Unable to resize the canvas: {:?}", err))?;
+        self.context.clear_with_color(self.inner.clear);
+
+        self.inner.tick = tick;
+        self.context.set_interpolation(0.0);
+        self.draw()
+    }
+
+    fn draw(&mut self) -> Result<(), String> {
+        if let Some(vertex_array) = self.inner.vertex_array {
+            self.context.bind_vertex_array(vertex_array)?;
         }
 
-        self.context.draw_triangles(*self.inner.get_count());
+        let tick = self.inner.tick;
+        for uniform in &self.inner.uniform_handles {
+            self.context.update_uniform(*uniform, tick)?;
+        }
 
-        self.inner.tick += 1.0;
+        let count = *self.inner.get_count();
+        if let Some(transform_feedback) = self.inner.transform_feedback {
+            self.context
+                .draw_with_feedback(transform_feedback, count, self.inner.discard)?;
+        } else if self.inner.instances > 0 {
+            self.context.draw_triangles_instanced(count, self.inner.instances)?;
+        } else {
+            self.context.draw_triangles(count);
+        }
 
         Ok(())
     }
     pub fn is_dynamic(&self) -> bool {
         self.inner.is_dynamic()
     }
+
+    /// Read what the design drew last, from its framebuffer if it has one
+    pub fn screenshot(&self) -> Result<Image, String> {
+        self.context.screenshot(self.inner.framebuffer_handle)
+    }
+}
+
+impl Rugl<'static> {
+    /// Create a design on the given canvas and start drawing it, several designs can be
+    /// mounted side by side each with their own context and animation loop
+    pub fn mount<T: Into<CanvasTarget>>(
+        canvas: T,
+        mut description: RuglInner<'static>,
+    ) -> Result<Instance, String> {
+        description.canvas = canvas.into();
+        Ok(Rugl::new(description)?.run())
+    }
+
+    /// Start drawing the design, it keeps being drawn for as long as the instance lives
+    pub fn run(self) -> Instance {
+        Instance::start(self)
+    }
+}
+
+pub(crate) fn js_error(err: JsValue) -> String {
+    err.as_string().unwrap_or_else(|| format!("{:?}", err))
 }
 
 /// The internal Rugl struct holds the vertex and fragment shaders,
@@ -74,6 +368,16 @@ impl Rugl<'_> {
 /// a design.
 #[derive(Default)]
 pub struct RuglInner<'a> {
+    /// Canvas the application is drawn on
+    pub canvas: CanvasTarget,
+    /// WebGL context creation attributes
+    pub context: ContextAttributes,
+    /// How the drawing buffer follows the size of the canvas
+    pub resize: ResizeMode,
+    /// How the tick advances between frames
+    pub timestep: Timestep,
+    /// Extensions the application needs or can make use of
+    pub extensions: ExtensionRequest,
     /// Clear color
     pub clear: [f64; 4],
     /// Application vertex shader
@@ -84,8 +388,26 @@ pub struct RuglInner<'a> {
     pub attributes: Vec<Attribute>,
     /// Collection of uniforms in the application
     pub uniforms: Vec<Uniform>,
+    /// Handles to the attributes once they are created on the context
+    pub attribute_handles: Vec<AttributeHandle>,
+    /// Handles to the uniforms once they are created on the context
+    pub uniform_handles: Vec<UniformHandle>,
+    /// Attribute bindings of the draw once the attributes are created on the context
+    pub vertex_array: Option<VertexArrayHandle>,
     /// Number of primitives to draw
     pub count: i32,
+    /// Number of instances to draw, 0 to draw without instancing
+    pub instances: i32,
+    /// Attributes written by transform feedback, along with the varying captured into each
+    pub feedback: Vec<(String, String)>,
+    /// Whether to skip rasterizing while capturing varyings
+    pub discard: bool,
+    /// Transform feedback of the draw once the attributes are created on the context
+    pub transform_feedback: Option<TransformFeedbackHandle>,
+    /// Framebuffer drawn into instead of the canvas
+    pub framebuffer: Option<FramebufferOptions>,
+    /// Framebuffer drawn into once it is created on the context
+    pub framebuffer_handle: Option<FramebufferHandle>,
     /// Determine if we need to dynamically update the screen
     pub dynamic: bool, 
     /// Internal time tick
@@ -129,73 +451,29 @@ impl<'a> RuglInner<'a> {
 /// A macro that enables giving named-arguments to the rugl struct, and sets up the WebGlContext
 #[macro_export]
 macro_rules! rugl_inner {
+    ($($tt:tt)*) => {
+        Rugl::new(rugl_description!($($tt)*))
+    }
+}
+
+/// A macro that turns named-arguments into the description of a design, without creating
+/// anything on the page yet
+///
+/// Values under `uniforms:` are told apart by their Rust type. Arrays of 2 to 4 numbers or
+/// booleans are vectors, `[f64; 9]`, `[f64; 16]` and square nested arrays like `[[f64; 3]; 3]`
+/// are matrices read as columns, and other lengths up to 16 are arrays, e.g. `weights: [0.1; 5]`
+/// for `uniform float weights[5]`. Use a `Vec` to upload an array of one of the square lengths,
+/// e.g. `lights: vec![[1.0, 0.0, 0.0]; 3]` for `uniform vec3 lights[3]`.
+#[macro_export]
+macro_rules! rugl_description {
     (
         $( $i:ident: { $($tokens:tt)* } ),*
     ) => {{
-        #[inline]
-        fn build_inner<'a>() -> Result<(RuglInner<'a>, WebGlContext), JsValue> {
-            use std::borrow::Cow;
-
-            let mut context = WebGlContext::new("canvas")?;
-
-            let mut inner = RuglInner {
-                $($i: rugl_type!($i: $($tokens)*),)*
-                ..Default::default()
-            };
-
-            let vertex = context.compile_shader(
-                ShaderType::Vertex(
-                    inner.get_vertex_shader(),
-                    std::marker::PhantomData
-                )
-            )?;
-
-            let fragment = context.compile_shader(
-                ShaderType::Fragment(
-                    inner.get_fragment_shader(),
-                    std::marker::PhantomData
-                )
-            )?;
+        use std::borrow::Cow;
 
-            context.link_and_add_program(&[vertex, fragment])?;
-            context.use_program()?;
-
-            let count = inner.get_count().clone();
-
-            for attribute in inner.get_mut_attributes() {
-
-                // Unpack Vec<Qualifer>
-                let mut attr_data = Vec::new();
-                for layer in attribute.get_qualifiers() {
-                    attr_data.extend_from_slice(&layer.to_vec());
-                }
-
-                context.create_buffer_with_data(attribute.get_name(), &attr_data[..], count)?;
-                context.bind_buffer_with_name(attribute.get_name())?;
-                context.enable_attribute(attribute.get_name())?;
-            }
-
-            let mut dynamic = false;
-            for uniform in inner.get_mut_uniforms() {
-                if uniform.get_data().is_dynamic() {
-                    dynamic = true;
-                }
-                context.create_uniform(uniform.get_name(), uniform.get_data())?;
-                context.bind_uniform(uniform.get_name())?;
-            }
-
-            inner.dynamic = dynamic;
-
-            Ok((inner, context))
-        }
-
-        match build_inner() {
-            Ok((inner, context)) => Ok(Rugl { inner, context }),
-            Err(err) => {
-                //TODO: Proper error handling
-                log!("There was an error! {}", err.as_string().unwrap());
-                Err("There was a problem!!!".to_owned())
-            }
+        RuglInner {
+            $($i: rugl_type!($i: $($tokens)*),)*
+            ..Default::default()
         }
     }}
 }
@@ -217,7 +495,46 @@ macro_rules! rugl_type {
     };
     (count: $expr:expr) => {
         $expr
-    }
+    };
+    (clear: $expr:expr) => {
+        $expr
+    };
+    (instances: $expr:expr) => {
+        $expr
+    };
+    (feedback: $($attribute:ident: $varying:expr),* $(,)*) => {
+        vec![$((stringify!($attribute).to_owned(), String::from($varying))),*]
+    };
+    (discard: $expr:expr) => {
+        $expr
+    };
+    (framebuffer: $($field:ident: $value:expr),* $(,)*) => {
+        Some(FramebufferOptions {
+            $($field: $value,)*
+            ..Default::default()
+        })
+    };
+    (resize: $expr:expr) => {
+        $expr
+    };
+    (timestep: $expr:expr) => {
+        $expr
+    };
+    (extensions: $($field:ident: [$($extension:expr),* $(,)*]),* $(,)*) => {
+        ExtensionRequest {
+            $($field: vec![$($extension),*],)*
+            ..Default::default()
+        }
+    };
+    (canvas: $expr:expr) => {
+        CanvasTarget::from($expr)
+    };
+    (context: $($field:ident: $value:expr),* $(,)*) => {
+        ContextAttributes {
+            $($field: $value,)*
+            ..Default::default()
+        }
+    };
 }
 
 #[doc(hidden)]
@@ -225,9 +542,30 @@ macro_rules! rugl_type {
 macro_rules! parse_ident {
     // Parse each attribute to generate ident and data array
     (@attribute
-        $($id:ident: [$($tokens:tt)*]),+ $(,)*
-    ) => {
-        vec![$( Attribute::from((stringify!($id).to_owned(), [$($tokens)*])) ),*]
+        $($tokens:tt)*
+    ) => {{
+        let mut attributes: Vec<Attribute> = Vec::new();
+        parse_ident!(@attribute_inner attributes, $($tokens)*);
+        attributes
+    }};
+    // Attribute Array
+    (@attribute_inner $expr:expr, $id:ident: [$($tokens:tt)*] ) => {
+        $expr.push(Attribute::from((stringify!($id).to_owned(), [$($tokens)*])));
+    };
+    (@attribute_inner $expr:expr, $id:ident: [$($tokens:tt)*], $($extra:tt)* ) => {
+        parse_ident!(@attribute_inner $expr, $id: [$($tokens)*]);
+        parse_ident!(@attribute_inner $expr, $($extra)* );
+    };
+    // Attribute with a divisor, advancing per instance
+    (@attribute_inner $expr:expr, $id:ident: { divisor: $divisor:expr, data: $data:expr $(,)* } ) => {
+        $expr.push(Attribute::from((stringify!($id).to_owned(), $data)).with_divisor($divisor));
+    };
+    (@attribute_inner $expr:expr, $id:ident: { divisor: $divisor:expr, data: $data:expr $(,)* }, $($extra:tt)* ) => {
+        parse_ident!(@attribute_inner $expr, $id: { divisor: $divisor, data: $data });
+        parse_ident!(@attribute_inner $expr, $($extra)* );
+    };
+    // Empty base for trailing commas
+    (@attribute_inner $expr:expr,) => {
     };
     // Parse uniform to generate variable-use function
     (@uniform
@@ -261,6 +599,16 @@ macro_rules! parse_ident {
         parse_ident!(@uniform_inner $expr, $id: |$($fn_head: $fn_type),*| $fn_body );
         parse_ident!(@uniform_inner $expr, $($extra)* );
     };
+    // Uniform Struct, each field becomes its own `struct.field` uniform
+    (@uniform_inner $expr:expr, $id:ident: { $field:ident: $($fields:tt)* } ) => {
+        for uniform in parse_ident!(@uniform $field: $($fields)*) {
+            $expr.push(uniform.with_prefix(stringify!($id)));
+        }
+    };
+    (@uniform_inner $expr:expr, $id:ident: { $field:ident: $($fields:tt)* }, $($extra:tt)* ) => {
+        parse_ident!(@uniform_inner $expr, $id: { $field: $($fields)* } );
+        parse_ident!(@uniform_inner $expr, $($extra)* );
+    };
     // Uniform Direct Expression
     (@uniform_inner $expr:expr, $id:ident: $data:expr ) => {
         $expr.push(Uniform::from((stringify!($id).to_owned(), UniformInner::from($data))));
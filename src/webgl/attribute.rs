@@ -8,9 +8,24 @@ use super::qualifier::Qualifier;
 pub struct Attribute {
     name: String,
     data: Vec<Qualifier>,
+    divisor: u32,
 }
 
 impl Attribute {
+    pub fn new(name: String, data: Vec<Qualifier>) -> Self {
+        Self {
+            name,
+            data,
+            divisor: 0,
+        }
+    }
+
+    /// Advance the attribute once every `divisor` instances instead of once per vertex
+    pub fn with_divisor(mut self, divisor: u32) -> Self {
+        self.divisor = divisor;
+        self
+    }
+
     pub fn get_name(&self) -> &String {
         &self.name
     }
@@ -18,6 +33,22 @@ impl Attribute {
     pub fn get_qualifiers(&self) -> &Vec<Qualifier> {
         &self.data
     }
+
+    pub fn get_divisor(&self) -> u32 {
+        self.divisor
+    }
+
+    /// Number of components making up one element, e.g. 2 for a `vec2`
+    pub fn components(&self) -> i32 {
+        self.data.first().map_or(1, Qualifier::components)
+    }
+}
+
+// Arbitrarily long data, e.g. one element per instance
+impl<T: Into<Qualifier>> From<(String, Vec<T>)> for Attribute {
+    fn from(items: (String, Vec<T>)) -> Self {
+        Self::new(items.0, items.1.into_iter().map(Into::into).collect())
+    }
 }
 
 // Array impls
@@ -25,6 +56,7 @@ __impl_from_repeat_vec!(i32, Attribute, Qualifier::Float);
 __impl_from_repeat_vec!(f32, Attribute, Qualifier::Float);
 __impl_from_repeat_vec!([i32; 2], Attribute, Qualifier::Vec2);
 __impl_from_repeat_vec!([i32; 3], Attribute, Qualifier::Vec3);
+__impl_from_repeat_vec!([i32; 4], Attribute, Qualifier::Vec4);
 __impl_from_repeat_vec!([f32; 2], Attribute, Qualifier::Vec2f);
 __impl_from_repeat_vec!([f32; 3], Attribute, Qualifier::Vec3f);
 __impl_from_repeat_vec!([f32; 4], Attribute, Qualifier::Vec4f);
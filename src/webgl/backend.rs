@@ -0,0 +1,839 @@
+/*!
+The calls resources are created and restored with. `WebGlContext` keeps what every resource was
+created from and hands it to a backend, `Gl` uploads it to WebGL while the tests record it so
+restoring a lost context can be checked without a browser.
+ */
+
+use std::fmt;
+
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::JsValue;
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlRenderingContext,
+    WebGlTexture, WebGlTransformFeedback, WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use crate::webgl::{
+    ActiveInfo, Extension, Extensions, Framebuffer, Gl, GlslType, JsArray, Program, Scalar, Shader,
+    Texture, UniformValue, Version, Viewport,
+};
+
+pub trait Backend: Sized {
+    type Shader: Clone + fmt::Debug;
+    type Program: Clone + fmt::Debug;
+    type Buffer;
+    type Location;
+    type Texture;
+    type Framebuffer;
+    type VertexArray;
+    type TransformFeedback;
+    /// Attribute data and texels, kept to upload them again after the context is lost
+    type Data: Clone;
+
+    fn version(&self) -> Version;
+
+    /// Compile the shaders again from their sources and link them, capturing the named
+    /// varyings through transform feedback
+    fn relink(
+        &self,
+        shaders: &[Self::Shader],
+        varyings: &[String],
+    ) -> Result<Program<Self>, String>;
+
+    fn bind_program(&self, program: &Self::Program);
+
+    /// Location of an attribute, negative if the program does not use it
+    fn attribute_location(&self, program: &Self::Program, name: &str) -> i32;
+
+    fn uniform_location(&self, program: &Self::Program, name: &str) -> Option<Self::Location>;
+
+    fn create_buffer(&self) -> Result<Self::Buffer, String>;
+
+    fn upload_buffer(&self, buffer: &Self::Buffer, data: &Self::Data, usage: u32);
+
+    /// Read an attribute from a buffer, advancing once every `divisor` instances
+    fn attribute_pointer(
+        &self,
+        extensions: &Extensions,
+        buffer: &Self::Buffer,
+        location: u32,
+        size: i32,
+        data: &Self::Data,
+        divisor: u32,
+    ) -> Result<(), String>;
+
+    /// Upload a value already coerced to the declared type of the uniform
+    fn upload_uniform(
+        &self,
+        location: &Self::Location,
+        info: &ActiveInfo,
+        transpose: bool,
+        value: UniformValue,
+    ) -> Result<(), String>;
+
+    fn upload_uniform_block(
+        &self,
+        buffer: &Self::Buffer,
+        binding: u32,
+        data: &[u8],
+    ) -> Result<(), String>;
+
+    fn uniform_block_binding(
+        &self,
+        program: &Self::Program,
+        index: u32,
+        binding: u32,
+    ) -> Result<(), String>;
+
+    fn create_texture(&self) -> Result<Self::Texture, String>;
+
+    /// Allocate the storage of a texture and upload its texels
+    fn allocate_texture(&self, texture: &Texture<Self>) -> Result<(), String>;
+
+    fn create_framebuffer(&self) -> Result<Self::Framebuffer, String>;
+
+    /// How many color outputs a framebuffer can have
+    fn max_draw_buffers(&self) -> usize;
+
+    /// Attach a texture per color output, checking the combination can be drawn into, and
+    /// bind the framebuffer drawn into before again
+    fn attach_textures(
+        &self,
+        framebuffer: &Self::Framebuffer,
+        textures: &[&Self::Texture],
+        bound: Option<&Self::Framebuffer>,
+    ) -> Result<(), String>;
+
+    /// Draw into a framebuffer, or into the drawing buffer at `viewport` with `None`
+    fn draw_into(
+        &self,
+        extensions: &Extensions,
+        framebuffer: Option<&Framebuffer<Self>>,
+        viewport: Viewport,
+    ) -> Result<(), String>;
+
+    /// A vertex array object, `None` when the context does not support them
+    fn create_vertex_array(
+        &self,
+        extensions: &Extensions,
+    ) -> Result<Option<Self::VertexArray>, String>;
+
+    fn bind_vertex_array(&self, extensions: &Extensions, vertex_array: Option<&Self::VertexArray>);
+
+    fn create_transform_feedback(&self) -> Result<Self::TransformFeedback, String>;
+}
+
+impl Backend for Gl {
+    type Shader = Shader;
+    type Program = WebGlProgram;
+    type Buffer = WebGlBuffer;
+    type Location = WebGlUniformLocation;
+    type Texture = WebGlTexture;
+    type Framebuffer = WebGlFramebuffer;
+    type VertexArray = WebGlVertexArrayObject;
+    type TransformFeedback = WebGlTransformFeedback;
+    type Data = JsArray;
+
+    fn version(&self) -> Version {
+        Gl::version(self)
+    }
+
+    fn relink(&self, shaders: &[Shader], varyings: &[String]) -> Result<Program, String> {
+        let shaders = shaders
+            .iter()
+            .map(|shader| shader.recompile(self))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Program::with_varyings(self, &shaders, varyings)
+    }
+
+    fn bind_program(&self, program: &WebGlProgram) {
+        self.use_program(Some(program));
+    }
+
+    fn attribute_location(&self, program: &WebGlProgram, name: &str) -> i32 {
+        self.get_attrib_location(program, name)
+    }
+
+    fn uniform_location(&self, program: &WebGlProgram, name: &str) -> Option<WebGlUniformLocation> {
+        self.get_uniform_location(program, name)
+    }
+
+    fn create_buffer(&self) -> Result<WebGlBuffer, String> {
+        Gl::create_buffer(self).ok_or_else(|| String::from("Unable to create buffer"))
+    }
+
+    fn upload_buffer(&self, buffer: &WebGlBuffer, data: &JsArray, usage: u32) {
+        self.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(buffer));
+        self.buffer_data_with_array_buffer_view(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            data.to_object(),
+            usage,
+        );
+    }
+
+    fn attribute_pointer(
+        &self,
+        extensions: &Extensions,
+        buffer: &WebGlBuffer,
+        location: u32,
+        size: i32,
+        data: &JsArray,
+        divisor: u32,
+    ) -> Result<(), String> {
+        let gl_type = data
+            .gl_type()
+            .ok_or_else(|| String::from("Attributes can not be made of doubles!"))?;
+
+        // The pointer refers to whichever buffer is bound at this point
+        self.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(buffer));
+
+        match self {
+            // Integers stay integers for `int` and `uint` attributes of GLSL ES 3.00
+            Gl::WebGl2(context) if data.is_integer() => {
+                context.vertex_attrib_i_pointer_with_i32(location, size, gl_type, 0, 0)
+            }
+            context => context.vertex_attrib_pointer_with_i32(location, size, gl_type, false, 0, 0),
+        }
+        self.enable_vertex_attrib_array(location);
+
+        // Divisors are global state, reset them for per-vertex attributes too
+        match self {
+            Gl::WebGl2(context) => context.vertex_attrib_divisor(location, divisor),
+            Gl::WebGl1(_) => {
+                if let Some(instanced_arrays) = &extensions.instanced_arrays {
+                    instanced_arrays.vertex_attrib_divisor_angle(location, divisor);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn upload_uniform(
+        &self,
+        location: &WebGlUniformLocation,
+        info: &ActiveInfo,
+        transpose: bool,
+        value: UniformValue,
+    ) -> Result<(), String> {
+        let location = Some(location);
+        match value {
+            UniformValue::Float(mut val) => match info.kind {
+                GlslType::Float => self.uniform1fv_with_f32_array(location, &mut val),
+                GlslType::Vec2 => self.uniform2fv_with_f32_array(location, &mut val),
+                GlslType::Vec3 => self.uniform3fv_with_f32_array(location, &mut val),
+                GlslType::Vec4 => self.uniform4fv_with_f32_array(location, &mut val),
+                GlslType::Mat2 => {
+                    self.uniform_matrix2fv_with_f32_array(location, transpose, &mut val)
+                }
+                GlslType::Mat3 => {
+                    self.uniform_matrix3fv_with_f32_array(location, transpose, &mut val)
+                }
+                GlslType::Mat4 => {
+                    self.uniform_matrix4fv_with_f32_array(location, transpose, &mut val)
+                }
+                kind => {
+                    let context = self.webgl2("Non-square matrices")?;
+                    match kind {
+                        GlslType::Mat2x3 => context
+                            .uniform_matrix2x3fv_with_f32_array(location, transpose, &mut val),
+                        GlslType::Mat2x4 => context
+                            .uniform_matrix2x4fv_with_f32_array(location, transpose, &mut val),
+                        GlslType::Mat3x2 => context
+                            .uniform_matrix3x2fv_with_f32_array(location, transpose, &mut val),
+                        GlslType::Mat3x4 => context
+                            .uniform_matrix3x4fv_with_f32_array(location, transpose, &mut val),
+                        GlslType::Mat4x2 => context
+                            .uniform_matrix4x2fv_with_f32_array(location, transpose, &mut val),
+                        GlslType::Mat4x3 => context
+                            .uniform_matrix4x3fv_with_f32_array(location, transpose, &mut val),
+                        _ => unreachable!("Only float uniforms are coerced to floats"),
+                    }
+                }
+            },
+            UniformValue::Int(val) if info.kind.scalar() == Scalar::UnsignedInt => {
+                let context = self.webgl2("Unsigned integer uniforms")?;
+                let mut val: Vec<u32> = val.into_iter().map(|val| val as u32).collect();
+                match info.kind.components() {
+                    1 => context.uniform1uiv_with_u32_array(location, &mut val),
+                    2 => context.uniform2uiv_with_u32_array(location, &mut val),
+                    3 => context.uniform3uiv_with_u32_array(location, &mut val),
+                    _ => context.uniform4uiv_with_u32_array(location, &mut val),
+                }
+            }
+            UniformValue::Int(mut val) => match info.kind.components() {
+                1 => self.uniform1iv_with_i32_array(location, &mut val),
+                2 => self.uniform2iv_with_i32_array(location, &mut val),
+                3 => self.uniform3iv_with_i32_array(location, &mut val),
+                _ => self.uniform4iv_with_i32_array(location, &mut val),
+            },
+        }
+
+        Ok(())
+    }
+
+    fn upload_uniform_block(
+        &self,
+        buffer: &WebGlBuffer,
+        binding: u32,
+        data: &[u8],
+    ) -> Result<(), String> {
+        let context = self.webgl2("Uniform blocks")?;
+        let target = WebGl2RenderingContext::UNIFORM_BUFFER;
+
+        context.bind_buffer(target, Some(buffer));
+        context.buffer_data_with_array_buffer_view(
+            target,
+            &Uint8Array::from(data),
+            WebGl2RenderingContext::DYNAMIC_DRAW,
+        );
+        context.bind_buffer_base(target, binding, Some(buffer));
+
+        Ok(())
+    }
+
+    fn uniform_block_binding(
+        &self,
+        program: &WebGlProgram,
+        index: u32,
+        binding: u32,
+    ) -> Result<(), String> {
+        self.webgl2("Uniform blocks")?
+            .uniform_block_binding(program, index, binding);
+        Ok(())
+    }
+
+    fn create_texture(&self) -> Result<WebGlTexture, String> {
+        Gl::create_texture(self).ok_or_else(|| String::from("Unable to create texture"))
+    }
+
+    /// Textures are sampled without filtering or mipmaps so every format and size works the
+    /// same
+    fn allocate_texture(&self, texture: &Texture) -> Result<(), String> {
+        let format = texture.get_format();
+        let target = WebGlRenderingContext::TEXTURE_2D;
+
+        // Half floats of WebGL 2 are converted from floats on upload
+        let data_type = match texture.get_data() {
+            Some(JsArray::Float32Array(_)) if format.is_float() && self.is_webgl2() => {
+                WebGlRenderingContext::FLOAT
+            }
+            _ => format.data_type(),
+        };
+
+        self.bind_texture(target, Some(texture.as_ref()));
+        let allocated = self
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+                target,
+                0,
+                format.internal_format() as i32,
+                texture.get_width(),
+                texture.get_height(),
+                0,
+                format.format(),
+                data_type,
+                texture.get_data().map(|data| data.to_object()),
+            );
+        for (parameter, value) in &[
+            (
+                WebGlRenderingContext::TEXTURE_MIN_FILTER,
+                WebGlRenderingContext::NEAREST,
+            ),
+            (
+                WebGlRenderingContext::TEXTURE_MAG_FILTER,
+                WebGlRenderingContext::NEAREST,
+            ),
+            (
+                WebGlRenderingContext::TEXTURE_WRAP_S,
+                WebGlRenderingContext::CLAMP_TO_EDGE,
+            ),
+            (
+                WebGlRenderingContext::TEXTURE_WRAP_T,
+                WebGlRenderingContext::CLAMP_TO_EDGE,
+            ),
+        ] {
+            self.tex_parameteri(target, *parameter, *value as i32);
+        }
+        self.bind_texture(target, None);
+
+        allocated.map_err(|err| format!("Unable to allocate texture: {:?}", err))
+    }
+
+    fn create_framebuffer(&self) -> Result<WebGlFramebuffer, String> {
+        Gl::create_framebuffer(self).ok_or_else(|| String::from("Unable to create framebuffer"))
+    }
+
+    fn max_draw_buffers(&self) -> usize {
+        // MAX_DRAW_BUFFERS_WEBGL has the same value
+        self.get_parameter(WebGl2RenderingContext::MAX_DRAW_BUFFERS)
+            .ok()
+            .and_then(|max| max.as_f64())
+            .unwrap_or(1.0) as usize
+    }
+
+    fn attach_textures(
+        &self,
+        framebuffer: &WebGlFramebuffer,
+        textures: &[&WebGlTexture],
+        bound: Option<&WebGlFramebuffer>,
+    ) -> Result<(), String> {
+        let target = WebGlRenderingContext::FRAMEBUFFER;
+
+        self.bind_framebuffer(target, Some(framebuffer));
+        for (index, texture) in textures.iter().enumerate() {
+            self.framebuffer_texture_2d(
+                target,
+                WebGlRenderingContext::COLOR_ATTACHMENT0 + index as u32,
+                WebGlRenderingContext::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+        }
+        let status = self.check_framebuffer_status(target);
+        self.bind_framebuffer(target, bound);
+
+        if status != WebGlRenderingContext::FRAMEBUFFER_COMPLETE {
+            return Err(format!(
+                "Framebuffer can not be drawn into, its status is 0x{:x}!",
+                status
+            ));
+        }
+        Ok(())
+    }
+
+    fn draw_into(
+        &self,
+        extensions: &Extensions,
+        framebuffer: Option<&Framebuffer>,
+        viewport: Viewport,
+    ) -> Result<(), String> {
+        let target = WebGlRenderingContext::FRAMEBUFFER;
+
+        let framebuffer = match framebuffer {
+            Some(framebuffer) => framebuffer,
+            None => {
+                self.bind_framebuffer(target, None);
+                self.viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+                return Ok(());
+            }
+        };
+
+        self.bind_framebuffer(target, Some(framebuffer.as_ref()));
+        self.viewport(0, 0, framebuffer.get_width(), framebuffer.get_height());
+
+        // Output n is written to attachment n, the constants of WEBGL_draw_buffers are the same
+        let outputs = framebuffer.get_colors().len() as u32;
+        if outputs > 1 {
+            let attachments: Array = (0..outputs)
+                .map(|index| JsValue::from(WebGlRenderingContext::COLOR_ATTACHMENT0 + index))
+                .collect();
+            match self {
+                Gl::WebGl2(context) => context.draw_buffers(&attachments),
+                Gl::WebGl1(_) => match &extensions.draw_buffers {
+                    Some(extension) => extension.draw_buffers_webgl(&attachments),
+                    None => {
+                        return Err(format!(
+                            "Several color outputs require {}!",
+                            Extension::WebglDrawBuffers.name()
+                        ))
+                    }
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_vertex_array(
+        &self,
+        extensions: &Extensions,
+    ) -> Result<Option<WebGlVertexArrayObject>, String> {
+        let object = match self {
+            Gl::WebGl2(context) => context.create_vertex_array(),
+            Gl::WebGl1(_) => match &extensions.vertex_array_object {
+                Some(extension) => extension.create_vertex_array_oes(),
+                None => return Ok(None),
+            },
+        };
+
+        object
+            .map(Some)
+            .ok_or_else(|| String::from("Unable to create vertex array"))
+    }
+
+    fn bind_vertex_array(&self, extensions: &Extensions, object: Option<&WebGlVertexArrayObject>) {
+        match self {
+            Gl::WebGl2(context) => context.bind_vertex_array(object),
+            Gl::WebGl1(_) => {
+                if let Some(extension) = &extensions.vertex_array_object {
+                    extension.bind_vertex_array_oes(object);
+                }
+            }
+        }
+    }
+
+    fn create_transform_feedback(&self) -> Result<WebGlTransformFeedback, String> {
+        self.webgl2("Transform feedback")?
+            .create_transform_feedback()
+            .ok_or_else(|| String::from("Unable to create transform feedback"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::collections::{BTreeMap, HashMap};
+
+    use wasm_bindgen::JsCast;
+
+    use super::*;
+    use crate::webgl::{FramebufferOptions, TextureFormat, UniformInner, WebGlContext};
+
+    const VERTEX: &str = "
+        attribute vec2 position;
+        attribute vec2 offset;
+        uniform float scale;
+    ";
+    const FRAGMENT: &str = "
+        uniform vec4 color;
+    ";
+
+    /// A context describing every object by what it was given, losing it forgets them all
+    #[derive(Default)]
+    struct Fake {
+        ids: Cell<u32>,
+        objects: RefCell<HashMap<u32, String>>,
+        sources: RefCell<HashMap<u32, Vec<&'static str>>>,
+        /// Attribute pointers of every vertex array object, 0 being the default one
+        pointers: RefCell<HashMap<u32, BTreeMap<u32, String>>>,
+        vertex_array: Cell<u32>,
+        program: Cell<Option<u32>>,
+        framebuffer: Cell<Option<u32>>,
+    }
+
+    impl Fake {
+        fn create(&self, description: &str) -> u32 {
+            let id = self.ids.get() + 1;
+            self.ids.set(id);
+            self.objects.borrow_mut().insert(id, description.to_owned());
+            id
+        }
+
+        /// What an object holds, objects of a lost context can not be used anymore
+        fn get(&self, id: u32) -> String {
+            self.objects
+                .borrow()
+                .get(&id)
+                .cloned()
+                .unwrap_or_else(|| panic!("Object {} belongs to a lost context!", id))
+        }
+
+        fn set(&self, id: u32, description: String) {
+            self.get(id);
+            self.objects.borrow_mut().insert(id, description);
+        }
+
+        fn lose(&self) {
+            self.objects.borrow_mut().clear();
+            self.sources.borrow_mut().clear();
+            self.pointers.borrow_mut().clear();
+            self.vertex_array.set(0);
+            self.program.set(None);
+            self.framebuffer.set(None);
+        }
+
+        /// Everything the context holds, without the ids its objects happen to have
+        fn describe(&self) -> Vec<String> {
+            let mut state: Vec<String> = self.objects.borrow().values().cloned().collect();
+            for (vertex_array, pointers) in self.pointers.borrow().iter() {
+                let name = match vertex_array {
+                    0 => "default vertex array",
+                    _ => "vertex array",
+                };
+                state.push(format!("{} pointing {:?}", name, pointers));
+            }
+            let program = self.program.get().map(|program| self.get(program));
+            state.push(format!("using {:?}", program));
+            let framebuffer = self
+                .framebuffer
+                .get()
+                .map(|framebuffer| self.get(framebuffer));
+            state.push(format!("drawing into {:?}", framebuffer));
+
+            state.sort();
+            state
+        }
+
+        /// The variables the shaders of a program declare with the given qualifier
+        fn declared(&self, program: u32, qualifier: &str) -> Vec<ActiveInfo> {
+            self.get(program);
+            self.sources.borrow()[&program]
+                .iter()
+                .flat_map(|source| source.lines())
+                .filter_map(
+                    |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                        [declared, kind, name] if declared == qualifier => Some(ActiveInfo {
+                            name: name.trim_end_matches(';').to_owned(),
+                            kind: match kind {
+                                "float" => GlslType::Float,
+                                "vec2" => GlslType::Vec2,
+                                _ => GlslType::Vec4,
+                            },
+                            size: 1,
+                        }),
+                        _ => None,
+                    },
+                )
+                .collect()
+        }
+    }
+
+    impl Backend for Fake {
+        type Shader = &'static str;
+        type Program = u32;
+        type Buffer = u32;
+        type Location = (u32, String);
+        type Texture = u32;
+        type Framebuffer = u32;
+        type VertexArray = u32;
+        type TransformFeedback = u32;
+        type Data = Vec<f32>;
+
+        fn version(&self) -> Version {
+            Version::WebGl1
+        }
+
+        fn relink(
+            &self,
+            shaders: &[&'static str],
+            varyings: &[String],
+        ) -> Result<Program<Self>, String> {
+            let program = self.create(&format!("program {:?} capturing {:?}", shaders, varyings));
+            self.sources.borrow_mut().insert(program, shaders.to_vec());
+
+            let attributes = self.declared(program, "attribute");
+            let uniforms = self.declared(program, "uniform");
+            Ok(Program::declaring(
+                program,
+                shaders.to_vec(),
+                attributes,
+                uniforms,
+            ))
+        }
+
+        fn bind_program(&self, program: &u32) {
+            self.get(*program);
+            self.program.set(Some(*program));
+        }
+
+        fn attribute_location(&self, program: &u32, name: &str) -> i32 {
+            self.declared(*program, "attribute")
+                .iter()
+                .position(|info| info.name == name)
+                .map_or(-1, |location| location as i32)
+        }
+
+        fn uniform_location(&self, program: &u32, name: &str) -> Option<(u32, String)> {
+            let declared = self.declared(*program, "uniform");
+            declared.iter().find(|info| info.name == name)?;
+
+            let name = format!("{} of {}", name, self.get(*program));
+            Some((self.create(&format!("{} unset", name)), name))
+        }
+
+        fn create_buffer(&self) -> Result<u32, String> {
+            Ok(self.create("empty buffer"))
+        }
+
+        fn upload_buffer(&self, buffer: &u32, data: &Vec<f32>, usage: u32) {
+            self.set(
+                *buffer,
+                format!("buffer of {:?} used as 0x{:x}", data, usage),
+            );
+        }
+
+        fn attribute_pointer(
+            &self,
+            _: &Extensions,
+            buffer: &u32,
+            location: u32,
+            size: i32,
+            _: &Vec<f32>,
+            divisor: u32,
+        ) -> Result<(), String> {
+            let pointer = format!(
+                "{} read {} at a time per {}",
+                self.get(*buffer),
+                size,
+                divisor
+            );
+            self.pointers
+                .borrow_mut()
+                .entry(self.vertex_array.get())
+                .or_default()
+                .insert(location, pointer);
+            Ok(())
+        }
+
+        fn upload_uniform(
+            &self,
+            (location, name): &(u32, String),
+            info: &ActiveInfo,
+            _: bool,
+            value: UniformValue,
+        ) -> Result<(), String> {
+            self.set(*location, format!("{} {} = {:?}", info, name, value));
+            Ok(())
+        }
+
+        fn upload_uniform_block(
+            &self,
+            buffer: &u32,
+            binding: u32,
+            data: &[u8],
+        ) -> Result<(), String> {
+            self.set(
+                *buffer,
+                format!("uniform block of {:?} at {}", data, binding),
+            );
+            Ok(())
+        }
+
+        fn uniform_block_binding(&self, _: &u32, _: u32, _: u32) -> Result<(), String> {
+            Err(String::from("Uniform blocks need WebGL 2!"))
+        }
+
+        fn create_texture(&self) -> Result<u32, String> {
+            Ok(self.create("empty texture"))
+        }
+
+        fn allocate_texture(&self, texture: &Texture<Self>) -> Result<(), String> {
+            let description = format!(
+                "{}x{} {:?} texture of {:?}",
+                texture.get_width(),
+                texture.get_height(),
+                texture.get_format(),
+                texture.get_data()
+            );
+            self.set(*texture.as_ref(), description);
+            Ok(())
+        }
+
+        fn create_framebuffer(&self) -> Result<u32, String> {
+            Ok(self.create("empty framebuffer"))
+        }
+
+        fn max_draw_buffers(&self) -> usize {
+            1
+        }
+
+        fn attach_textures(
+            &self,
+            framebuffer: &u32,
+            textures: &[&u32],
+            bound: Option<&u32>,
+        ) -> Result<(), String> {
+            let textures: Vec<String> =
+                textures.iter().map(|texture| self.get(**texture)).collect();
+            self.set(*framebuffer, format!("framebuffer of {:?}", textures));
+            if let Some(bound) = bound {
+                self.get(*bound);
+            }
+            Ok(())
+        }
+
+        fn draw_into(
+            &self,
+            _: &Extensions,
+            framebuffer: Option<&Framebuffer<Self>>,
+            _: Viewport,
+        ) -> Result<(), String> {
+            let framebuffer = framebuffer.map(|framebuffer| *framebuffer.as_ref());
+            if let Some(framebuffer) = framebuffer {
+                self.get(framebuffer);
+            }
+            self.framebuffer.set(framebuffer);
+            Ok(())
+        }
+
+        fn create_vertex_array(&self, _: &Extensions) -> Result<Option<u32>, String> {
+            Ok(Some(self.create("vertex array")))
+        }
+
+        fn bind_vertex_array(&self, _: &Extensions, vertex_array: Option<&u32>) {
+            if let Some(vertex_array) = vertex_array {
+                self.get(*vertex_array);
+            }
+            self.vertex_array.set(vertex_array.copied().unwrap_or(0));
+        }
+
+        fn create_transform_feedback(&self) -> Result<u32, String> {
+            Err(String::from("Transform feedback need WebGL 2!"))
+        }
+    }
+
+    fn fake_context() -> WebGlContext<Fake> {
+        // The fake is never drawn, a canvas and memory that are not backed by JavaScript are
+        // enough for it
+        WebGlContext::with_backend(
+            Fake::default(),
+            Extensions::default(),
+            JsValue::NULL.unchecked_into(),
+            None,
+            JsValue::NULL,
+        )
+    }
+
+    #[test]
+    fn restoring_recreates_every_resource_from_what_was_kept() {
+        let mut context = fake_context();
+
+        let program = context.context.relink(&[VERTEX, FRAGMENT], &[]).unwrap();
+        let program = context.add_program(program).unwrap();
+        context.use_program(program).unwrap();
+
+        let position = vec![-1.0, -1.0, 1.0, -1.0, -1.0, 1.0];
+        let position = context.insert_attribute("position", position, 2).unwrap();
+        let offset = context
+            .insert_attribute("offset", vec![0.5, 0.5], 2)
+            .unwrap();
+        for attribute in &[position, offset] {
+            context.bind_buffer(*attribute).unwrap();
+            context.enable_attribute(*attribute).unwrap();
+        }
+
+        let color = UniformInner::from([1.0, 0.5, 0.0, 1.0]);
+        let color = context.create_uniform("color", color).unwrap();
+        context.bind_uniform(color).unwrap();
+        let scale = context
+            .create_uniform("scale", UniformInner::from(2.0))
+            .unwrap();
+        context.bind_uniform(scale).unwrap();
+
+        let texels = Some(vec![0.0, 0.25, 0.5, 1.0]);
+        context
+            .insert_texture(1, 1, TextureFormat::Rgba, texels)
+            .unwrap();
+        let options = FramebufferOptions {
+            width: 4,
+            height: 2,
+            color: Vec::new(),
+        };
+        let framebuffer = context.create_framebuffer(&options).unwrap();
+        context.bind_framebuffer(Some(framebuffer)).unwrap();
+        context.create_vertex_array(&[position, offset]).unwrap();
+
+        let before = context.context.describe();
+        let texture = String::from("4x2 Rgba texture of None");
+        assert!(before.contains(&texture), "{:#?}", before);
+
+        context.context.lose();
+        assert_eq!(
+            context.context.describe(),
+            vec!["drawing into None", "using None"]
+        );
+
+        context.restore_resources().unwrap();
+        assert_eq!(context.context.describe(), before);
+    }
+}
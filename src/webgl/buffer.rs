@@ -1,38 +1,99 @@
 /// WebGlBuffer wrapper
-use web_sys::{WebGlBuffer, WebGlUniformLocation};
-
-use crate::webgl::JsArray;
+use crate::webgl::ActiveInfo;
+use crate::webgl::Backend;
+use crate::webgl::BufferHandle;
+use crate::webgl::Gl;
 use crate::webgl::UniformInner;
 
-pub enum BufferInternal {
-    Attribute(JsArray, u32),
-    Uniform(UniformInner, WebGlUniformLocation),
+pub enum BufferInternal<B: Backend = Gl> {
+    /// Data, location and divisor of an attribute
+    Attribute(B::Data, u32, u32),
+    /// Data, location and divisor of an attribute written by transform feedback, along with
+    /// the buffer the next capture writes into while the current one is read
+    Feedback(B::Data, u32, u32, BufferHandle),
+    Uniform(UniformInner, B::Location, ActiveInfo),
 }
 
-impl BufferInternal {
-    pub fn get_attribute(&self) -> (&JsArray, &u32) {
+impl<B: Backend> BufferInternal<B> {
+    pub fn get_attribute(&self) -> (&B::Data, &u32) {
+        match self {
+            BufferInternal::Attribute(data, location, _) => (data, location),
+            BufferInternal::Feedback(data, location, _, _) => (data, location),
+            BufferInternal::Uniform(_, _, _) => panic!("Not an attribute!"),
+        }
+    }
+
+    /// Point the data at a new location, e.g. after the program was linked again
+    pub fn set_attribute_location(&mut self, location: u32) {
+        match self {
+            BufferInternal::Attribute(_, current, _) => *current = location,
+            BufferInternal::Feedback(_, current, _, _) => *current = location,
+            BufferInternal::Uniform(_, _, _) => {}
+        }
+    }
+
+    /// How many instances share one element of an attribute, 0 for per-vertex data
+    pub fn get_divisor(&self) -> u32 {
         match self {
-            BufferInternal::Attribute(data, location) => (data, location),
-            BufferInternal::Uniform(_, _) => panic!("Not an attribute!"),
+            BufferInternal::Attribute(_, _, divisor) => *divisor,
+            BufferInternal::Feedback(_, _, divisor, _) => *divisor,
+            BufferInternal::Uniform(_, _, _) => 0,
         }
     }
 
-    pub fn get_uniform(&self) -> (&UniformInner, &WebGlUniformLocation) {
+    pub fn set_divisor(&mut self, divisor: u32) {
         match self {
-            BufferInternal::Attribute(_, _) => panic!("Not a uniform!"),
-            BufferInternal::Uniform(data, location) => (data, location),
+            BufferInternal::Attribute(_, _, current) => *current = divisor,
+            BufferInternal::Feedback(_, _, current, _) => *current = divisor,
+            BufferInternal::Uniform(_, _, _) => {}
+        }
+    }
+
+    /// Let transform feedback write the attribute, into `back` while its own buffer is read
+    pub fn set_feedback(&mut self, back: BufferHandle) {
+        if let BufferInternal::Attribute(data, location, divisor) = self {
+            *self = BufferInternal::Feedback(data.clone(), *location, *divisor, back);
+        }
+    }
+
+    /// The buffer transform feedback writes into next, `None` unless the attribute is captured
+    pub fn get_feedback(&self) -> Option<BufferHandle> {
+        match self {
+            BufferInternal::Feedback(_, _, _, back) => Some(*back),
+            _ => None,
+        }
+    }
+
+    pub fn set_uniform_location(&mut self, location: B::Location) {
+        if let BufferInternal::Uniform(_, current, _) = self {
+            *current = location;
+        }
+    }
+
+    pub fn set_uniform(&mut self, data: UniformInner) {
+        if let BufferInternal::Uniform(current, _, _) = self {
+            *current = data;
+        }
+    }
+
+    pub fn get_uniform(&self) -> (&UniformInner, &B::Location, &ActiveInfo) {
+        match self {
+            BufferInternal::Attribute(_, _, _) | BufferInternal::Feedback(_, _, _, _) => {
+                panic!("Not a uniform!")
+            }
+            BufferInternal::Uniform(data, location, info) => (data, location, info),
         }
     }
 }
 
-pub struct Buffer {
-    buffer: Option<WebGlBuffer>,
-    data: BufferInternal,
+pub struct Buffer<B: Backend = Gl> {
+    buffer: Option<BufferHandle>,
+    data: BufferInternal<B>,
     count: i32,
 }
 
-impl Buffer {
-    pub fn new(buffer: Option<WebGlBuffer>, data: BufferInternal, count: i32) -> Self {
+impl<B: Backend> Buffer<B> {
+    pub fn new(buffer: Option<BufferHandle>, data: BufferInternal<B>, count: i32) -> Self {
         Self {
             buffer,
             data,
@@ -40,14 +101,31 @@ impl Buffer {
         }
     }
 
-    pub fn get_buffer(&self) -> Option<&WebGlBuffer> {
-        self.buffer.as_ref()
+    pub fn get_buffer(&self) -> Option<BufferHandle> {
+        self.buffer
     }
 
-    pub fn get_data(&self) -> &BufferInternal {
+    pub fn get_data(&self) -> &BufferInternal<B> {
         &self.data
     }
 
+    pub fn get_mut_data(&mut self) -> &mut BufferInternal<B> {
+        &mut self.data
+    }
+
+    pub fn set_buffer(&mut self, buffer: Option<BufferHandle>) {
+        self.buffer = buffer;
+    }
+
+    /// Read what the last capture wrote, handing the buffer read so far to the next capture
+    pub fn swap_feedback(&mut self) {
+        if let (Some(front), BufferInternal::Feedback(_, _, _, back)) =
+            (&mut self.buffer, &mut self.data)
+        {
+            std::mem::swap(front, back);
+        }
+    }
+
     pub fn get_count(&self) -> &i32 {
         &self.count
     }
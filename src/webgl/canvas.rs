@@ -0,0 +1,149 @@
+/*!
+Where rugl draws to and how the WebGL context is created on it.
+ */
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlCanvasElement};
+
+use super::gl::Version;
+
+/// The canvas a design is drawn on
+#[derive(Debug, Clone)]
+pub enum CanvasTarget {
+    /// An existing canvas with the given element id
+    Id(String),
+    /// An existing canvas element
+    Element(HtmlCanvasElement),
+    /// A new canvas appended to the element with the given id
+    AppendToId(String),
+    /// A new canvas appended to the given element
+    AppendTo(Element),
+    /// A new canvas which is not added to the page, e.g. for computations
+    Detached,
+}
+
+impl CanvasTarget {
+    /// Find or create the canvas element
+    pub fn resolve(&self) -> Result<HtmlCanvasElement, JsValue> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| String::from("Unable to get the document!"))?;
+
+        match self {
+            CanvasTarget::Id(id) => document
+                .get_element_by_id(id)
+                .ok_or_else(|| format!("Unable to get Canvas element: {}!", id))?
+                .dyn_into::<HtmlCanvasElement>()
+                .map_err(|_| JsValue::from(format!("Element: {} is not a canvas!", id))),
+            CanvasTarget::Element(canvas) => Ok(canvas.clone()),
+            CanvasTarget::AppendToId(id) => {
+                let container = document
+                    .get_element_by_id(id)
+                    .ok_or_else(|| format!("Unable to get container element: {}!", id))?;
+                CanvasTarget::AppendTo(container).resolve()
+            }
+            CanvasTarget::Detached => Ok(document
+                .create_element("canvas")?
+                .dyn_into::<HtmlCanvasElement>()?),
+            CanvasTarget::AppendTo(container) => {
+                let canvas = document
+                    .create_element("canvas")?
+                    .dyn_into::<HtmlCanvasElement>()?;
+                container.append_child(&canvas)?;
+                Ok(canvas)
+            }
+        }
+    }
+}
+
+impl Default for CanvasTarget {
+    fn default() -> Self {
+        CanvasTarget::Id(String::from("canvas"))
+    }
+}
+
+impl From<&str> for CanvasTarget {
+    fn from(id: &str) -> Self {
+        CanvasTarget::Id(id.to_owned())
+    }
+}
+
+impl From<String> for CanvasTarget {
+    fn from(id: String) -> Self {
+        CanvasTarget::Id(id)
+    }
+}
+
+impl From<HtmlCanvasElement> for CanvasTarget {
+    fn from(canvas: HtmlCanvasElement) -> Self {
+        CanvasTarget::Element(canvas)
+    }
+}
+
+/// Hint to the browser about which GPU to use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PowerPreference {
+    #[default]
+    Default,
+    HighPerformance,
+    LowPower,
+}
+
+impl PowerPreference {
+    fn as_str(self) -> &'static str {
+        match self {
+            PowerPreference::Default => "default",
+            PowerPreference::HighPerformance => "high-performance",
+            PowerPreference::LowPower => "low-power",
+        }
+    }
+}
+
+/// WebGL context creation attributes, defaults match the ones of the browser
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextAttributes {
+    pub alpha: bool,
+    pub antialias: bool,
+    pub depth: bool,
+    pub stencil: bool,
+    pub premultiplied_alpha: bool,
+    pub preserve_drawing_buffer: bool,
+    pub power_preference: PowerPreference,
+    /// WebGL version to ask for
+    pub version: Version,
+}
+
+impl ContextAttributes {
+    /// Build the dictionary given to `getContext`
+    pub fn to_js(&self) -> Result<JsValue, JsValue> {
+        let attributes = Object::new();
+        let set = |key: &str, value: JsValue| Reflect::set(&attributes, &key.into(), &value);
+
+        set("alpha", self.alpha.into())?;
+        set("antialias", self.antialias.into())?;
+        set("depth", self.depth.into())?;
+        set("stencil", self.stencil.into())?;
+        set("premultipliedAlpha", self.premultiplied_alpha.into())?;
+        set("preserveDrawingBuffer", self.preserve_drawing_buffer.into())?;
+        set("powerPreference", self.power_preference.as_str().into())?;
+
+        Ok(attributes.into())
+    }
+}
+
+impl Default for ContextAttributes {
+    fn default() -> Self {
+        ContextAttributes {
+            alpha: true,
+            antialias: true,
+            depth: true,
+            stencil: false,
+            premultiplied_alpha: true,
+            preserve_drawing_buffer: false,
+            power_preference: PowerPreference::Default,
+            version: Version::default(),
+        }
+    }
+}
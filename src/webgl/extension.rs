@@ -0,0 +1,171 @@
+/*!
+WebGL extensions, enabled by name and kept around as their typed objects.
+ */
+
+use wasm_bindgen::JsCast;
+use web_sys::{
+    AngleInstancedArrays, ExtColorBufferFloat, ExtColorBufferHalfFloat, OesElementIndexUint,
+    OesTextureFloat, OesTextureHalfFloat, OesVertexArrayObject, WebglColorBufferFloat,
+    WebglDepthTexture, WebglDrawBuffers,
+};
+
+use super::gl::Gl;
+
+/// The extensions rugl knows how to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Extension {
+    AngleInstancedArrays,
+    OesVertexArrayObject,
+    OesTextureFloat,
+    OesTextureHalfFloat,
+    OesElementIndexUint,
+    WebglDepthTexture,
+    /// Drawing into 32 bit float textures in WebGL 1
+    WebglColorBufferFloat,
+    /// Drawing into 16 and 32 bit float textures in WebGL 2
+    ExtColorBufferFloat,
+    /// Drawing into 16 bit float textures
+    ExtColorBufferHalfFloat,
+    WebglDrawBuffers,
+}
+
+impl Extension {
+    /// Name the extension is requested with
+    pub fn name(self) -> &'static str {
+        match self {
+            Extension::AngleInstancedArrays => "ANGLE_instanced_arrays",
+            Extension::OesVertexArrayObject => "OES_vertex_array_object",
+            Extension::OesTextureFloat => "OES_texture_float",
+            Extension::OesTextureHalfFloat => "OES_texture_half_float",
+            Extension::OesElementIndexUint => "OES_element_index_uint",
+            Extension::WebglDepthTexture => "WEBGL_depth_texture",
+            Extension::WebglColorBufferFloat => "WEBGL_color_buffer_float",
+            Extension::ExtColorBufferFloat => "EXT_color_buffer_float",
+            Extension::ExtColorBufferHalfFloat => "EXT_color_buffer_half_float",
+            Extension::WebglDrawBuffers => "WEBGL_draw_buffers",
+        }
+    }
+
+    /// Whether WebGL 2 has the functionality built in, without an extension object
+    pub fn is_core_in_webgl2(self) -> bool {
+        !matches!(
+            self,
+            Extension::WebglColorBufferFloat
+                | Extension::ExtColorBufferFloat
+                | Extension::ExtColorBufferHalfFloat
+        )
+    }
+}
+
+/// Extensions a design needs, it fails to start without the required ones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtensionRequest {
+    pub required: Vec<Extension>,
+    pub optional: Vec<Extension>,
+}
+
+/// The extensions enabled on a context
+#[derive(Debug, Clone, Default)]
+pub struct Extensions {
+    pub instanced_arrays: Option<AngleInstancedArrays>,
+    pub vertex_array_object: Option<OesVertexArrayObject>,
+    pub texture_float: Option<OesTextureFloat>,
+    pub texture_half_float: Option<OesTextureHalfFloat>,
+    pub element_index_uint: Option<OesElementIndexUint>,
+    pub depth_texture: Option<WebglDepthTexture>,
+    pub webgl_color_buffer_float: Option<WebglColorBufferFloat>,
+    pub color_buffer_float: Option<ExtColorBufferFloat>,
+    pub color_buffer_half_float: Option<ExtColorBufferHalfFloat>,
+    pub draw_buffers: Option<WebglDrawBuffers>,
+    core: bool,
+}
+
+impl Extensions {
+    /// No extensions enabled yet, a WebGL 2 context has the ones promoted to core already
+    pub fn for_context(context: &Gl) -> Self {
+        Extensions {
+            core: context.is_webgl2(),
+            ..Default::default()
+        }
+    }
+
+    /// Enable an extension, returning whether the browser supports it
+    pub fn enable(&mut self, context: &Gl, extension: Extension) -> bool {
+        if self.core && extension.is_core_in_webgl2() {
+            return true;
+        }
+
+        let object = match context.get_extension(extension.name()) {
+            Ok(Some(object)) => object,
+            _ => return false,
+        };
+
+        match extension {
+            Extension::AngleInstancedArrays => {
+                self.instanced_arrays = Some(object.unchecked_into())
+            }
+            Extension::OesVertexArrayObject => {
+                self.vertex_array_object = Some(object.unchecked_into())
+            }
+            Extension::OesTextureFloat => self.texture_float = Some(object.unchecked_into()),
+            Extension::OesTextureHalfFloat => {
+                self.texture_half_float = Some(object.unchecked_into())
+            }
+            Extension::OesElementIndexUint => {
+                self.element_index_uint = Some(object.unchecked_into())
+            }
+            Extension::WebglDepthTexture => self.depth_texture = Some(object.unchecked_into()),
+            Extension::WebglColorBufferFloat => {
+                self.webgl_color_buffer_float = Some(object.unchecked_into())
+            }
+            Extension::ExtColorBufferFloat => {
+                self.color_buffer_float = Some(object.unchecked_into())
+            }
+            Extension::ExtColorBufferHalfFloat => {
+                self.color_buffer_half_float = Some(object.unchecked_into())
+            }
+            Extension::WebglDrawBuffers => self.draw_buffers = Some(object.unchecked_into()),
+        }
+
+        true
+    }
+
+    pub fn is_enabled(&self, extension: Extension) -> bool {
+        if self.core && extension.is_core_in_webgl2() {
+            return true;
+        }
+
+        match extension {
+            Extension::AngleInstancedArrays => self.instanced_arrays.is_some(),
+            Extension::OesVertexArrayObject => self.vertex_array_object.is_some(),
+            Extension::OesTextureFloat => self.texture_float.is_some(),
+            Extension::OesTextureHalfFloat => self.texture_half_float.is_some(),
+            Extension::OesElementIndexUint => self.element_index_uint.is_some(),
+            Extension::WebglDepthTexture => self.depth_texture.is_some(),
+            Extension::WebglColorBufferFloat => self.webgl_color_buffer_float.is_some(),
+            Extension::ExtColorBufferFloat => self.color_buffer_float.is_some(),
+            Extension::ExtColorBufferHalfFloat => self.color_buffer_half_float.is_some(),
+            Extension::WebglDrawBuffers => self.draw_buffers.is_some(),
+        }
+    }
+
+    /// Every enabled extension
+    pub fn enabled(&self) -> Vec<Extension> {
+        [
+            Extension::AngleInstancedArrays,
+            Extension::OesVertexArrayObject,
+            Extension::OesTextureFloat,
+            Extension::OesTextureHalfFloat,
+            Extension::OesElementIndexUint,
+            Extension::WebglDepthTexture,
+            Extension::WebglColorBufferFloat,
+            Extension::ExtColorBufferFloat,
+            Extension::ExtColorBufferHalfFloat,
+            Extension::WebglDrawBuffers,
+        ]
+        .iter()
+        .cloned()
+        .filter(|extension| self.is_enabled(*extension))
+        .collect()
+    }
+}
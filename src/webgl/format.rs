@@ -0,0 +1,227 @@
+/*!
+Texture formats, the unsized ones of WebGL 1 and the sized ones WebGL 2 adds.
+
+Float textures need extensions to be sampled in WebGL 1 and to be drawn into in either version.
+Where a context has none of them, floats in `[0, 1)` can still be stored in `Rgba8` or `Rgba`
+textures, the shader packing them with `rugl_pack_float` from `GLSL_FLOAT_PACKING` and the data
+read back being unpacked with `unpack_float`.
+ */
+
+use web_sys::{OesTextureHalfFloat, WebGl2RenderingContext as Gl2, WebGlRenderingContext as Gl1};
+
+use super::extension::Extension;
+use super::gl::Version;
+
+/// How texels are stored, as the internal format, format and type given to `texImage2D`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextureFormat {
+    #[default]
+    Rgba,
+    Rgb,
+    Luminance,
+    LuminanceAlpha,
+    Alpha,
+    /// 32 bit float RGBA with `OES_texture_float`, WebGL 2 has `Rgba32f` instead
+    RgbaFloat,
+    /// 16 bit float RGBA with `OES_texture_half_float`, WebGL 2 has `Rgba16f` instead
+    RgbaHalfFloat,
+    R8,
+    Rg8,
+    Rgb8,
+    Rgba8,
+    Srgb8Alpha8,
+    R8ui,
+    Rgba8ui,
+    R16f,
+    Rg16f,
+    Rgba16f,
+    R32f,
+    Rg32f,
+    Rgba32f,
+    R32i,
+    Rgba32i,
+    R32ui,
+    Rgba32ui,
+    DepthComponent16,
+    DepthComponent24,
+    DepthComponent32f,
+    Depth24Stencil8,
+}
+
+impl TextureFormat {
+    pub fn internal_format(self) -> u32 {
+        match self {
+            TextureFormat::Rgba | TextureFormat::RgbaFloat | TextureFormat::RgbaHalfFloat => {
+                Gl1::RGBA
+            }
+            TextureFormat::Rgb => Gl1::RGB,
+            TextureFormat::Luminance => Gl1::LUMINANCE,
+            TextureFormat::LuminanceAlpha => Gl1::LUMINANCE_ALPHA,
+            TextureFormat::Alpha => Gl1::ALPHA,
+            TextureFormat::R8 => Gl2::R8,
+            TextureFormat::Rg8 => Gl2::RG8,
+            TextureFormat::Rgb8 => Gl2::RGB8,
+            TextureFormat::Rgba8 => Gl2::RGBA8,
+            TextureFormat::Srgb8Alpha8 => Gl2::SRGB8_ALPHA8,
+            TextureFormat::R8ui => Gl2::R8UI,
+            TextureFormat::Rgba8ui => Gl2::RGBA8UI,
+            TextureFormat::R16f => Gl2::R16F,
+            TextureFormat::Rg16f => Gl2::RG16F,
+            TextureFormat::Rgba16f => Gl2::RGBA16F,
+            TextureFormat::R32f => Gl2::R32F,
+            TextureFormat::Rg32f => Gl2::RG32F,
+            TextureFormat::Rgba32f => Gl2::RGBA32F,
+            TextureFormat::R32i => Gl2::R32I,
+            TextureFormat::Rgba32i => Gl2::RGBA32I,
+            TextureFormat::R32ui => Gl2::R32UI,
+            TextureFormat::Rgba32ui => Gl2::RGBA32UI,
+            TextureFormat::DepthComponent16 => Gl1::DEPTH_COMPONENT16,
+            TextureFormat::DepthComponent24 => Gl2::DEPTH_COMPONENT24,
+            TextureFormat::DepthComponent32f => Gl2::DEPTH_COMPONENT32F,
+            TextureFormat::Depth24Stencil8 => Gl2::DEPTH24_STENCIL8,
+        }
+    }
+
+    /// Layout of the pixel data given when uploading
+    pub fn format(self) -> u32 {
+        match self {
+            TextureFormat::Rgba | TextureFormat::Rgba8 | TextureFormat::Srgb8Alpha8 => Gl1::RGBA,
+            TextureFormat::Rgba16f | TextureFormat::Rgba32f => Gl1::RGBA,
+            TextureFormat::RgbaFloat | TextureFormat::RgbaHalfFloat => Gl1::RGBA,
+            TextureFormat::Rgb | TextureFormat::Rgb8 => Gl1::RGB,
+            TextureFormat::Luminance => Gl1::LUMINANCE,
+            TextureFormat::LuminanceAlpha => Gl1::LUMINANCE_ALPHA,
+            TextureFormat::Alpha => Gl1::ALPHA,
+            TextureFormat::R8 | TextureFormat::R16f | TextureFormat::R32f => Gl2::RED,
+            TextureFormat::Rg8 | TextureFormat::Rg16f | TextureFormat::Rg32f => Gl2::RG,
+            TextureFormat::R8ui | TextureFormat::R32i | TextureFormat::R32ui => Gl2::RED_INTEGER,
+            TextureFormat::Rgba8ui | TextureFormat::Rgba32i | TextureFormat::Rgba32ui => {
+                Gl2::RGBA_INTEGER
+            }
+            TextureFormat::DepthComponent16
+            | TextureFormat::DepthComponent24
+            | TextureFormat::DepthComponent32f => Gl1::DEPTH_COMPONENT,
+            TextureFormat::Depth24Stencil8 => Gl1::DEPTH_STENCIL,
+        }
+    }
+
+    /// Type of the pixel data given when uploading
+    pub fn data_type(self) -> u32 {
+        match self {
+            TextureFormat::R16f | TextureFormat::Rg16f | TextureFormat::Rgba16f => Gl2::HALF_FLOAT,
+            TextureFormat::R32f | TextureFormat::Rg32f | TextureFormat::Rgba32f => Gl1::FLOAT,
+            TextureFormat::DepthComponent32f | TextureFormat::RgbaFloat => Gl1::FLOAT,
+            TextureFormat::RgbaHalfFloat => OesTextureHalfFloat::HALF_FLOAT_OES,
+            TextureFormat::R32i | TextureFormat::Rgba32i => Gl1::INT,
+            TextureFormat::R32ui | TextureFormat::Rgba32ui | TextureFormat::DepthComponent24 => {
+                Gl1::UNSIGNED_INT
+            }
+            TextureFormat::DepthComponent16 => Gl1::UNSIGNED_SHORT,
+            TextureFormat::Depth24Stencil8 => Gl2::UNSIGNED_INT_24_8,
+            _ => Gl1::UNSIGNED_BYTE,
+        }
+    }
+
+    /// The unsized formats are all WebGL 1 has, apart from 16 bit depth
+    pub fn version(self) -> Version {
+        match self {
+            TextureFormat::Rgba
+            | TextureFormat::Rgb
+            | TextureFormat::Luminance
+            | TextureFormat::LuminanceAlpha
+            | TextureFormat::Alpha
+            | TextureFormat::RgbaFloat
+            | TextureFormat::RgbaHalfFloat
+            | TextureFormat::DepthComponent16 => Version::WebGl1,
+            _ => Version::WebGl2,
+        }
+    }
+
+    /// Whether the format can be used with a context of the given version, the unsized float
+    /// formats only exist in WebGL 1
+    pub fn is_available(self, version: Version) -> bool {
+        match self {
+            TextureFormat::RgbaFloat | TextureFormat::RgbaHalfFloat => version == Version::WebGl1,
+            format => version == Version::WebGl2 || format.version() == Version::WebGl1,
+        }
+    }
+
+    pub fn is_float(self) -> bool {
+        match self.data_type() {
+            Gl1::FLOAT | Gl2::HALF_FLOAT | OesTextureHalfFloat::HALF_FLOAT_OES => !self.is_depth(),
+            _ => false,
+        }
+    }
+
+    /// Extension needed to sample the format, if any
+    pub fn texture_extension(self, version: Version) -> Option<Extension> {
+        match (self, version) {
+            (TextureFormat::RgbaFloat, _) => Some(Extension::OesTextureFloat),
+            (TextureFormat::RgbaHalfFloat, _) => Some(Extension::OesTextureHalfFloat),
+            (TextureFormat::DepthComponent16, Version::WebGl1) => {
+                Some(Extension::WebglDepthTexture)
+            }
+            _ => None,
+        }
+    }
+
+    /// Extensions which each let the format be drawn into, empty if it needs none
+    pub fn render_extensions(self, version: Version) -> &'static [Extension] {
+        match (self, version) {
+            (TextureFormat::RgbaFloat, _) => &[Extension::WebglColorBufferFloat],
+            (TextureFormat::RgbaHalfFloat, _) => &[Extension::ExtColorBufferHalfFloat],
+            (TextureFormat::R16f, _) | (TextureFormat::Rg16f, _) | (TextureFormat::Rgba16f, _) => {
+                &[Extension::ExtColorBufferFloat, Extension::ExtColorBufferHalfFloat]
+            }
+            (TextureFormat::R32f, _) | (TextureFormat::Rg32f, _) | (TextureFormat::Rgba32f, _) => {
+                &[Extension::ExtColorBufferFloat]
+            }
+            _ => &[],
+        }
+    }
+
+    /// Whether the texels are integers which have to be sampled with an `isampler` or
+    /// `usampler`
+    pub fn is_integer(self) -> bool {
+        self.format() == Gl2::RED_INTEGER || self.format() == Gl2::RGBA_INTEGER
+    }
+
+    pub fn is_depth(self) -> bool {
+        self.format() == Gl1::DEPTH_COMPONENT || self.format() == Gl1::DEPTH_STENCIL
+    }
+}
+
+/// GLSL functions storing a float in `[0, 1)` in the four bytes of an RGBA8 texel and reading
+/// it back, for contexts which can not draw into float textures
+pub const GLSL_FLOAT_PACKING: &str = "
+vec4 rugl_pack_float(float value) {
+    vec4 packed = fract(value * vec4(1.0, 255.0, 65025.0, 16581375.0));
+    return packed - packed.yzww * vec4(1.0 / 255.0, 1.0 / 255.0, 1.0 / 255.0, 0.0);
+}
+
+float rugl_unpack_float(vec4 packed) {
+    return dot(packed, vec4(1.0, 1.0 / 255.0, 1.0 / 65025.0, 1.0 / 16581375.0));
+}
+";
+
+/// Store a float in `[0, 1)` the way `rugl_pack_float` does, e.g. to upload data for a
+/// shader unpacking it
+pub fn pack_float(value: f32) -> [u8; 4] {
+    let mut rest = f64::from(value.clamp(0.0, 1.0));
+    let mut packed = [0; 4];
+    for byte in &mut packed {
+        rest *= 255.0;
+        let digit = rest.floor().min(255.0);
+        *byte = digit as u8;
+        rest -= digit;
+    }
+    packed
+}
+
+/// Read back a float stored by `rugl_pack_float`
+pub fn unpack_float(packed: [u8; 4]) -> f32 {
+    packed
+        .iter()
+        .rev()
+        .fold(0.0, |rest, byte| (rest + f64::from(*byte)) / 255.0) as f32
+}
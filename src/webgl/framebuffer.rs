@@ -0,0 +1,61 @@
+/*!
+Framebuffers drawing into textures instead of the canvas, with one color attachment per output
+of the fragment shader.
+ */
+
+use crate::webgl::{Backend, Gl, TextureFormat, TextureHandle};
+
+/// Size and color attachments of a framebuffer, a size of 0 takes the size of the drawing
+/// buffer at the time the framebuffer is created
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FramebufferOptions {
+    pub width: i32,
+    pub height: i32,
+    /// Format of the texture behind each `gl_FragData[n]` or `layout(location = n)` output,
+    /// a single RGBA texture if empty
+    pub color: Vec<TextureFormat>,
+}
+
+pub struct Framebuffer<B: Backend = Gl> {
+    object: B::Framebuffer,
+    width: i32,
+    height: i32,
+    colors: Vec<TextureHandle>,
+}
+
+impl<B: Backend> Framebuffer<B> {
+    pub fn new(
+        object: B::Framebuffer,
+        width: i32,
+        height: i32,
+        colors: Vec<TextureHandle>,
+    ) -> Self {
+        Self {
+            object,
+            width,
+            height,
+            colors,
+        }
+    }
+
+    pub fn as_ref(&self) -> &B::Framebuffer {
+        &self.object
+    }
+
+    pub fn set_object(&mut self, object: B::Framebuffer) {
+        self.object = object;
+    }
+
+    pub fn get_width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> i32 {
+        self.height
+    }
+
+    /// The texture of every color attachment, in the order of the outputs
+    pub fn get_colors(&self) -> &[TextureHandle] {
+        &self.colors
+    }
+}
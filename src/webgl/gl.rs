@@ -0,0 +1,231 @@
+/*!
+The WebGL 1 or WebGL 2 context a design is drawn with. Calls both versions share are forwarded
+to whichever context was created, calls only WebGL 2 has go through `webgl2`.
+ */
+
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    WebGl2RenderingContext, WebGlActiveInfo, WebGlBuffer, WebGlFramebuffer, WebGlProgram,
+    WebGlRenderingContext, WebGlShader, WebGlTexture, WebGlUniformLocation,
+};
+
+/// The WebGL version to create a context for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Version {
+    #[default]
+    WebGl1,
+    /// WebGL 2, falling back to WebGL 1 when the browser does not support it
+    WebGl2,
+}
+
+impl Version {
+    /// The version a shader is written for, `#version 300 es` shaders need WebGL 2
+    pub fn of_source(source: &str) -> Self {
+        if source.trim_start().starts_with("#version 300 es") {
+            Version::WebGl2
+        } else {
+            Version::WebGl1
+        }
+    }
+
+    /// The version both shaders of a program are written for, they can not be mixed
+    pub fn of_sources(vertex: &str, fragment: &str) -> Result<Self, String> {
+        let (version, other) = (Self::of_source(vertex), Self::of_source(fragment));
+        if version == other {
+            return Ok(version);
+        }
+
+        let (newer, older) = match version {
+            Version::WebGl2 => ("vertex", "fragment"),
+            Version::WebGl1 => ("fragment", "vertex"),
+        };
+        Err(format!(
+            "The {} shader is written for `#version 300 es` but the {} shader is not!",
+            newer, older
+        ))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Gl {
+    WebGl1(WebGlRenderingContext),
+    WebGl2(WebGl2RenderingContext),
+}
+
+impl Gl {
+    pub fn version(&self) -> Version {
+        match self {
+            Gl::WebGl1(_) => Version::WebGl1,
+            Gl::WebGl2(_) => Version::WebGl2,
+        }
+    }
+
+    pub fn is_webgl2(&self) -> bool {
+        self.version() == Version::WebGl2
+    }
+
+    /// The WebGL 2 context, failing with `what` needing WebGL 2 otherwise
+    pub fn webgl2(&self, what: &str) -> Result<&WebGl2RenderingContext, String> {
+        match self {
+            Gl::WebGl2(context) => Ok(context),
+            Gl::WebGl1(_) => Err(format!("{} need WebGL 2!", what)),
+        }
+    }
+}
+
+macro_rules! forward {
+    ($(fn $name:ident(&self $(, $arg:ident: $type:ty)*) $(-> $ret:ty)*;)*) => {
+        impl Gl {
+            $(
+                // As many arguments as the WebGL call being forwarded
+                #[allow(clippy::too_many_arguments)]
+                pub fn $name(&self $(, $arg: $type)*) $(-> $ret)* {
+                    match self {
+                        Gl::WebGl1(context) => context.$name($($arg),*),
+                        Gl::WebGl2(context) => context.$name($($arg),*),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+forward! {
+    fn create_shader(&self, gl_type: u32) -> Option<WebGlShader>;
+    fn shader_source(&self, shader: &WebGlShader, source: &str);
+    fn compile_shader(&self, shader: &WebGlShader);
+    fn get_shader_parameter(&self, shader: &WebGlShader, pname: u32) -> JsValue;
+    fn get_shader_info_log(&self, shader: &WebGlShader) -> Option<String>;
+    fn create_program(&self) -> Option<WebGlProgram>;
+    fn attach_shader(&self, program: &WebGlProgram, shader: &WebGlShader);
+    fn link_program(&self, program: &WebGlProgram);
+    fn get_program_parameter(&self, program: &WebGlProgram, pname: u32) -> JsValue;
+    fn get_program_info_log(&self, program: &WebGlProgram) -> Option<String>;
+    fn use_program(&self, program: Option<&WebGlProgram>);
+    fn get_active_attrib(&self, program: &WebGlProgram, index: u32) -> Option<WebGlActiveInfo>;
+    fn get_active_uniform(&self, program: &WebGlProgram, index: u32) -> Option<WebGlActiveInfo>;
+    fn get_attrib_location(&self, program: &WebGlProgram, name: &str) -> i32;
+    fn get_uniform_location(
+        &self,
+        program: &WebGlProgram,
+        name: &str
+    ) -> Option<WebGlUniformLocation>;
+    fn create_buffer(&self) -> Option<WebGlBuffer>;
+    fn bind_buffer(&self, target: u32, buffer: Option<&WebGlBuffer>);
+    fn buffer_data_with_array_buffer_view(&self, target: u32, data: &Object, usage: u32);
+    fn vertex_attrib_pointer_with_i32(
+        &self,
+        index: u32,
+        size: i32,
+        gl_type: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32
+    );
+    fn enable_vertex_attrib_array(&self, index: u32);
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32);
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+    fn clear(&self, mask: u32);
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32);
+    fn uniform1fv_with_f32_array(&self, location: Option<&WebGlUniformLocation>, data: &mut [f32]);
+    fn uniform2fv_with_f32_array(&self, location: Option<&WebGlUniformLocation>, data: &mut [f32]);
+    fn uniform3fv_with_f32_array(&self, location: Option<&WebGlUniformLocation>, data: &mut [f32]);
+    fn uniform4fv_with_f32_array(&self, location: Option<&WebGlUniformLocation>, data: &mut [f32]);
+    fn uniform1iv_with_i32_array(&self, location: Option<&WebGlUniformLocation>, data: &mut [i32]);
+    fn uniform2iv_with_i32_array(&self, location: Option<&WebGlUniformLocation>, data: &mut [i32]);
+    fn uniform3iv_with_i32_array(&self, location: Option<&WebGlUniformLocation>, data: &mut [i32]);
+    fn uniform4iv_with_i32_array(&self, location: Option<&WebGlUniformLocation>, data: &mut [i32]);
+    fn uniform_matrix2fv_with_f32_array(
+        &self,
+        location: Option<&WebGlUniformLocation>,
+        transpose: bool,
+        data: &mut [f32]
+    );
+    fn uniform_matrix3fv_with_f32_array(
+        &self,
+        location: Option<&WebGlUniformLocation>,
+        transpose: bool,
+        data: &mut [f32]
+    );
+    fn uniform_matrix4fv_with_f32_array(
+        &self,
+        location: Option<&WebGlUniformLocation>,
+        transpose: bool,
+        data: &mut [f32]
+    );
+    fn create_texture(&self) -> Option<WebGlTexture>;
+    fn bind_texture(&self, target: u32, texture: Option<&WebGlTexture>);
+    fn active_texture(&self, texture: u32);
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32);
+    fn tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&Object>
+    ) -> Result<(), JsValue>;
+    fn create_framebuffer(&self) -> Option<WebGlFramebuffer>;
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&WebGlFramebuffer>);
+    fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<&WebGlTexture>,
+        level: i32
+    );
+    fn check_framebuffer_status(&self, target: u32) -> u32;
+    fn read_pixels_with_opt_array_buffer_view(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&Object>
+    ) -> Result<(), JsValue>;
+    fn get_extension(&self, name: &str) -> Result<Option<Object>, JsValue>;
+    fn get_supported_extensions(&self) -> Option<Array>;
+    fn get_parameter(&self, pname: u32) -> Result<JsValue, JsValue>;
+    fn is_context_lost(&self) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERTEX_100: &str = "attribute vec2 position;";
+    const VERTEX_300: &str = "
+        #version 300 es
+        in vec2 position;
+    ";
+    const FRAGMENT_100: &str = "void main() { gl_FragColor = vec4(1.0); }";
+    const FRAGMENT_300: &str = "#version 300 es\nout vec4 color;";
+
+    #[test]
+    fn shaders_of_the_same_version_agree() {
+        assert_eq!(
+            Version::of_sources(VERTEX_100, FRAGMENT_100),
+            Ok(Version::WebGl1)
+        );
+        assert_eq!(
+            Version::of_sources(VERTEX_300, FRAGMENT_300),
+            Ok(Version::WebGl2)
+        );
+    }
+
+    #[test]
+    fn mixing_versions_names_the_newer_shader() {
+        let err = Version::of_sources(VERTEX_300, FRAGMENT_100).unwrap_err();
+        assert!(err.starts_with("The vertex shader"), "{}", err);
+        let err = Version::of_sources(VERTEX_100, FRAGMENT_300).unwrap_err();
+        assert!(err.starts_with("The fragment shader"), "{}", err);
+    }
+}
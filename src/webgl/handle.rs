@@ -0,0 +1,239 @@
+/*!
+Typed handles into the resources owned by a `WebGlContext`.
+
+Names are only resolved once while a design is being set up, afterwards every
+per-frame operation goes through a handle, which is a plain index plus a
+generation so stale handles are rejected instead of aliasing a new resource.
+ */
+
+use std::marker::PhantomData;
+
+/// A key into a `SlotMap`
+pub trait Key: Copy {
+    fn new(index: u32, generation: u32) -> Self;
+    fn index(&self) -> u32;
+    fn generation(&self) -> u32;
+}
+
+macro_rules! new_handle {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name {
+            index: u32,
+            generation: u32,
+        }
+
+        impl Key for $name {
+            fn new(index: u32, generation: u32) -> Self {
+                Self { index, generation }
+            }
+
+            fn index(&self) -> u32 {
+                self.index
+            }
+
+            fn generation(&self) -> u32 {
+                self.generation
+            }
+        }
+    };
+}
+
+new_handle!(
+    /// Handle to an attribute and the buffer holding its data
+    AttributeHandle
+);
+new_handle!(
+    /// Handle to a uniform and its location in a program
+    UniformHandle
+);
+new_handle!(
+    /// Handle to a raw `WebGlBuffer`
+    BufferHandle
+);
+new_handle!(
+    /// Handle to a linked `Program`
+    ProgramHandle
+);
+new_handle!(
+    /// Handle to the attribute bindings of a draw
+    VertexArrayHandle
+);
+new_handle!(
+    /// Handle to a `UniformBlock` shared between programs
+    UniformBlockHandle
+);
+new_handle!(
+    /// Handle to a `TransformFeedback` capturing varyings into attributes
+    TransformFeedbackHandle
+);
+new_handle!(
+    /// Handle to a `Texture`
+    TextureHandle
+);
+new_handle!(
+    /// Handle to a `Framebuffer` and the textures it draws into
+    FramebufferHandle
+);
+
+struct Slot<V> {
+    generation: u32,
+    value: Option<V>,
+}
+
+/// A generational arena, inserting returns a key which stays valid until the
+/// value is removed
+pub struct SlotMap<K: Key, V> {
+    slots: Vec<Slot<V>>,
+    free: Vec<u32>,
+    len: usize,
+    _key: PhantomData<K>,
+}
+
+impl<K: Key, V> SlotMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            _key: PhantomData,
+        }
+    }
+
+    pub fn insert(&mut self, value: V) -> K {
+        self.len += 1;
+
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.value = Some(value);
+                K::new(index, slot.generation)
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                K::new(self.slots.len() as u32 - 1, 0)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let slot = self.slots.get_mut(key.index() as usize)?;
+        if slot.generation != key.generation() {
+            return None;
+        }
+
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index());
+        self.len -= 1;
+
+        Some(value)
+    }
+
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.slots
+            .get(key.index() as usize)
+            .filter(|slot| slot.generation == key.generation())
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.slots
+            .get_mut(key.index() as usize)
+            .filter(|slot| slot.generation == key.generation())
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    pub fn contains_key(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut V)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let generation = slot.generation;
+                slot.value
+                    .as_mut()
+                    .map(|value| (K::new(index as u32, generation), value))
+            })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.value
+                .as_ref()
+                .map(|value| (K::new(index as u32, slot.generation), value))
+        })
+    }
+}
+
+impl<K: Key, V> Default for SlotMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inserted_values_are_found_until_removed() {
+        let mut map: SlotMap<BufferHandle, &str> = SlotMap::new();
+        let first = map.insert("first");
+        let second = map.insert("second");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(first), Some(&"first"));
+        assert_eq!(map.get(second), Some(&"second"));
+
+        *map.get_mut(second).unwrap() = "changed";
+        assert_eq!(map.remove(second), Some("changed"));
+        assert_eq!(map.remove(second), None);
+        assert!(!map.contains_key(second));
+        assert_eq!(map.len(), 1);
+        assert_eq!(
+            map.iter().map(|(_, value)| *value).collect::<Vec<_>>(),
+            ["first"]
+        );
+    }
+
+    #[test]
+    fn reused_slots_get_a_new_generation() {
+        let mut map: SlotMap<TextureHandle, u32> = SlotMap::new();
+        let old = map.insert(1);
+        map.remove(old);
+
+        let new = map.insert(2);
+        assert_eq!(new.index(), old.index());
+        assert_ne!(new.generation(), old.generation());
+        assert_eq!(map.get(new), Some(&2));
+    }
+
+    #[test]
+    fn stale_handles_do_not_alias_the_new_value() {
+        let mut map: SlotMap<ProgramHandle, u32> = SlotMap::new();
+        let old = map.insert(1);
+        map.remove(old);
+        let new = map.insert(2);
+
+        assert_eq!(map.get(old), None);
+        assert_eq!(map.get_mut(old), None);
+        assert_eq!(map.remove(old), None);
+        assert_eq!(map.get(new), Some(&2));
+        assert_eq!(map.len(), 1);
+    }
+}
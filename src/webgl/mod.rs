@@ -1,66 +1,256 @@
 mod attribute;
+mod backend;
 /// Webassembly Context
 mod buffer;
+mod canvas;
+mod extension;
+mod format;
+mod framebuffer;
+mod gl;
+mod handle;
 mod program;
 mod qualifier;
+mod reflect;
+mod resize;
 mod shader;
+mod std140;
+mod texture;
+mod transform_feedback;
 mod uniform;
+mod uniform_block;
+mod vertex_array;
 
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::mem::size_of_val;
+use std::mem::size_of;
 
 pub use attribute::Attribute;
+pub use backend::Backend;
 pub use buffer::{Buffer, BufferInternal};
+pub use canvas::{CanvasTarget, ContextAttributes, PowerPreference};
+pub use extension::{Extension, ExtensionRequest, Extensions};
+pub use format::{pack_float, unpack_float, TextureFormat, GLSL_FLOAT_PACKING};
+pub use framebuffer::{Framebuffer, FramebufferOptions};
+pub use gl::{Gl, Version};
+pub use handle::{
+    AttributeHandle, BufferHandle, FramebufferHandle, Key, ProgramHandle, SlotMap, TextureHandle,
+    TransformFeedbackHandle, UniformBlockHandle, UniformHandle, VertexArrayHandle,
+};
 pub use program::Program;
+pub use reflect::{ActiveInfo, BlockInfo, GlslType, Scalar};
+pub use resize::{ResizeMode, ResizeWatcher, Viewport};
 pub use shader::{Shader, ShaderType};
-pub use uniform::{Uniform, UniformInner};
+pub use std140::{Std140, Std140Writer};
+pub use texture::Texture;
+pub use transform_feedback::TransformFeedback;
+pub use uniform::{Builtin, Sampler2D, SamplerCube, Uniform, UniformInner, UniformValue};
+pub use uniform_block::UniformBlock;
+pub use vertex_array::VertexArray;
 
 use js_sys::*;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{WebGlBuffer, WebGlRenderingContext};
-
-pub struct WebGlContext {
-    context: WebGlRenderingContext,
-    _canvas: web_sys::HtmlCanvasElement,
-    program: Program,
-    attributes: HashMap<String, Buffer>,
-    uniforms: HashMap<String, Buffer>,
+use web_sys::{WebGl2RenderingContext, WebGlRenderingContext, WebglLoseContext};
+
+use crate::image::Image;
+
+pub struct WebGlContext<B: Backend = Gl> {
+    context: B,
+    canvas: web_sys::HtmlCanvasElement,
+    resize_mode: ResizeMode,
+    resize_watcher: Option<ResizeWatcher>,
+    viewport: Viewport,
+    interpolation: f64,
+    extensions: Extensions,
+    programs: SlotMap<ProgramHandle, Program<B>>,
+    program: Option<ProgramHandle>,
+    buffers: SlotMap<BufferHandle, B::Buffer>,
+    attributes: SlotMap<AttributeHandle, Buffer<B>>,
+    uniforms: SlotMap<UniformHandle, Buffer<B>>,
+    vertex_arrays: SlotMap<VertexArrayHandle, VertexArray<B>>,
+    uniform_blocks: SlotMap<UniformBlockHandle, UniformBlock>,
+    transform_feedbacks: SlotMap<TransformFeedbackHandle, TransformFeedback<B>>,
+    textures: SlotMap<TextureHandle, Texture<B>>,
+    framebuffers: SlotMap<FramebufferHandle, Framebuffer<B>>,
+    framebuffer: Option<FramebufferHandle>,
+    attribute_names: HashMap<String, AttributeHandle>,
+    uniform_names: HashMap<String, UniformHandle>,
     memory: JsValue,
+    /// Kept from `simulate_context_loss`, a lost context has no extensions to hand out
+    lose_context: Option<WebglLoseContext>,
 }
 
 impl WebGlContext {
     pub fn new<'a, T: Into<&'a str>>(id: T) -> Result<Self, JsValue> {
-        let document = web_sys::window().unwrap().document().unwrap();
-        let canvas = document
-            .get_element_by_id(id.into())
-            .ok_or_else(|| String::from("Unable to get Canvas element!"))?;
-        let canvas: web_sys::HtmlCanvasElement =
-            canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
-
-        let context = canvas
-            .get_context("webgl")?
-            .unwrap()
-            .dyn_into::<WebGlRenderingContext>()?;
-
-        let window = web_sys::window().unwrap();
-        canvas.set_width(window.inner_width().unwrap().as_f64().unwrap() as _);
-        canvas.set_height(window.inner_height().unwrap().as_f64().unwrap() as _);
-        context.viewport(0, 0, window.inner_width().unwrap().as_f64().unwrap() as _, window.inner_height().unwrap().as_f64().unwrap() as _);
+        Self::with_target(&CanvasTarget::from(id.into()), &ContextAttributes::default())
+    }
+
+    /// Create a context on any canvas target with the given creation attributes
+    pub fn with_target(
+        target: &CanvasTarget,
+        attributes: &ContextAttributes,
+    ) -> Result<Self, JsValue> {
+        let canvas = target.resolve()?;
+        let options = attributes.to_js()?;
+
+        // Fall back to WebGL 1 when WebGL 2 is not available
+        let webgl2 = match attributes.version {
+            Version::WebGl2 => canvas.get_context_with_context_options("webgl2", &options)?,
+            Version::WebGl1 => None,
+        };
+        let context = match webgl2 {
+            Some(context) => Gl::WebGl2(context.dyn_into::<WebGl2RenderingContext>()?),
+            None => Gl::WebGl1(
+                canvas
+                    .get_context_with_context_options("webgl", &options)?
+                    .ok_or_else(|| String::from("WebGL is not supported!"))?
+                    .dyn_into::<WebGlRenderingContext>()?,
+            ),
+        };
+
+        let window = web_sys::window().ok_or_else(|| String::from("No global window exists!"))?;
+        let resize_watcher = ResizeWatcher::new(&window, &canvas).ok();
 
         let memory = wasm_bindgen::memory()
             .dyn_into::<WebAssembly::Memory>()?
             .buffer();
 
-        Ok(WebGlContext {
-            context,
-            _canvas: canvas,
-            program: Program::empty(),
-            attributes: HashMap::new(),
-            uniforms: HashMap::new(),
-            memory,
-        })
+        let extensions = Extensions::for_context(&context);
+        let mut context =
+            WebGlContext::with_backend(context, extensions, canvas, resize_watcher, memory);
+        context.resize()?;
+
+        Ok(context)
+    }
+
+    /// Change how the drawing buffer is sized, applying it right away
+    pub fn set_resize_mode(&mut self, mode: ResizeMode) -> Result<(), JsValue> {
+        self.resize_mode = mode;
+        self.resize()?;
+        Ok(())
+    }
+
+    /// Resize only when the canvas could have changed size since the last resize
+    pub fn resize_if_needed(&mut self) -> Result<bool, JsValue> {
+        match &self.resize_watcher {
+            Some(watcher) if !watcher.take_stale() => Ok(false),
+            _ => self.resize(),
+        }
+    }
+
+    /// Be notified whenever the canvas could have changed size, e.g. to draw static designs
+    /// again, `None` removes the listener
+    pub fn on_resize(&self, listener: Option<Box<Fn()>>) {
+        if let Some(watcher) = &self.resize_watcher {
+            watcher.set_listener(listener);
+        }
+    }
+
+    /// Bring the drawing buffer and viewport up to date with the size of the canvas,
+    /// returning whether anything changed
+    pub fn resize(&mut self) -> Result<bool, JsValue> {
+        let window = web_sys::window().ok_or_else(|| String::from("No global window exists!"))?;
+        let viewport = self.resize_mode.apply(&window, &self.canvas)?;
+
+        if viewport == self.viewport {
+            return Ok(false);
+        }
+
+        // A framebuffer being drawn into keeps its own viewport
+        if self.framebuffer.is_none() {
+            self.context
+                .viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+        }
+        self.viewport = viewport;
+
+        Ok(true)
+    }
+
+    /// Names of every extension the browser supports on this context
+    pub fn extensions(&self) -> Vec<String> {
+        self.context
+            .get_supported_extensions()
+            .map(|names| names.iter().filter_map(|name| name.as_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Enable an extension, failing if the browser does not support it
+    pub fn enable_extension(&mut self, extension: Extension) -> Result<(), String> {
+        if self.extensions.enable(&self.context, extension) {
+            Ok(())
+        } else {
+            Err(format!("Extension: {} is not supported!", extension.name()))
+        }
+    }
+
+    /// Enable every required extension, failing on the first unsupported one, and as many
+    /// optional extensions as possible
+    pub fn request_extensions(&mut self, request: &ExtensionRequest) -> Result<(), String> {
+        for extension in &request.required {
+            self.enable_extension(*extension)?;
+        }
+
+        for extension in &request.optional {
+            self.extensions.enable(&self.context, *extension);
+        }
+
+        Ok(())
+    }
+
+    pub fn is_context_lost(&self) -> bool {
+        self.context.is_context_lost()
+    }
+
+    /// Recreate every program, buffer and uniform after the context was restored
+    pub fn restore(&mut self) -> Result<(), String> {
+        let context = &self.context;
+
+        // Extension objects belong to the lost context, get them again
+        let enabled = self.extensions.enabled();
+        self.extensions = Extensions::for_context(context);
+        for extension in enabled {
+            self.extensions.enable(context, extension);
+        }
+
+        self.restore_resources()?;
+
+        // Force the viewport to be set again
+        self.viewport = Viewport::default();
+        self.resize().map_err(|err| format!("{:?}", err))?;
+
+        Ok(())
+    }
+
+    /// Lose the context on purpose through `WEBGL_lose_context`, to exercise restoring
+    pub fn simulate_context_loss(&mut self) -> Result<(), String> {
+        let extension = self
+            .context
+            .get_extension("WEBGL_lose_context")
+            .ok()
+            .and_then(|extension| extension)
+            .map(|extension| extension.unchecked_into::<WebglLoseContext>())
+            .ok_or_else(|| String::from("WEBGL_lose_context is not supported!"))?;
+        extension.lose_context();
+        self.lose_context = Some(extension);
+        Ok(())
+    }
+
+    /// Restore a context lost through `simulate_context_loss`
+    pub fn simulate_context_restore(&mut self) -> Result<(), String> {
+        self.lose_context
+            .take()
+            .ok_or_else(|| String::from("The context was not lost through simulate_context_loss!"))?
+            .restore_context();
+        Ok(())
+    }
+
+    /// Set how far the frame being drawn is between two fixed ticks
+    pub fn set_interpolation(&mut self, alpha: f64) {
+        self.interpolation = alpha;
+    }
+
+    pub fn canvas(&self) -> &web_sys::HtmlCanvasElement {
+        &self.canvas
     }
 
     /// Compile shaders
@@ -79,64 +269,804 @@ impl WebGlContext {
         Program::new(&self.context, shaders.into_iter())
     }
 
-    /// Link shaders to program, adding the program to our internal hashmap
+    /// Link shaders to program, adding the program to our internal slot map
     pub fn link_and_add_program<'a, Shaders: IntoIterator<Item = &'a Shader>>(
         &mut self,
         shaders: Shaders,
-    ) -> Result<(), String> {
+    ) -> Result<ProgramHandle, String> {
         let program = self.link_program(shaders)?;
-        let _ = std::mem::replace(&mut self.program, program);
+        self.add_program(program)
+    }
+
+    /// Link shaders to a program capturing the named varyings through transform feedback,
+    /// adding the program to our internal slot map, needs WebGL 2
+    pub fn link_and_add_program_with_varyings<'a, Shaders, Name>(
+        &mut self,
+        shaders: Shaders,
+        varyings: &[Name],
+    ) -> Result<ProgramHandle, String>
+    where
+        Shaders: IntoIterator<Item = &'a Shader>,
+        Name: AsRef<str>,
+    {
+        let program = Program::with_varyings(&self.context, shaders, varyings)?;
+        self.add_program(program)
+    }
+
+    /// Create an attribute from its data, `size` being the number of components per element
+    pub fn create_buffer_with_data<'a, Name: Into<Cow<'a, str>>, Type: FromSlice>(
+        &mut self,
+        name: Name,
+        data: Type,
+        size: i32,
+    ) -> Result<AttributeHandle, String> {
+        let data = FromSlice::from_slice(&mut self.memory, data);
+        self.insert_attribute(name, data, size)
+    }
+
+    /// Clear and set background color, integer textures of the framebuffer being drawn into
+    /// are cleared to the color cast to integers
+    pub fn clear_with_color(&self, color: [f64; 4]) {
+        let framebuffer = self
+            .framebuffer
+            .and_then(|handle| self.framebuffers.get(handle));
+
+        if let (Some(framebuffer), Gl::WebGl2(context)) = (framebuffer, &self.context) {
+            let formats: Vec<TextureFormat> = framebuffer
+                .get_colors()
+                .iter()
+                .filter_map(|texture| self.textures.get(*texture))
+                .map(|texture| texture.get_format())
+                .collect();
+
+            if formats.iter().any(|format| format.is_integer()) {
+                for (index, format) in formats.into_iter().enumerate() {
+                    let (buffer, index) = (WebGl2RenderingContext::COLOR, index as i32);
+                    if !format.is_integer() {
+                        let mut values: Vec<f32> = color.iter().map(|value| *value as _).collect();
+                        context.clear_bufferfv_with_f32_array(buffer, index, &mut values);
+                    } else if format.data_type() == WebGlRenderingContext::INT {
+                        let mut values: Vec<i32> = color.iter().map(|value| *value as _).collect();
+                        context.clear_bufferiv_with_i32_array(buffer, index, &mut values);
+                    } else {
+                        let mut values: Vec<u32> = color.iter().map(|value| *value as _).collect();
+                        context.clear_bufferuiv_with_u32_array(buffer, index, &mut values);
+                    }
+                }
+                return;
+            }
+        }
+
+        self.context.clear_color(color[0] as _, color[1] as _, color[2] as _, color[3] as _);
+        self.context.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+    }
+
+    /// Create a uniform block bound to every program declaring a block with this name, needs
+    /// WebGL 2
+    pub fn create_uniform_block<T: Std140>(
+        &mut self,
+        name: &str,
+        value: &T,
+    ) -> Result<UniformBlockHandle, String> {
+        self.context.webgl2("Uniform blocks")?;
+
+        let binding = self
+            .uniform_blocks
+            .iter()
+            .map(|(_, block)| block.get_binding() + 1)
+            .max()
+            .unwrap_or(0);
+        let buffer = self.create_buffer()?;
+        let handle = self.uniform_blocks.insert(UniformBlock::new(
+            name.to_owned(),
+            binding,
+            buffer,
+            Std140Writer::pack(value),
+        ));
+
+        self.upload_uniform_block(handle)?;
+        self.bind_uniform_block(handle)?;
+
+        Ok(handle)
+    }
+
+    /// Replace the contents of a uniform block, every program using it sees the new values
+    pub fn update_uniform_block<T: Std140>(
+        &mut self,
+        handle: UniformBlockHandle,
+        value: &T,
+    ) -> Result<(), String> {
+        self.uniform_blocks
+            .get_mut(handle)
+            .ok_or_else(|| String::from("Uniform block does not exist!"))?
+            .set_data(Std140Writer::pack(value));
+
+        self.upload_uniform_block(handle)
+    }
+
+    /// Enable the extension a format needs to be sampled, returning whether the context
+    /// supports textures of the format
+    pub fn enable_texture_format(&mut self, format: TextureFormat) -> bool {
+        let version = self.version();
+        if !format.is_available(version) {
+            return false;
+        }
+
+        match format.texture_extension(version) {
+            Some(extension) => self.extensions.enable(&self.context, extension),
+            None => true,
+        }
+    }
+
+    /// Enable an extension a format needs to be drawn into, returning whether the context
+    /// supports drawing into textures of the format
+    pub fn enable_render_format(&mut self, format: TextureFormat) -> bool {
+        if !self.enable_texture_format(format) {
+            return false;
+        }
+
+        let extensions = format.render_extensions(self.version());
+        extensions.is_empty()
+            || extensions
+                .iter()
+                .any(|extension| self.extensions.enable(&self.context, *extension))
+    }
+
+    /// The most precise RGBA format the context can draw into, enabling what it needs: 32 bit
+    /// floats, then 16 bit floats and otherwise 8 bits a channel, which floats in `[0, 1)` are
+    /// packed into with `GLSL_FLOAT_PACKING`
+    pub fn float_render_format(&mut self) -> TextureFormat {
+        let candidates = match self.version() {
+            Version::WebGl1 => [
+                TextureFormat::RgbaFloat,
+                TextureFormat::RgbaHalfFloat,
+                TextureFormat::Rgba,
+            ],
+            Version::WebGl2 => [
+                TextureFormat::Rgba32f,
+                TextureFormat::Rgba16f,
+                TextureFormat::Rgba8,
+            ],
+        };
+
+        candidates
+            .iter()
+            .cloned()
+            .find(|format| self.enable_render_format(*format))
+            .unwrap_or_default()
+    }
+
+    /// Create a texture holding the given texels, row by row from the bottom, floats can be
+    /// given for half float formats on WebGL 2 while WebGL 1 takes their bits as `u16`s
+    pub fn create_texture_with_data<Type: FromSlice>(
+        &mut self,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        data: Type,
+    ) -> Result<TextureHandle, String> {
+        if data.is_empty() {
+            return Err(String::from("Texture data is empty!"));
+        }
+
+        let data = FromSlice::from_slice(&mut self.memory, data);
+        self.insert_texture(width, height, format, Some(data))
+    }
+
+    /// Replace the texels of a texture, which keeps its size and format
+    pub fn set_texture_data<Type: FromSlice>(
+        &mut self,
+        handle: TextureHandle,
+        data: Type,
+    ) -> Result<(), String> {
+        if data.is_empty() {
+            return Err(String::from("Texture data is empty!"));
+        }
+
+        let data = FromSlice::from_slice(&mut self.memory, data);
+        self.textures
+            .get_mut(handle)
+            .ok_or_else(|| String::from("Texture does not exist!"))?
+            .set_data(Some(data));
+
+        self.allocate_texture(handle)
+    }
+
+    /// Bind a texture to a texture unit, for a sampler uniform given the same unit
+    pub fn bind_texture(&self, handle: TextureHandle, unit: u32) -> Result<(), String> {
+        let texture = self
+            .textures
+            .get(handle)
+            .ok_or_else(|| String::from("Texture does not exist!"))?;
+
+        self.context
+            .active_texture(WebGlRenderingContext::TEXTURE0 + unit);
+        self.context
+            .bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(texture.as_ref()));
+        Ok(())
+    }
+
+    /// Read a rectangle of the canvas, or of the first color output of a framebuffer, as four
+    /// bytes per pixel from the bottom row. The canvas is cleared once it is shown, so read it
+    /// right after drawing or create the context with `preserve_drawing_buffer`
+    pub fn read_pixels(
+        &self,
+        framebuffer: Option<FramebufferHandle>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<Vec<u8>, String> {
+        match framebuffer.map(|handle| self.framebuffer_format(handle)) {
+            Some(Ok(format)) if format.is_float() || format.is_integer() => {
+                return Err(format!("Framebuffer drawing into {:?} is not read as bytes!", format))
+            }
+            Some(Err(err)) => return Err(err),
+            _ => (),
+        }
+
+        let pixels = Uint8Array::new_with_length((width.max(0) * height.max(0) * 4) as u32);
+        self.read_pixels_into(
+            framebuffer,
+            [x, y, width, height],
+            WebGlRenderingContext::UNSIGNED_BYTE,
+            &pixels,
+        )?;
+        Ok(pixels.to_vec())
+    }
+
+    /// Read a rectangle of a framebuffer drawing into a float format, as four floats per pixel
+    /// from the bottom row
+    pub fn read_pixels_f32(
+        &self,
+        framebuffer: FramebufferHandle,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<Vec<f32>, String> {
+        let format = self.framebuffer_format(framebuffer)?;
+        if !format.is_float() {
+            return Err(format!("Framebuffer drawing into {:?} is not read as floats!", format));
+        }
+
+        let pixels = Float32Array::new_with_length((width.max(0) * height.max(0) * 4) as u32);
+        self.read_pixels_into(
+            Some(framebuffer),
+            [x, y, width, height],
+            WebGlRenderingContext::FLOAT,
+            &pixels,
+        )?;
+        Ok(pixels.to_vec())
+    }
+
+    /// Read the whole canvas, or the first color output of a framebuffer, as an image
+    pub fn screenshot(&self, framebuffer: Option<FramebufferHandle>) -> Result<Image, String> {
+        let (width, height) = match framebuffer {
+            Some(handle) => self
+                .framebuffers
+                .get(handle)
+                .map(|framebuffer| (framebuffer.get_width(), framebuffer.get_height()))
+                .ok_or_else(|| String::from("Framebuffer does not exist!"))?,
+            None => (self.canvas.width() as i32, self.canvas.height() as i32),
+        };
+
+        let pixels = self.read_pixels(framebuffer, 0, 0, width, height)?;
+        Image::from_bottom_up(width as u32, height as u32, pixels)
+    }
+
+    fn framebuffer_format(&self, handle: FramebufferHandle) -> Result<TextureFormat, String> {
+        self.framebuffers
+            .get(handle)
+            .and_then(|framebuffer| framebuffer.get_colors().first())
+            .and_then(|texture| self.textures.get(*texture))
+            .map(Texture::get_format)
+            .ok_or_else(|| String::from("Framebuffer does not exist!"))
+    }
+
+    fn read_pixels_into(
+        &self,
+        framebuffer: Option<FramebufferHandle>,
+        [x, y, width, height]: [i32; 4],
+        data_type: u32,
+        pixels: &Object,
+    ) -> Result<(), String> {
+        if width < 0 || height < 0 {
+            return Err(format!("Unable to read {} by {} pixels!", width, height));
+        }
+
+        let target = WebGlRenderingContext::FRAMEBUFFER;
+        let object = match framebuffer {
+            Some(handle) => Some(
+                self.framebuffers
+                    .get(handle)
+                    .ok_or_else(|| String::from("Framebuffer does not exist!"))?
+                    .as_ref(),
+            ),
+            None => None,
+        };
+        self.context.bind_framebuffer(target, object);
+        let read = self.context.read_pixels_with_opt_array_buffer_view(
+            x,
+            y,
+            width,
+            height,
+            WebGlRenderingContext::RGBA,
+            data_type,
+            Some(pixels),
+        );
+
+        // Draws keep going to the framebuffer bound before
+        let bound = self
+            .framebuffer
+            .and_then(|handle| self.framebuffers.get(handle))
+            .map(Framebuffer::as_ref);
+        self.context.bind_framebuffer(target, bound);
+
+        read.map_err(|err| format!("Unable to read pixels: {:?}", err))
+    }
+
+    /// Draw triangles
+    pub fn draw_triangles(&self, count: i32) {
+        self.context.draw_arrays(WebGlRenderingContext::TRIANGLES, 0, count);
+    }
+
+    /// Draw `instances` copies of the triangles, requires `ANGLE_instanced_arrays` on WebGL 1
+    pub fn draw_triangles_instanced(&self, count: i32, instances: i32) -> Result<(), String> {
+        if let Gl::WebGl2(context) = &self.context {
+            context.draw_arrays_instanced(WebGlRenderingContext::TRIANGLES, 0, count, instances);
+            return Ok(());
+        }
+
+        let instanced_arrays = self.extensions.instanced_arrays.as_ref().ok_or_else(|| {
+            format!(
+                "Instanced drawing requires {}!",
+                Extension::AngleInstancedArrays.name()
+            )
+        })?;
+
+        instanced_arrays.draw_arrays_instanced_angle(
+            WebGlRenderingContext::TRIANGLES,
+            0,
+            count,
+            instances,
+        );
+        Ok(())
+    }
+
+    /// Capture the varyings of the program in use into the given attributes, the first
+    /// varying into the first attribute and so on, needs WebGL 2
+    pub fn create_transform_feedback(
+        &mut self,
+        captures: &[AttributeHandle],
+    ) -> Result<TransformFeedbackHandle, String> {
+        self.context.webgl2("Transform feedback")?;
+
+        let varyings = self.current()?.get_varyings().to_vec();
+        if varyings.len() != captures.len() {
+            return Err(format!(
+                "The program captures {} varyings but {} attributes were given!",
+                varyings.len(),
+                captures.len()
+            ));
+        }
+
+        for (varying, handle) in varyings.iter().zip(captures) {
+            let attribute = self
+                .attributes
+                .get(*handle)
+                .ok_or_else(|| String::from("Attribute does not exist!"))?;
+            let (data, _) = attribute.get_data().get_attribute();
+
+            let components = varying.kind.components() as i32 * varying.size;
+            if components != *attribute.get_count() {
+                return Err(format!(
+                    "Varying: {} is a {} but the attribute it is captured into has {} components!",
+                    varying.name,
+                    varying,
+                    attribute.get_count()
+                ));
+            }
+            if data.is_integer() != (varying.kind.scalar() != Scalar::Float) {
+                return Err(format!(
+                    "Varying: {} is a {} but the attribute it is captured into has {} data!",
+                    varying.name,
+                    varying,
+                    if data.is_integer() { "integer" } else { "float" }
+                ));
+            }
+
+            let back = self.create_buffer()?;
+            self.attributes
+                .get_mut(*handle)
+                .ok_or_else(|| String::from("Attribute does not exist!"))?
+                .get_mut_data()
+                .set_feedback(back);
+            self.bind_buffer(*handle)?;
+        }
+
+        let object = self.context.create_transform_feedback()?;
+
+        Ok(self
+            .transform_feedbacks
+            .insert(TransformFeedback::new(object, captures.to_vec())))
+    }
+
+    /// Draw while capturing the varyings of the program in use into their attributes, which
+    /// the next draw reads. With `discard` nothing is rasterized and points are drawn instead
+    /// of triangles, so `count` is just the number of vertices to update
+    pub fn draw_with_feedback(
+        &mut self,
+        handle: TransformFeedbackHandle,
+        count: i32,
+        discard: bool,
+    ) -> Result<(), String> {
+        let context = self.context.webgl2("Transform feedback")?;
+        let transform_feedback = self
+            .transform_feedbacks
+            .get(handle)
+            .ok_or_else(|| String::from("Transform feedback does not exist!"))?;
+        let captures = transform_feedback.get_captures().to_vec();
+
+        context.bind_transform_feedback(
+            WebGl2RenderingContext::TRANSFORM_FEEDBACK,
+            Some(transform_feedback.get_object()),
+        );
+        for (index, attribute) in captures.iter().enumerate() {
+            let back = self
+                .attributes
+                .get(*attribute)
+                .and_then(|attribute| attribute.get_data().get_feedback())
+                .and_then(|buffer| self.buffers.get(buffer))
+                .ok_or_else(|| String::from("Buffer does not exist!"))?;
+            context.bind_buffer_base(
+                WebGl2RenderingContext::TRANSFORM_FEEDBACK_BUFFER,
+                index as u32,
+                Some(back),
+            );
+        }
+
+        let mode = if discard {
+            context.enable(WebGl2RenderingContext::RASTERIZER_DISCARD);
+            WebGl2RenderingContext::POINTS
+        } else {
+            WebGl2RenderingContext::TRIANGLES
+        };
+        context.begin_transform_feedback(mode);
+        context.draw_arrays(mode, 0, count);
+        context.end_transform_feedback();
+        if discard {
+            context.disable(WebGl2RenderingContext::RASTERIZER_DISCARD);
+        }
+
+        // A buffer can not be bound for capturing while it is read as an attribute
+        for index in 0..captures.len() {
+            context.bind_buffer_base(
+                WebGl2RenderingContext::TRANSFORM_FEEDBACK_BUFFER,
+                index as u32,
+                None,
+            );
+        }
+        context.bind_transform_feedback(WebGl2RenderingContext::TRANSFORM_FEEDBACK, None);
+
+        for attribute in &captures {
+            if let Some(attribute) = self.attributes.get_mut(*attribute) {
+                attribute.swap_feedback();
+            }
+        }
+        self.refresh_vertex_arrays(&captures)
+    }
+
+    /// Point the vertex array objects holding any of the attributes at their current buffers
+    fn refresh_vertex_arrays(&self, attributes: &[AttributeHandle]) -> Result<(), String> {
+        for (_, vertex_array) in self.vertex_arrays.iter() {
+            let object = match vertex_array.get_object() {
+                Some(object) => object,
+                None => continue,
+            };
+            let stale: Vec<AttributeHandle> = vertex_array
+                .get_attributes()
+                .iter()
+                .filter(|attribute| attributes.contains(attribute))
+                .copied()
+                .collect();
+            if stale.is_empty() {
+                continue;
+            }
+
+            self.bind_vertex_array_object(Some(object));
+            let refreshed = stale
+                .iter()
+                .try_for_each(|attribute| self.enable_attribute(*attribute));
+            self.bind_vertex_array_object(None);
+            refreshed?;
+        }
+
+        Ok(())
+    }
+
+    pub fn context(&self) -> &Gl {
+        &self.context
+    }
+}
+
+/// Resources and what they are created from, which is all restoring a lost context needs
+impl<B: Backend> WebGlContext<B> {
+    fn with_backend(
+        context: B,
+        extensions: Extensions,
+        canvas: web_sys::HtmlCanvasElement,
+        resize_watcher: Option<ResizeWatcher>,
+        memory: JsValue,
+    ) -> Self {
+        WebGlContext {
+            context,
+            canvas,
+            resize_mode: ResizeMode::default(),
+            resize_watcher,
+            viewport: Viewport::default(),
+            interpolation: 0.0,
+            extensions,
+            programs: SlotMap::new(),
+            program: None,
+            buffers: SlotMap::new(),
+            attributes: SlotMap::new(),
+            uniforms: SlotMap::new(),
+            vertex_arrays: SlotMap::new(),
+            uniform_blocks: SlotMap::new(),
+            transform_feedbacks: SlotMap::new(),
+            textures: SlotMap::new(),
+            framebuffers: SlotMap::new(),
+            framebuffer: None,
+            attribute_names: HashMap::new(),
+            uniform_names: HashMap::new(),
+            memory,
+            lose_context: None,
+        }
+    }
+
+    /// Area of the drawing buffer currently drawn into
+    pub fn get_viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// The extensions enabled so far
+    pub fn get_extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// The WebGL version the context was created with
+    pub fn version(&self) -> Version {
+        self.context.version()
+    }
+
+    /// Recreate every resource from what was kept of it, once the context was restored
+    fn restore_resources(&mut self) -> Result<(), String> {
+        for (_, program) in self.programs.iter_mut() {
+            *program = program.relink(&self.context)?;
+        }
+
+        for (_, buffer) in self.buffers.iter_mut() {
+            *buffer = self.context.create_buffer()?;
+        }
+
+        if let Some(program) = self.program {
+            self.use_program(program)?;
+        }
+
+        // Uniform blocks are uploaded and bound to the relinked programs again
+        let blocks: Vec<UniformBlockHandle> =
+            self.uniform_blocks.iter().map(|(handle, _)| handle).collect();
+        for handle in blocks {
+            self.upload_uniform_block(handle)?;
+            self.bind_uniform_block(handle)?;
+        }
+
+        // Locations are looked up again in the program in use
+        let attributes: Vec<(String, AttributeHandle)> = self
+            .attribute_names
+            .iter()
+            .map(|(name, handle)| (name.clone(), *handle))
+            .collect();
+        for (name, handle) in attributes {
+            let location = self
+                .context
+                .attribute_location(self.current_program()?, &name);
+            if location < 0 {
+                return Err(format!("Attribute: {} does not exist!", name));
+            }
+
+            if let Some(attribute) = self.attributes.get_mut(handle) {
+                attribute.get_mut_data().set_attribute_location(location as _);
+            }
+            self.bind_buffer(handle)?;
+            self.enable_attribute(handle)?;
+        }
+
+        let uniforms: Vec<(String, UniformHandle)> = self
+            .uniform_names
+            .iter()
+            .map(|(name, handle)| (name.clone(), *handle))
+            .collect();
+        for (name, handle) in uniforms {
+            let program = self.current_program()?;
+            let location = self
+                .context
+                .uniform_location(program, &name)
+                .or_else(|| {
+                    self.context
+                        .uniform_location(program, &format!("{}[0]", name))
+                })
+                .ok_or_else(|| format!("Uniform: {} location not found!", name))?;
+
+            if let Some(uniform) = self.uniforms.get_mut(handle) {
+                uniform.get_mut_data().set_uniform_location(location);
+            }
+            self.bind_uniform(handle)?;
+        }
+
+        // Captured attributes were seeded again along with the others, only the transform
+        // feedback objects are left
+        for (_, transform_feedback) in self.transform_feedbacks.iter_mut() {
+            transform_feedback.set_object(self.context.create_transform_feedback()?);
+        }
+
+        // Textures are allocated again and attached to their framebuffers, which start out
+        // cleared
+        let textures: Vec<TextureHandle> = self.textures.iter().map(|(handle, _)| handle).collect();
+        for handle in textures {
+            let object = self.context.create_texture()?;
+            if let Some(texture) = self.textures.get_mut(handle) {
+                texture.set_object(object);
+            }
+            self.allocate_texture(handle)?;
+        }
+
+        let framebuffers: Vec<FramebufferHandle> =
+            self.framebuffers.iter().map(|(handle, _)| handle).collect();
+        for handle in framebuffers {
+            let object = self.context.create_framebuffer()?;
+            if let Some(framebuffer) = self.framebuffers.get_mut(handle) {
+                framebuffer.set_object(object);
+            }
+            self.attach_framebuffer(handle)?;
+        }
+        self.bind_framebuffer(self.framebuffer)?;
+
+        // Vertex array objects are captured again from the restored attributes
+        let vertex_arrays: Vec<VertexArrayHandle> = self
+            .vertex_arrays
+            .iter()
+            .filter(|(_, vertex_array)| vertex_array.get_object().is_some())
+            .map(|(handle, _)| handle)
+            .collect();
+        for handle in vertex_arrays {
+            let object = self.capture_vertex_array(handle)?;
+            if let Some(vertex_array) = self.vertex_arrays.get_mut(handle) {
+                vertex_array.set_object(object);
+            }
+        }
+
         Ok(())
     }
 
-    /// Use internal program
-    pub fn use_program(&self) -> Result<(), String> {
-        match &self.program.as_ref() {
+    fn add_program(&mut self, program: Program<B>) -> Result<ProgramHandle, String> {
+        let handle = self.programs.insert(program);
+
+        // Blocks created earlier are bound to new programs declaring them as well
+        let blocks: Vec<UniformBlockHandle> =
+            self.uniform_blocks.iter().map(|(handle, _)| handle).collect();
+        for block in blocks {
+            self.bind_uniform_block(block)?;
+        }
+
+        Ok(handle)
+    }
+
+    /// Use a linked program, all following attribute and uniform lookups are made against it
+    pub fn use_program(&mut self, handle: ProgramHandle) -> Result<(), String> {
+        match self.programs.get(handle).and_then(|program| program.as_ref()) {
             Some(program) => {
-                self.context.use_program(Some(&program));
+                self.context.bind_program(program);
+                self.program = Some(handle);
                 Ok(())
             }
             None => Err(String::from("Program has not been setup yet!")),
         }
     }
 
+    fn current(&self) -> Result<&Program<B>, String> {
+        self.program
+            .and_then(|handle| self.programs.get(handle))
+            .ok_or_else(|| String::from("Program has not been setup yet!"))
+    }
+
+    /// Declared type of an attribute of the program in use
+    pub fn get_attribute_info(&self, name: &str) -> Option<&ActiveInfo> {
+        self.current().ok()?.get_attribute_info(name)
+    }
+
+    fn current_program(&self) -> Result<&B::Program, String> {
+        self.current()?
+            .as_ref()
+            .ok_or_else(|| String::from("Program has not been setup yet!"))
+    }
+
     /// Create a buffer
-    pub fn create_buffer(&self) -> Result<WebGlBuffer, String> {
-        let buffer = self
-            .context
-            .create_buffer()
-            .ok_or("Unable to create buffer")?;
-        Ok(buffer)
+    pub fn create_buffer(&mut self) -> Result<BufferHandle, String> {
+        let buffer = self.context.create_buffer()?;
+        Ok(self.buffers.insert(buffer))
     }
 
-    pub fn create_buffer_with_data<'a, Name: Into<Cow<'a, str>>, Type: FromSlice>(
+    fn insert_attribute<'a, Name: Into<Cow<'a, str>>>(
         &mut self,
         name: Name,
-        data: Type,
-        count: i32,
-    ) -> Result<(), String> {
+        data: B::Data,
+        size: i32,
+    ) -> Result<AttributeHandle, String> {
         let qualifer_name = name.into();
-        let buffer = self.create_buffer()?;
-        let data = FromSlice::from_slice(&mut self.memory, data);
-        let program = self.program.as_ref().unwrap();
-        let location = self.context.get_attrib_location(&program, &qualifer_name);
+        let location = self
+            .context
+            .attribute_location(self.current_program()?, &qualifer_name);
 
         if location < 0 {
-            return Err(String::from(format!(
-                "Attribute: {} does not exist!",
-                qualifer_name
-            )));
-        }
-
-        self.attributes.insert(
-            qualifer_name.into_owned(),
-            Buffer::new(
-                Some(buffer),
-                BufferInternal::Attribute(data, location as _),
-                count,
-            ),
-        );
+            return Err(format!("Attribute: {} does not exist!", qualifer_name));
+        }
+
+        let buffer = self.create_buffer()?;
+
+        let handle = self.attributes.insert(Buffer::new(
+            Some(buffer),
+            BufferInternal::Attribute(data, location as _, 0),
+            size,
+        ));
+        self.attribute_names.insert(qualifer_name.into_owned(), handle);
+
+        Ok(handle)
+    }
+
+    /// Look up an attribute by name, meant to be used while setting up a design
+    pub fn get_attribute_handle<'a, Name: Into<Cow<'a, str>>>(
+        &self,
+        name: Name,
+    ) -> Option<AttributeHandle> {
+        self.attribute_names.get(name.into().as_ref()).copied()
+    }
+
+    /// Look up a uniform by name, meant to be used while setting up a design
+    pub fn get_uniform_handle<'a, Name: Into<Cow<'a, str>>>(
+        &self,
+        name: Name,
+    ) -> Option<UniformHandle> {
+        self.uniform_names.get(name.into().as_ref()).copied()
+    }
+
+    /// Bind an attribute's array to the context
+    pub fn bind_buffer(&self, handle: AttributeHandle) -> Result<(), String> {
+        let attribute = self
+            .attributes
+            .get(handle)
+            .ok_or_else(|| String::from("Attribute does not exist!"))?;
+        let (data, _) = attribute.get_data().get_attribute();
 
+        // Captured attributes seed both the buffer read and the one written by the GPU
+        let front = attribute
+            .get_buffer()
+            .ok_or_else(|| String::from("Buffer does not exist!"))?;
+        let back = attribute.get_data().get_feedback();
+        let usage = match back {
+            Some(_) => WebGl2RenderingContext::DYNAMIC_COPY,
+            None => WebGlRenderingContext::STATIC_DRAW,
+        };
+
+        for buffer in std::iter::once(front).chain(back) {
+            let buffer = self
+                .buffers
+                .get(buffer)
+                .ok_or_else(|| String::from("Buffer does not exist!"))?;
+
+            self.context.upload_buffer(buffer, data, usage);
+        }
         Ok(())
     }
 
@@ -145,186 +1075,469 @@ impl WebGlContext {
         &self,
         name: Name,
     ) -> Result<(), String> {
-        let attribute = self.attributes.get(&name.into().into_owned());
-        match attribute {
-            Some(attribute) => {
-                self.context.bind_buffer(
-                    WebGlRenderingContext::ARRAY_BUFFER,
-                    Some(&attribute.get_buffer().unwrap()),
-                );
-
-                let (data, _) = attribute.get_data().get_attribute();
-
-                self.context.buffer_data_with_array_buffer_view(
-                    WebGlRenderingContext::ARRAY_BUFFER,
-                    data.to_object(),
-                    WebGlRenderingContext::STATIC_DRAW,
-                );
-                Ok(())
-            }
-            None => Err(String::from("Attribute does not exist!")),
-        }
+        let handle = self
+            .get_attribute_handle(name)
+            .ok_or_else(|| String::from("Attribute does not exist!"))?;
+        self.bind_buffer(handle)
     }
 
-    /// Clear and set background color
-    pub fn clear_with_color(&self, color: [f64; 4]) {
-        self.context.clear_color(color[0] as _, color[1] as _, color[2] as _, color[3] as _);
-        self.context.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+    /// Advance an attribute once every `divisor` instances, requires `ANGLE_instanced_arrays`
+    pub fn set_attribute_divisor(
+        &mut self,
+        handle: AttributeHandle,
+        divisor: u32,
+    ) -> Result<(), String> {
+        if divisor != 0 && !self.extensions.is_enabled(Extension::AngleInstancedArrays) {
+            return Err(format!(
+                "Attribute divisors require {}!",
+                Extension::AngleInstancedArrays.name()
+            ));
+        }
+
+        self.attributes
+            .get_mut(handle)
+            .ok_or_else(|| String::from("Attribute does not exist!"))?
+            .get_mut_data()
+            .set_divisor(divisor);
+        Ok(())
     }
 
-    pub fn enable_attribute<'a, T: Into<Cow<'a, str>>>(&self, name: T) -> Result<(), String> {
-        let attribute = self.attributes.get(&name.into().into_owned());
+    pub fn enable_attribute(&self, handle: AttributeHandle) -> Result<(), String> {
+        let attribute = self.attributes.get(handle);
         match attribute {
             Some(attribute) => {
-                let (_, location) = attribute.get_data().get_attribute();
+                let (data, location) = attribute.get_data().get_attribute();
+                let buffer = attribute
+                    .get_buffer()
+                    .and_then(|buffer| self.buffers.get(buffer))
+                    .ok_or_else(|| String::from("Buffer does not exist!"))?;
 
-                self.context.vertex_attrib_pointer_with_i32(
+                self.context.attribute_pointer(
+                    &self.extensions,
+                    buffer,
                     *location,
-                    *attribute.get_count() as _,
-                    WebGlRenderingContext::FLOAT,
-                    false,
-                    0,
-                    0,
-                );
-                self.context.enable_vertex_attrib_array(0);
-                Ok(())
+                    *attribute.get_count(),
+                    data,
+                    attribute.get_data().get_divisor(),
+                )
             }
             None => Err(String::from("Attribute does not exist!")),
         }
     }
 
-    pub fn create_uniform<'a, T: Copy + Into<Cow<'a, str>>>(
+    /// Create a uniform, checking the value against the type the program declares it with
+    pub fn create_uniform<'a, T: Into<Cow<'a, str>>>(
         &mut self,
         name: T,
         uniform: UniformInner,
-    ) -> Result<(), String> {
+    ) -> Result<UniformHandle, String> {
+        let name = name.into();
+        let program = self.current()?;
+        let web_program = self.current_program()?;
+
+        let info = program
+            .get_uniform_info(&name)
+            .cloned()
+            .ok_or_else(|| format!("Uniform: {} is not used by the program!", name))?;
+
+        // Arrays can also be located through their first element
         let location = self
             .context
-            .get_uniform_location(&self.program.as_ref().unwrap(), name.into().as_ref());
+            .uniform_location(web_program, &name)
+            .or_else(|| {
+                self.context
+                    .uniform_location(web_program, &format!("{}[0]", name))
+            })
+            .ok_or_else(|| format!("Uniform: {} location not found!", name))?;
+
+        // Surface type mismatches now rather than on the first frame
+        self.evaluate_uniform(&uniform, 0.0).coerce(&info)?;
+
+        let handle = self.uniforms.insert(Buffer::new(
+            None,
+            BufferInternal::Uniform(uniform, location, info),
+            0,
+        ));
+        self.uniform_names.insert(name.into_owned(), handle);
+
+        Ok(handle)
+    }
 
-        if !location.is_some() {
-            return Err(String::from("Uniform location not found!"));
+    /// Replace the value of a uniform and upload it, checking it against the declared type
+    pub fn set_uniform(
+        &mut self,
+        handle: UniformHandle,
+        uniform: UniformInner,
+    ) -> Result<(), String> {
+        let info = self
+            .uniforms
+            .get(handle)
+            .ok_or_else(|| String::from("Uniform does not exist!"))?
+            .get_data()
+            .get_uniform()
+            .2
+            .clone();
+        self.evaluate_uniform(&uniform, 0.0).coerce(&info)?;
+
+        if let Some(buffer) = self.uniforms.get_mut(handle) {
+            buffer.get_mut_data().set_uniform(uniform);
         }
+        self.bind_uniform(handle)
+    }
 
-        self.uniforms.insert(
-            name.into().as_ref().to_string(),
-            Buffer::new(None, BufferInternal::Uniform(uniform, location.unwrap()), 0),
-        );
+    pub fn bind_uniform(&self, handle: UniformHandle) -> Result<(), String> {
+        let uniform = self
+            .uniforms
+            .get(handle)
+            .ok_or_else(|| String::from("Uniform does not exist!"))?;
+        let (data, location, info) = uniform.get_data().get_uniform();
+
+        self.upload_uniform(data, location, info, self.evaluate_uniform(data, 0.0))
+    }
+
+    pub fn update_uniform(&self, handle: UniformHandle, tick: f64) -> Result<(), String> {
+        let uniform = self
+            .uniforms
+            .get(handle)
+            .ok_or_else(|| String::from("Uniform does not exist!"))?;
+        let (data, location, info) = uniform.get_data().get_uniform();
+
+        if data.is_dynamic() || data.is_builtin() {
+            self.upload_uniform(data, location, info, self.evaluate_uniform(data, tick))?;
+        }
 
         Ok(())
     }
 
-    pub fn bind_uniform<'a, T: Into<Cow<'a, str>>>(&self, name: T) -> Result<(), String> {
-        let uniform = self.uniforms.get(&name.into().into_owned());
-        match uniform {
-            Some(uniform) => {
-                let (data, location) = uniform.get_data().get_uniform();
+    /// Evaluate a uniform, filling in builtins from the state of the context
+    fn evaluate_uniform(&self, data: &UniformInner, tick: f64) -> UniformValue {
+        match data {
+            UniformInner::Builtin(Builtin::Resolution) => UniformValue::Float(vec![
+                self.viewport.width as f32,
+                self.viewport.height as f32,
+            ]),
+            UniformInner::Builtin(Builtin::PixelRatio) => UniformValue::Float(vec![
+                web_sys::window().map_or(1.0, |window| window.device_pixel_ratio()) as f32,
+            ]),
+            UniformInner::Builtin(Builtin::Interpolation) => {
+                UniformValue::Float(vec![self.interpolation as f32])
+            }
+            data => data.value(tick),
+        }
+    }
 
-                match data {
-                    UniformInner::Uniform1i(val, _) => self.context.uniform1i(Some(location), *val),
-                    UniformInner::Uniform1f(val, _) => self.context.uniform1f(Some(location), *val as _),
-                    UniformInner::Uniform2i(val1, val2, _) => {
-                        self.context.uniform2i(Some(location), *val1, *val2)
-                    }
-                    UniformInner::Uniform2f(val1, val2, _) => {
-                        self.context.uniform2f(Some(location), *val1 as _, *val2 as _)
-                    }
-                    UniformInner::Uniform3i(val1, val2, val3, _) => {
-                        self.context.uniform3i(Some(location), *val1, *val2, *val3)
-                    }
-                    UniformInner::Uniform3f(val1, val2, val3, _) => {
-                        self.context.uniform3f(Some(location), *val1 as _, *val2 as _, *val3 as _)
-                    }
-                    UniformInner::Uniform4i(val1, val2, val3, val4, _) => {
-                        self.context.uniform4i(Some(location), *val1, *val2, *val3, *val4)
-                    }
-                    UniformInner::Uniform4f(val1, val2, val3, val4, _) => {
-                        self.context.uniform4f(Some(location), *val1 as _, *val2 as _, *val3 as _, *val4 as _)
-                    }
-                }
+    /// Upload a value with the call matching the declared type of the uniform
+    fn upload_uniform(
+        &self,
+        data: &UniformInner,
+        location: &B::Location,
+        info: &ActiveInfo,
+        value: UniformValue,
+    ) -> Result<(), String> {
+        let transpose = data.is_transposed();
+        if transpose && self.version() != Version::WebGl2 {
+            return Err(String::from("Matrix uniforms can not be transposed in WebGL 1!"));
+        }
 
-                Ok(())
+        let value = value.coerce(info)?;
+        self.context
+            .upload_uniform(location, info, transpose, value)
+    }
+
+    fn upload_uniform_block(&mut self, handle: UniformBlockHandle) -> Result<(), String> {
+        let block = self
+            .uniform_blocks
+            .get(handle)
+            .ok_or_else(|| String::from("Uniform block does not exist!"))?;
+        let buffer = self
+            .buffers
+            .get(block.get_buffer())
+            .ok_or_else(|| String::from("Buffer does not exist!"))?;
+
+        self.context
+            .upload_uniform_block(buffer, block.get_binding(), block.get_data())
+    }
+
+    /// Point the block of every program declaring it at the binding of the uniform block,
+    /// checking the program does not expect more data than was given
+    fn bind_uniform_block(&self, handle: UniformBlockHandle) -> Result<(), String> {
+        let block = self
+            .uniform_blocks
+            .get(handle)
+            .ok_or_else(|| String::from("Uniform block does not exist!"))?;
+
+        for (_, program) in self.programs.iter() {
+            let (info, web_program) =
+                match (program.get_block_info(block.get_name()), program.as_ref()) {
+                    (Some(info), Some(web_program)) => (info, web_program),
+                    _ => continue,
+                };
+
+            if block.get_data().len() < info.size {
+                return Err(format!(
+                    "Uniform block: {} is {} bytes in the program but {} bytes were given!",
+                    info.name,
+                    info.size,
+                    block.get_data().len()
+                ));
             }
-            None => Err(String::from("Uniform does not exist!")),
-        }
-    }
-
-    pub fn update_uniform<'a, T: Into<Cow<'a, str>>>(&self, name: T, tick: f64) -> Result<(), String> {
-        let uniform = self.uniforms.get(&name.into().into_owned());
-        match uniform {
-            Some(uniform) => {
-                let (data, location) = uniform.get_data().get_uniform();
-
-                if data.is_dynamic() {
-                    match data {
-                        UniformInner::Uniform1i(_, func) => {
-                            if let Some(func) = func {
-                                let val = func(tick);
-                                self.context.uniform1i(Some(location), val)   
-                            }
-                        },
-                        UniformInner::Uniform1f(_, func) => {
-                            if let Some(func) = func {
-                                let val = func(tick);
-                                self.context.uniform1f(Some(location), val as _)   
-                            }
-                        },
-                        UniformInner::Uniform2i(_, _, func) => {
-                            if let Some(func) = func {
-                                let val = func(tick);
-                                self.context.uniform2i(Some(location), val[0], val[1])
-                            }
-                        }
-                        UniformInner::Uniform2f(_, _, func) => {
-                            if let Some(func) = func {
-                                let val = func(tick);
-                                self.context.uniform2f(Some(location), val[0] as _, val[1] as _)
-                            }
-                        }
-                        UniformInner::Uniform3i(_, _, _, func) => {
-                            if let Some(func) = func {
-                                let val = func(tick);
-                                self.context.uniform3i(Some(location), val[0], val[1], val[2])
-                            }
-                        }
-                        UniformInner::Uniform3f(_, _, _, func) => {
-                            if let Some(func) = func {
-                                let val = func(tick);
-                                self.context.uniform3f(Some(location), val[0] as _, val[1] as _, val[2] as _)
-                            }
-                        }
-                        UniformInner::Uniform4i(_, _, _, _, func) => {
-                            if let Some(func) = func {
-                                let val = func(tick);
-                                self.context.uniform4i(Some(location), val[0], val[1], val[2], val[3])
-                            }
-                        }
-                        UniformInner::Uniform4f(_, _, _, _, func) => {
-                            if let Some(func) = func {
-                                let val = func(tick);
-                                self.context.uniform4f(Some(location), val[0] as _, val[1] as _, val[2] as _, val[3] as _)
-                            }
-                        }
-                    }
-                }
 
-                Ok(())
+            uniform_block::check_layout(info, block.get_offsets())?;
+
+            self.context
+                .uniform_block_binding(web_program, info.index, block.get_binding())?;
+        }
+
+        Ok(())
+    }
+
+    /// Create a texture of the given size and format, e.g. to draw into, its extension has
+    /// to be enabled through `enable_texture_format` first
+    pub fn create_texture(
+        &mut self,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+    ) -> Result<TextureHandle, String> {
+        self.insert_texture(width, height, format, None)
+    }
+
+    fn insert_texture(
+        &mut self,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        data: Option<B::Data>,
+    ) -> Result<TextureHandle, String> {
+        let version = self.version();
+        if !format.is_available(version) {
+            return Err(format!(
+                "Texture format: {:?} is not available on this context!",
+                format
+            ));
+        }
+        if let Some(extension) = format.texture_extension(version) {
+            if !self.extensions.is_enabled(extension) {
+                return Err(format!(
+                    "Texture format: {:?} requires {}!",
+                    format,
+                    extension.name()
+                ));
             }
-            None => Err(String::from("Uniform does not exist!")),
         }
+
+        let object = self.context.create_texture()?;
+        let handle = self
+            .textures
+            .insert(Texture::new(object, width, height, format, data));
+        self.allocate_texture(handle)?;
+
+        Ok(handle)
     }
 
-    /// Draw triangles
-    pub fn draw_triangles(&self, count: i32) {
-        self.context.draw_arrays(WebGlRenderingContext::TRIANGLES, 0, count);
+    /// Allocate the storage of a texture and upload its texels
+    fn allocate_texture(&self, handle: TextureHandle) -> Result<(), String> {
+        let texture = self
+            .textures
+            .get(handle)
+            .ok_or_else(|| String::from("Texture does not exist!"))?;
+        self.context.allocate_texture(texture)
     }
 
-    pub fn context(&self) -> &WebGlRenderingContext {
-        &self.context
+    pub fn get_texture(&self, handle: TextureHandle) -> Option<&Texture<B>> {
+        self.textures.get(handle)
+    }
+
+    /// Create a framebuffer with a texture for every color output, several outputs need
+    /// WebGL 2 or `WEBGL_draw_buffers`
+    pub fn create_framebuffer(
+        &mut self,
+        options: &FramebufferOptions,
+    ) -> Result<FramebufferHandle, String> {
+        let formats = match options.color.len() {
+            0 => vec![TextureFormat::default()],
+            _ => options.color.clone(),
+        };
+
+        if formats.len() > 1 {
+            if self.version() != Version::WebGl2
+                && !self.extensions.is_enabled(Extension::WebglDrawBuffers)
+            {
+                return Err(format!(
+                    "Several color outputs require {}!",
+                    Extension::WebglDrawBuffers.name()
+                ));
+            }
+
+            let max = self.context.max_draw_buffers();
+            if formats.len() > max {
+                return Err(format!(
+                    "Framebuffers can have at most {} color outputs but {} were given!",
+                    max,
+                    formats.len()
+                ));
+            }
+        }
+
+        // Follow the drawing buffer unless a size is given
+        let width = match options.width {
+            0 => self.viewport.width,
+            width => width,
+        };
+        let height = match options.height {
+            0 => self.viewport.height,
+            height => height,
+        };
+
+        // Float formats can be sampled with fewer extensions than they can be drawn into with
+        let version = self.version();
+        for format in &formats {
+            let extensions = format.render_extensions(version);
+            if !extensions.is_empty()
+                && !extensions
+                    .iter()
+                    .any(|extension| self.extensions.is_enabled(*extension))
+            {
+                return Err(format!(
+                    "Texture format: {:?} can not be drawn into without {}!",
+                    format,
+                    extensions[0].name()
+                ));
+            }
+        }
+
+        let mut colors = Vec::new();
+        for format in formats {
+            colors.push(self.create_texture(width, height, format)?);
+        }
+
+        let object = self.context.create_framebuffer()?;
+        let handle = self
+            .framebuffers
+            .insert(Framebuffer::new(object, width, height, colors));
+        self.attach_framebuffer(handle)?;
+
+        Ok(handle)
+    }
+
+    /// Attach the textures of a framebuffer, checking the combination can be drawn into
+    fn attach_framebuffer(&self, handle: FramebufferHandle) -> Result<(), String> {
+        let framebuffer = self
+            .framebuffers
+            .get(handle)
+            .ok_or_else(|| String::from("Framebuffer does not exist!"))?;
+        let textures = framebuffer
+            .get_colors()
+            .iter()
+            .map(|texture| self.textures.get(*texture).map(Texture::as_ref))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| String::from("Texture does not exist!"))?;
+
+        // Go back to whatever was drawn into before
+        let bound = self
+            .framebuffer
+            .and_then(|handle| self.framebuffers.get(handle))
+            .map(Framebuffer::as_ref);
+
+        self.context
+            .attach_textures(framebuffer.as_ref(), &textures, bound)
+    }
+
+    pub fn get_framebuffer(&self, handle: FramebufferHandle) -> Option<&Framebuffer<B>> {
+        self.framebuffers.get(handle)
+    }
+
+    /// Draw into a framebuffer, writing every color output into its texture, or into the
+    /// canvas again with `None`
+    pub fn bind_framebuffer(&mut self, handle: Option<FramebufferHandle>) -> Result<(), String> {
+        let framebuffer = match handle {
+            Some(handle) => Some(
+                self.framebuffers
+                    .get(handle)
+                    .ok_or_else(|| String::from("Framebuffer does not exist!"))?,
+            ),
+            None => None,
+        };
+
+        self.context
+            .draw_into(&self.extensions, framebuffer, self.viewport)?;
+        self.framebuffer = handle;
+        Ok(())
+    }
+
+    /// Group the attributes of a draw, capturing their bindings in a vertex array object on
+    /// WebGL 2 or if `OES_vertex_array_object` is enabled
+    pub fn create_vertex_array(
+        &mut self,
+        attributes: &[AttributeHandle],
+    ) -> Result<VertexArrayHandle, String> {
+        let handle = self
+            .vertex_arrays
+            .insert(VertexArray::new(None, attributes.to_vec()));
+        let object = self.capture_vertex_array(handle)?;
+        if let Some(vertex_array) = self.vertex_arrays.get_mut(handle) {
+            vertex_array.set_object(object);
+        }
+
+        Ok(handle)
+    }
+
+    fn capture_vertex_array(
+        &self,
+        handle: VertexArrayHandle,
+    ) -> Result<Option<B::VertexArray>, String> {
+        let vertex_array = self
+            .vertex_arrays
+            .get(handle)
+            .ok_or_else(|| String::from("Vertex array does not exist!"))?;
+
+        let object = match self.context.create_vertex_array(&self.extensions)? {
+            Some(object) => object,
+            None => return Ok(None),
+        };
+
+        self.bind_vertex_array_object(Some(&object));
+        let captured = vertex_array
+            .get_attributes()
+            .iter()
+            .try_for_each(|attribute| self.enable_attribute(*attribute));
+        self.bind_vertex_array_object(None);
+        captured?;
+
+        Ok(Some(object))
+    }
+
+    fn bind_vertex_array_object(&self, object: Option<&B::VertexArray>) {
+        self.context.bind_vertex_array(&self.extensions, object);
+    }
+
+    /// Make the attributes of a draw current, by binding its vertex array object or by
+    /// enabling every attribute again without one
+    pub fn bind_vertex_array(&self, handle: VertexArrayHandle) -> Result<(), String> {
+        let vertex_array = self
+            .vertex_arrays
+            .get(handle)
+            .ok_or_else(|| String::from("Vertex array does not exist!"))?;
+
+        match vertex_array.get_object() {
+            Some(object) => self.bind_vertex_array_object(Some(object)),
+            None => {
+                for attribute in vertex_array.get_attributes() {
+                    self.enable_attribute(*attribute)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Go back to the default vertex array object
+    pub fn unbind_vertex_array(&self) {
+        self.bind_vertex_array_object(None);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum JsArray {
     Uint8Array(Uint8Array),
     Uint16Array(Uint16Array),
@@ -337,6 +1550,26 @@ pub enum JsArray {
 }
 
 impl JsArray {
+    /// Type of the elements as given to `vertexAttribPointer`, WebGL has no doubles
+    fn gl_type(&self) -> Option<u32> {
+        let gl_type = match self {
+            JsArray::Uint8Array(_) => WebGlRenderingContext::UNSIGNED_BYTE,
+            JsArray::Uint16Array(_) => WebGlRenderingContext::UNSIGNED_SHORT,
+            JsArray::Uint32Array(_) => WebGlRenderingContext::UNSIGNED_INT,
+            JsArray::Int8Array(_) => WebGlRenderingContext::BYTE,
+            JsArray::Int16Array(_) => WebGlRenderingContext::SHORT,
+            JsArray::Int32Array(_) => WebGlRenderingContext::INT,
+            JsArray::Float32Array(_) => WebGlRenderingContext::FLOAT,
+            JsArray::Float64Array(_) => return None,
+        };
+
+        Some(gl_type)
+    }
+
+    fn is_integer(&self) -> bool {
+        !matches!(self, JsArray::Float32Array(_) | JsArray::Float64Array(_))
+    }
+
     fn to_object(&self) -> &Object {
         match self {
             JsArray::Uint8Array(arr) => arr.as_ref(),
@@ -353,15 +1586,17 @@ impl JsArray {
 
 pub trait FromSlice {
     fn from_slice(memory: &mut JsValue, data: Self) -> JsArray;
+
+    fn is_empty(&self) -> bool;
 }
 
 macro_rules! from_slice {
     ($type:ty, $id:ident) => {
-        impl FromSlice for $type {
-            fn from_slice(memory: &mut JsValue, data: $type) -> JsArray {
+        impl FromSlice for &[$type] {
+            fn from_slice(memory: &mut JsValue, data: &[$type]) -> JsArray {
                 let data_size = data.len();
                 let data_pointer = data.as_ptr() as u32;
-                let data_normilization = size_of_val(&data[0]) as u32;
+                let data_normilization = size_of::<$type>() as u32;
                 let data_location = data_pointer / data_normilization;
 
                 // Check to see if our memory was resized before we use it
@@ -376,20 +1611,25 @@ macro_rules! from_slice {
                     std::mem::replace(memory, memory_buffer);
                 }
 
-                // Return js_sys value
+                // Return js_sys value, copied out of our memory so it can be uploaded again
+                // after the context is lost
                 JsArray::$id(
-                    $id::new(memory).subarray(data_location, data_location + data_size as u32),
+                    $id::new(memory).slice(data_location, data_location + data_size as u32),
                 )
             }
+
+            fn is_empty(&self) -> bool {
+                <[$type]>::is_empty(self)
+            }
         }
     };
 }
 
-from_slice!(&[u8], Uint8Array);
-from_slice!(&[u16], Uint16Array);
-from_slice!(&[u32], Uint32Array);
-from_slice!(&[i8], Int8Array);
-from_slice!(&[i16], Int16Array);
-from_slice!(&[i32], Int32Array);
-from_slice!(&[f32], Float32Array);
-from_slice!(&[f64], Float64Array);
+from_slice!(u8, Uint8Array);
+from_slice!(u16, Uint16Array);
+from_slice!(u32, Uint32Array);
+from_slice!(i8, Int8Array);
+from_slice!(i16, Int16Array);
+from_slice!(i32, Int32Array);
+from_slice!(f32, Float32Array);
+from_slice!(f64, Float64Array);
@@ -1,20 +1,106 @@
 /// WebGl Program
-use crate::webgl::Shader;
-use web_sys::{WebGlProgram, WebGlRenderingContext};
+use std::collections::HashMap;
+
+use crate::webgl::reflect::{self, ActiveInfo, BlockInfo};
+use crate::webgl::{Backend, Gl, Shader};
+use js_sys::Array;
+use wasm_bindgen::JsValue;
+use web_sys::{WebGl2RenderingContext, WebGlRenderingContext};
 
 #[derive(Debug, Clone)]
-pub struct Program {
-    internal: Option<WebGlProgram>,
+pub struct Program<B: Backend = Gl> {
+    internal: Option<B::Program>,
+    shaders: Vec<B::Shader>,
+    attributes: HashMap<String, ActiveInfo>,
+    uniforms: HashMap<String, ActiveInfo>,
+    blocks: HashMap<String, BlockInfo>,
+    varyings: Vec<ActiveInfo>,
+    /// Names the varyings were requested with, to link them again
+    varying_names: Vec<String>,
 }
 
-impl Program {
+impl<B: Backend> Program<B> {
     pub fn empty() -> Self {
-        Program { internal: None }
+        Program {
+            internal: None,
+            shaders: Vec::new(),
+            attributes: HashMap::new(),
+            uniforms: HashMap::new(),
+            blocks: HashMap::new(),
+            varyings: Vec::new(),
+            varying_names: Vec::new(),
+        }
+    }
+
+    /// A program of a backend of the tests, declaring the given attributes and uniforms
+    #[cfg(test)]
+    pub(crate) fn declaring(
+        internal: B::Program,
+        shaders: Vec<B::Shader>,
+        attributes: Vec<ActiveInfo>,
+        uniforms: Vec<ActiveInfo>,
+    ) -> Self {
+        let by_name = |infos: Vec<ActiveInfo>| {
+            infos
+                .into_iter()
+                .map(|info| (info.name.clone(), info))
+                .collect()
+        };
+
+        Program {
+            internal: Some(internal),
+            shaders,
+            attributes: by_name(attributes),
+            uniforms: by_name(uniforms),
+            ..Program::empty()
+        }
+    }
+
+    /// Compile and link the program again from the sources of its shaders, e.g. after the
+    /// context was lost
+    pub fn relink(&self, context: &B) -> Result<Program<B>, String> {
+        context.relink(&self.shaders, &self.varying_names)
+    }
+
+    pub fn as_ref(&self) -> Option<&B::Program> {
+        self.internal.as_ref()
+    }
+
+    /// Declared type of an active attribute
+    pub fn get_attribute_info(&self, name: &str) -> Option<&ActiveInfo> {
+        self.attributes.get(name)
+    }
+
+    /// Declared type of an active uniform, arrays are found without their `[0]`
+    pub fn get_uniform_info(&self, name: &str) -> Option<&ActiveInfo> {
+        self.uniforms.get(name)
     }
 
+    /// Varyings captured through transform feedback, in the order of their buffers
+    pub fn get_varyings(&self) -> &[ActiveInfo] {
+        &self.varyings
+    }
+
+    /// Index and size of an active uniform block
+    pub fn get_block_info(&self, name: &str) -> Option<&BlockInfo> {
+        self.blocks.get(name)
+    }
+}
+
+impl Program {
     pub fn new<'a, T: IntoIterator<Item = &'a Shader>>(
-        context: &WebGlRenderingContext,
+        context: &Gl,
         shaders: T,
+    ) -> Result<Program, String> {
+        Program::with_varyings(context, shaders, &[] as &[&str])
+    }
+
+    /// Link a program capturing the named vertex shader outputs through transform feedback,
+    /// each into a buffer of its own, needs WebGL 2 unless no varyings are given
+    pub fn with_varyings<'a, T: IntoIterator<Item = &'a Shader>, Name: AsRef<str>>(
+        context: &Gl,
+        shaders: T,
+        varyings: &[Name],
     ) -> Result<Program, String> {
         // Create a webgl program
         let program = context.create_program().ok_or_else(|| {
@@ -23,10 +109,24 @@ impl Program {
         })?;
 
         // Iterate through shaders attaching them to the program
-        for shader in shaders {
+        let shaders: Vec<Shader> = shaders.into_iter().cloned().collect();
+        for shader in &shaders {
             context.attach_shader(&program, shader.as_ref());
         }
 
+        // Varyings to capture have to be declared before linking
+        if !varyings.is_empty() {
+            let names: Array = varyings
+                .iter()
+                .map(|name| JsValue::from(name.as_ref()))
+                .collect();
+            context.webgl2("Transform feedback")?.transform_feedback_varyings(
+                &program,
+                &names,
+                WebGl2RenderingContext::SEPARATE_ATTRIBS,
+            );
+        }
+
         // Link program with webgl
         context.link_program(&program);
 
@@ -37,7 +137,13 @@ impl Program {
             .unwrap_or(false)
         {
             Ok(Program {
+                attributes: reflect::active_attributes(context, &program),
+                uniforms: reflect::active_uniforms(context, &program),
+                blocks: reflect::active_uniform_blocks(context, &program),
+                varyings: reflect::active_varyings(context, &program),
+                varying_names: varyings.iter().map(|name| name.as_ref().to_owned()).collect(),
                 internal: Some(program),
+                shaders,
             })
         } else {
             Err(context
@@ -45,8 +151,4 @@ impl Program {
                 .unwrap_or_else(|| String::from("Unknown error creating program object")))
         }
     }
-
-    pub fn as_ref(&self) -> Option<&WebGlProgram> {
-        self.internal.as_ref()
-    }
 }
@@ -8,6 +8,7 @@ pub enum Qualifier {
     Float(f32),
     Vec2(i32, i32),
     Vec3(i32, i32, i32),
+    Vec4(i32, i32, i32, i32),
     Vec2f(f32, f32),
     Vec3f(f32, f32, f32),
     Vec4f(f32, f32, f32, f32),
@@ -22,6 +23,12 @@ impl Qualifier {
             Qualifier::Vec3(inner1, inner2, inner3) => {
                 vec![*inner1 as f32, *inner2 as f32, *inner3 as f32]
             }
+            Qualifier::Vec4(inner1, inner2, inner3, inner4) => vec![
+                *inner1 as f32,
+                *inner2 as f32,
+                *inner3 as f32,
+                *inner4 as f32,
+            ],
             Qualifier::Vec2f(inner1, inner2) => vec![*inner1, *inner2],
             Qualifier::Vec3f(inner1, inner2, inner3) => vec![*inner1, *inner2, *inner3],
             Qualifier::Vec4f(inner1, inner2, inner3, inner4) => {
@@ -29,6 +36,29 @@ impl Qualifier {
             }
         }
     }
+
+    /// The components as integers, for the `int` and `uint` attributes of GLSL ES 3.00
+    pub fn to_int_vec(&self) -> Vec<i32> {
+        match self {
+            Qualifier::Int(inner) => vec![*inner],
+            Qualifier::Vec2(inner1, inner2) => vec![*inner1, *inner2],
+            Qualifier::Vec3(inner1, inner2, inner3) => vec![*inner1, *inner2, *inner3],
+            Qualifier::Vec4(inner1, inner2, inner3, inner4) => {
+                vec![*inner1, *inner2, *inner3, *inner4]
+            }
+            _ => self.to_vec().into_iter().map(|inner| inner as i32).collect(),
+        }
+    }
+
+    /// Number of components, e.g. 2 for a `vec2`
+    pub fn components(&self) -> i32 {
+        match self {
+            Qualifier::Int(_) | Qualifier::Float(_) => 1,
+            Qualifier::Vec2(_, _) | Qualifier::Vec2f(_, _) => 2,
+            Qualifier::Vec3(_, _, _) | Qualifier::Vec3f(_, _, _) => 3,
+            Qualifier::Vec4(_, _, _, _) | Qualifier::Vec4f(_, _, _, _) => 4,
+        }
+    }
 }
 
 impl From<[i32; 1]> for Qualifier {
@@ -67,6 +97,12 @@ impl From<[i32; 3]> for Qualifier {
     }
 }
 
+impl From<[i32; 4]> for Qualifier {
+    fn from(item: [i32; 4]) -> Self {
+        Qualifier::Vec4(item[0], item[1], item[2], item[3])
+    }
+}
+
 impl From<[f32; 2]> for Qualifier {
     fn from(item: [f32; 2]) -> Self {
         Qualifier::Vec2f(item[0], item[1])
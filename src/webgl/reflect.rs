@@ -0,0 +1,350 @@
+/*!
+Reflection of the attributes, uniforms and varyings a linked program declares, used to check
+and coerce the values given to rugl into what the shader actually expects.
+ */
+
+use std::collections::HashMap;
+use std::fmt;
+
+use js_sys::Array;
+use web_sys::{
+    WebGl2RenderingContext as Gl2, WebGlActiveInfo, WebGlProgram, WebGlRenderingContext,
+};
+
+use super::gl::Gl;
+
+/// Every GLSL ES 1.00 and 3.00 attribute and uniform type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlslType {
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    Int,
+    IVec2,
+    IVec3,
+    IVec4,
+    UnsignedInt,
+    UVec2,
+    UVec3,
+    UVec4,
+    Bool,
+    BVec2,
+    BVec3,
+    BVec4,
+    Mat2,
+    Mat3,
+    Mat4,
+    Mat2x3,
+    Mat2x4,
+    Mat3x2,
+    Mat3x4,
+    Mat4x2,
+    Mat4x3,
+    Sampler2D,
+    SamplerCube,
+    Sampler3D,
+    Sampler2DArray,
+    Sampler2DShadow,
+    Sampler2DArrayShadow,
+    SamplerCubeShadow,
+    ISampler2D,
+    ISampler3D,
+    ISamplerCube,
+    ISampler2DArray,
+    USampler2D,
+    USampler3D,
+    USamplerCube,
+    USampler2DArray,
+}
+
+/// The kind of scalar a type is made up of, which decides how values are uploaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scalar {
+    Float,
+    Int,
+    UnsignedInt,
+    Bool,
+    Sampler,
+}
+
+impl GlslType {
+    pub fn from_gl(gl_type: u32) -> Option<Self> {
+        let glsl_type = match gl_type {
+            WebGlRenderingContext::FLOAT => GlslType::Float,
+            WebGlRenderingContext::FLOAT_VEC2 => GlslType::Vec2,
+            WebGlRenderingContext::FLOAT_VEC3 => GlslType::Vec3,
+            WebGlRenderingContext::FLOAT_VEC4 => GlslType::Vec4,
+            WebGlRenderingContext::INT => GlslType::Int,
+            WebGlRenderingContext::INT_VEC2 => GlslType::IVec2,
+            WebGlRenderingContext::INT_VEC3 => GlslType::IVec3,
+            WebGlRenderingContext::INT_VEC4 => GlslType::IVec4,
+            WebGlRenderingContext::BOOL => GlslType::Bool,
+            WebGlRenderingContext::BOOL_VEC2 => GlslType::BVec2,
+            WebGlRenderingContext::BOOL_VEC3 => GlslType::BVec3,
+            WebGlRenderingContext::BOOL_VEC4 => GlslType::BVec4,
+            WebGlRenderingContext::FLOAT_MAT2 => GlslType::Mat2,
+            WebGlRenderingContext::FLOAT_MAT3 => GlslType::Mat3,
+            WebGlRenderingContext::FLOAT_MAT4 => GlslType::Mat4,
+            WebGlRenderingContext::SAMPLER_2D => GlslType::Sampler2D,
+            WebGlRenderingContext::SAMPLER_CUBE => GlslType::SamplerCube,
+            Gl2::UNSIGNED_INT => GlslType::UnsignedInt,
+            Gl2::UNSIGNED_INT_VEC2 => GlslType::UVec2,
+            Gl2::UNSIGNED_INT_VEC3 => GlslType::UVec3,
+            Gl2::UNSIGNED_INT_VEC4 => GlslType::UVec4,
+            Gl2::FLOAT_MAT2X3 => GlslType::Mat2x3,
+            Gl2::FLOAT_MAT2X4 => GlslType::Mat2x4,
+            Gl2::FLOAT_MAT3X2 => GlslType::Mat3x2,
+            Gl2::FLOAT_MAT3X4 => GlslType::Mat3x4,
+            Gl2::FLOAT_MAT4X2 => GlslType::Mat4x2,
+            Gl2::FLOAT_MAT4X3 => GlslType::Mat4x3,
+            Gl2::SAMPLER_3D => GlslType::Sampler3D,
+            Gl2::SAMPLER_2D_ARRAY => GlslType::Sampler2DArray,
+            Gl2::SAMPLER_2D_SHADOW => GlslType::Sampler2DShadow,
+            Gl2::SAMPLER_2D_ARRAY_SHADOW => GlslType::Sampler2DArrayShadow,
+            Gl2::SAMPLER_CUBE_SHADOW => GlslType::SamplerCubeShadow,
+            Gl2::INT_SAMPLER_2D => GlslType::ISampler2D,
+            Gl2::INT_SAMPLER_3D => GlslType::ISampler3D,
+            Gl2::INT_SAMPLER_CUBE => GlslType::ISamplerCube,
+            Gl2::INT_SAMPLER_2D_ARRAY => GlslType::ISampler2DArray,
+            Gl2::UNSIGNED_INT_SAMPLER_2D => GlslType::USampler2D,
+            Gl2::UNSIGNED_INT_SAMPLER_3D => GlslType::USampler3D,
+            Gl2::UNSIGNED_INT_SAMPLER_CUBE => GlslType::USamplerCube,
+            Gl2::UNSIGNED_INT_SAMPLER_2D_ARRAY => GlslType::USampler2DArray,
+            _ => return None,
+        };
+
+        Some(glsl_type)
+    }
+
+    /// Number of scalars in a single element of this type
+    pub fn components(self) -> usize {
+        match self {
+            GlslType::Float | GlslType::Int | GlslType::UnsignedInt | GlslType::Bool => 1,
+            GlslType::Vec2 | GlslType::IVec2 | GlslType::UVec2 | GlslType::BVec2 => 2,
+            GlslType::Vec3 | GlslType::IVec3 | GlslType::UVec3 | GlslType::BVec3 => 3,
+            GlslType::Vec4 | GlslType::IVec4 | GlslType::UVec4 | GlslType::BVec4 => 4,
+            GlslType::Mat2 => 4,
+            GlslType::Mat3 => 9,
+            GlslType::Mat4 => 16,
+            GlslType::Mat2x3 | GlslType::Mat3x2 => 6,
+            GlslType::Mat2x4 | GlslType::Mat4x2 => 8,
+            GlslType::Mat3x4 | GlslType::Mat4x3 => 12,
+            _ => 1,
+        }
+    }
+
+    pub fn scalar(self) -> Scalar {
+        match self {
+            GlslType::Float | GlslType::Vec2 | GlslType::Vec3 | GlslType::Vec4 => Scalar::Float,
+            GlslType::Mat2 | GlslType::Mat3 | GlslType::Mat4 => Scalar::Float,
+            GlslType::Mat2x3 | GlslType::Mat2x4 | GlslType::Mat3x2 => Scalar::Float,
+            GlslType::Mat3x4 | GlslType::Mat4x2 | GlslType::Mat4x3 => Scalar::Float,
+            GlslType::Int | GlslType::IVec2 | GlslType::IVec3 | GlslType::IVec4 => Scalar::Int,
+            GlslType::UnsignedInt | GlslType::UVec2 | GlslType::UVec3 | GlslType::UVec4 => {
+                Scalar::UnsignedInt
+            }
+            GlslType::Bool | GlslType::BVec2 | GlslType::BVec3 | GlslType::BVec4 => Scalar::Bool,
+            _ => Scalar::Sampler,
+        }
+    }
+}
+
+impl fmt::Display for GlslType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            GlslType::Float => "float",
+            GlslType::Vec2 => "vec2",
+            GlslType::Vec3 => "vec3",
+            GlslType::Vec4 => "vec4",
+            GlslType::Int => "int",
+            GlslType::IVec2 => "ivec2",
+            GlslType::IVec3 => "ivec3",
+            GlslType::IVec4 => "ivec4",
+            GlslType::UnsignedInt => "uint",
+            GlslType::UVec2 => "uvec2",
+            GlslType::UVec3 => "uvec3",
+            GlslType::UVec4 => "uvec4",
+            GlslType::Bool => "bool",
+            GlslType::BVec2 => "bvec2",
+            GlslType::BVec3 => "bvec3",
+            GlslType::BVec4 => "bvec4",
+            GlslType::Mat2 => "mat2",
+            GlslType::Mat3 => "mat3",
+            GlslType::Mat4 => "mat4",
+            GlslType::Sampler2D => "sampler2D",
+            GlslType::SamplerCube => "samplerCube",
+            GlslType::Mat2x3 => "mat2x3",
+            GlslType::Mat2x4 => "mat2x4",
+            GlslType::Mat3x2 => "mat3x2",
+            GlslType::Mat3x4 => "mat3x4",
+            GlslType::Mat4x2 => "mat4x2",
+            GlslType::Mat4x3 => "mat4x3",
+            GlslType::Sampler3D => "sampler3D",
+            GlslType::Sampler2DArray => "sampler2DArray",
+            GlslType::Sampler2DShadow => "sampler2DShadow",
+            GlslType::Sampler2DArrayShadow => "sampler2DArrayShadow",
+            GlslType::SamplerCubeShadow => "samplerCubeShadow",
+            GlslType::ISampler2D => "isampler2D",
+            GlslType::ISampler3D => "isampler3D",
+            GlslType::ISamplerCube => "isamplerCube",
+            GlslType::ISampler2DArray => "isampler2DArray",
+            GlslType::USampler2D => "usampler2D",
+            GlslType::USampler3D => "usampler3D",
+            GlslType::USamplerCube => "usamplerCube",
+            GlslType::USampler2DArray => "usampler2DArray",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A single attribute or uniform as declared in a linked program
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveInfo {
+    /// Name without any trailing `[0]` of arrays
+    pub name: String,
+    pub kind: GlslType,
+    /// Number of array elements, 1 if it is not an array
+    pub size: i32,
+}
+
+impl ActiveInfo {
+    fn from_gl(info: WebGlActiveInfo) -> Option<Self> {
+        let name = info.name();
+        let name = name.trim_end_matches("[0]").to_owned();
+
+        Some(ActiveInfo {
+            name,
+            kind: GlslType::from_gl(info.type_())?,
+            size: info.size(),
+        })
+    }
+}
+
+impl fmt::Display for ActiveInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.size > 1 {
+            write!(f, "{}[{}]", self.kind, self.size)
+        } else {
+            write!(f, "{}", self.kind)
+        }
+    }
+}
+
+/// A uniform block as declared in a linked program
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockInfo {
+    pub name: String,
+    pub index: u32,
+    /// Size of the block in bytes
+    pub size: usize,
+    /// Byte offset of every member, named without the block name
+    pub members: Vec<(String, usize)>,
+}
+
+/// Query every active attribute of a linked program
+pub fn active_attributes(
+    context: &Gl,
+    program: &WebGlProgram,
+) -> HashMap<String, ActiveInfo> {
+    let count = context
+        .get_program_parameter(program, WebGlRenderingContext::ACTIVE_ATTRIBUTES)
+        .as_f64()
+        .unwrap_or(0.0) as u32;
+
+    (0..count)
+        .filter_map(|index| context.get_active_attrib(program, index))
+        .filter_map(ActiveInfo::from_gl)
+        .map(|info| (info.name.clone(), info))
+        .collect()
+}
+
+/// Query every active uniform of a linked program
+pub fn active_uniforms(
+    context: &Gl,
+    program: &WebGlProgram,
+) -> HashMap<String, ActiveInfo> {
+    let count = context
+        .get_program_parameter(program, WebGlRenderingContext::ACTIVE_UNIFORMS)
+        .as_f64()
+        .unwrap_or(0.0) as u32;
+
+    (0..count)
+        .filter_map(|index| context.get_active_uniform(program, index))
+        .filter_map(ActiveInfo::from_gl)
+        .map(|info| (info.name.clone(), info))
+        .collect()
+}
+
+/// Query every active uniform block of a linked program, WebGL 1 has none
+pub fn active_uniform_blocks(context: &Gl, program: &WebGlProgram) -> HashMap<String, BlockInfo> {
+    let context = match context {
+        Gl::WebGl2(context) => context,
+        Gl::WebGl1(_) => return HashMap::new(),
+    };
+
+    let count = context
+        .get_program_parameter(program, Gl2::ACTIVE_UNIFORM_BLOCKS)
+        .as_f64()
+        .unwrap_or(0.0) as u32;
+
+    (0..count)
+        .filter_map(|index| {
+            let name = context.get_active_uniform_block_name(program, index)?;
+            let size = context
+                .get_active_uniform_block_parameter(program, index, Gl2::UNIFORM_BLOCK_DATA_SIZE)
+                .ok()?
+                .as_f64()?;
+
+            let indices = context
+                .get_active_uniform_block_parameter(
+                    program,
+                    index,
+                    Gl2::UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES,
+                )
+                .ok()?;
+            let offsets = context.get_active_uniforms(program, &indices, Gl2::UNIFORM_OFFSET);
+
+            // Members of blocks with an instance name are prefixed with the block name
+            let prefix = format!("{}.", name);
+            let members = Array::from(&indices)
+                .iter()
+                .zip(Array::from(&offsets).iter())
+                .filter_map(|(member, offset)| {
+                    let member = context.get_active_uniform(program, member.as_f64()? as u32)?;
+                    let member = member.name();
+                    let member = member.trim_start_matches(prefix.as_str()).to_owned();
+                    Some((member, offset.as_f64()? as usize))
+                })
+                .collect();
+
+            Some(BlockInfo {
+                name,
+                index,
+                size: size as usize,
+                members,
+            })
+        })
+        .map(|info| (info.name.clone(), info))
+        .collect()
+}
+
+/// Query the varyings a linked program captures through transform feedback, in the order of
+/// the buffers they are written to, WebGL 1 has none
+pub fn active_varyings(context: &Gl, program: &WebGlProgram) -> Vec<ActiveInfo> {
+    let context = match context {
+        Gl::WebGl2(context) => context,
+        Gl::WebGl1(_) => return Vec::new(),
+    };
+
+    let count = context
+        .get_program_parameter(program, Gl2::TRANSFORM_FEEDBACK_VARYINGS)
+        .as_f64()
+        .unwrap_or(0.0) as u32;
+
+    (0..count)
+        .filter_map(|index| context.get_transform_feedback_varying(program, index))
+        .filter_map(ActiveInfo::from_gl)
+        .collect()
+}
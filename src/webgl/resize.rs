@@ -0,0 +1,249 @@
+/*!
+Keeps the drawing buffer of the canvas in sync with its size on the page.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlCanvasElement, Window};
+
+#[wasm_bindgen]
+extern "C" {
+    // Bound here as only later versions of web-sys have it
+    type ResizeObserver;
+
+    #[wasm_bindgen(constructor, catch)]
+    fn new(callback: &Function) -> Result<ResizeObserver, JsValue>;
+
+    #[wasm_bindgen(method)]
+    fn observe(this: &ResizeObserver, target: &Element);
+
+    #[wasm_bindgen(method)]
+    fn disconnect(this: &ResizeObserver);
+}
+
+/// How the drawing buffer is sized
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ResizeMode {
+    /// Fill the whole window
+    #[default]
+    Window,
+    /// Follow the size the canvas element is laid out with
+    Element,
+    /// A drawing buffer of exactly this many pixels
+    Fixed(u32, u32),
+    /// Follow the canvas element, drawing into the largest area with this aspect ratio
+    Letterbox(u32, u32),
+}
+
+/// Area of the drawing buffer that is drawn into, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl ResizeMode {
+    /// Resize the drawing buffer of the canvas if needed, returning the viewport to draw into
+    pub fn apply(
+        self,
+        window: &Window,
+        canvas: &HtmlCanvasElement,
+    ) -> Result<Viewport, JsValue> {
+        let ratio = window.device_pixel_ratio();
+
+        let (width, height) = match self {
+            ResizeMode::Window => {
+                let width = window.inner_width()?.as_f64().unwrap_or(0.0);
+                let height = window.inner_height()?.as_f64().unwrap_or(0.0);
+
+                let style = canvas.style();
+                style.set_property("width", &format!("{}px", width))?;
+                style.set_property("height", &format!("{}px", height))?;
+
+                (scale(width, ratio), scale(height, ratio))
+            }
+            ResizeMode::Element | ResizeMode::Letterbox(_, _) => (
+                scale(f64::from(canvas.client_width()), ratio),
+                scale(f64::from(canvas.client_height()), ratio),
+            ),
+            ResizeMode::Fixed(width, height) => (width, height),
+        };
+
+        if canvas.width() != width {
+            canvas.set_width(width);
+        }
+        if canvas.height() != height {
+            canvas.set_height(height);
+        }
+
+        Ok(self.viewport(width, height))
+    }
+
+    /// The area of a drawing buffer of `width` by `height` pixels to draw into
+    pub fn viewport(self, width: u32, height: u32) -> Viewport {
+        let (width, height) = (width as i32, height as i32);
+        match self {
+            ResizeMode::Letterbox(aspect_width, aspect_height) if aspect_height > 0 => {
+                let aspect = f64::from(aspect_width) / f64::from(aspect_height);
+
+                if f64::from(width) > f64::from(height) * aspect {
+                    let inner = (f64::from(height) * aspect) as i32;
+                    Viewport {
+                        x: (width - inner) / 2,
+                        y: 0,
+                        width: inner,
+                        height,
+                    }
+                } else {
+                    let inner = (f64::from(width) / aspect) as i32;
+                    Viewport {
+                        x: 0,
+                        y: (height - inner) / 2,
+                        width,
+                        height: inner,
+                    }
+                }
+            }
+            _ => Viewport {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            },
+        }
+    }
+}
+
+/// Pixels of the drawing buffer covering `size` CSS pixels
+fn scale(size: f64, ratio: f64) -> u32 {
+    (size * ratio) as u32
+}
+
+struct WatcherState {
+    stale: Cell<bool>,
+    listener: RefCell<Option<Box<Fn()>>>,
+}
+
+/// Marks the drawing buffer out of date when the canvas is laid out at another size or the
+/// window is resized, so the size of the canvas is only read when it could have changed
+pub struct ResizeWatcher {
+    state: Rc<WatcherState>,
+    observer: Option<ResizeObserver>,
+    callback: Closure<FnMut()>,
+}
+
+impl ResizeWatcher {
+    pub fn new(window: &Window, canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
+        let state = Rc::new(WatcherState {
+            stale: Cell::new(true),
+            listener: RefCell::new(None),
+        });
+
+        let callback_state = state.clone();
+        let callback = Closure::wrap(Box::new(move || {
+            callback_state.stale.set(true);
+            if let Some(listener) = callback_state.listener.borrow().as_ref() {
+                listener();
+            }
+        }) as Box<FnMut()>);
+
+        // Browsers without it fall back to reading the size on every frame
+        let observer = ResizeObserver::new(callback.as_ref().unchecked_ref()).ok();
+        if let Some(observer) = &observer {
+            observer.observe(canvas);
+        }
+        window.add_event_listener_with_callback("resize", callback.as_ref().unchecked_ref())?;
+
+        Ok(ResizeWatcher {
+            state,
+            observer,
+            callback,
+        })
+    }
+
+    /// Whether the size could have changed since the last call
+    pub fn take_stale(&self) -> bool {
+        self.state.stale.replace(false) || self.observer.is_none()
+    }
+
+    /// Be notified whenever the size could have changed, e.g. to draw static designs again
+    pub fn set_listener(&self, listener: Option<Box<Fn()>>) {
+        self.state.listener.replace(listener);
+    }
+}
+
+impl Drop for ResizeWatcher {
+    fn drop(&mut self) {
+        if let Some(observer) = &self.observer {
+            observer.disconnect();
+        }
+        if let Some(window) = web_sys::window() {
+            let _ = window.remove_event_listener_with_callback(
+                "resize",
+                self.callback.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(x: i32, y: i32, width: i32, height: i32) -> Viewport {
+        Viewport {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn most_modes_draw_into_the_whole_buffer() {
+        let modes = [
+            ResizeMode::Window,
+            ResizeMode::Element,
+            ResizeMode::Fixed(4, 4),
+        ];
+        for mode in &modes {
+            assert_eq!(mode.viewport(640, 480), viewport(0, 0, 640, 480));
+        }
+    }
+
+    #[test]
+    fn letterboxes_center_the_largest_area_with_the_aspect_ratio() {
+        let mode = ResizeMode::Letterbox(16, 9);
+        // Too wide, bars left and right
+        assert_eq!(mode.viewport(1000, 450), viewport(100, 0, 800, 450));
+        // Too tall, bars above and below
+        assert_eq!(mode.viewport(1600, 1000), viewport(0, 50, 1600, 900));
+        assert_eq!(mode.viewport(1920, 1080), viewport(0, 0, 1920, 1080));
+        // Odd leftovers round down on both sides
+        let square = ResizeMode::Letterbox(1, 1);
+        assert_eq!(square.viewport(5, 2), viewport(1, 0, 2, 2));
+    }
+
+    #[test]
+    fn letterboxes_without_a_height_fill_the_buffer() {
+        let mode = ResizeMode::Letterbox(16, 0);
+        assert_eq!(mode.viewport(300, 200), viewport(0, 0, 300, 200));
+        let empty = ResizeMode::Letterbox(4, 3);
+        assert_eq!(empty.viewport(0, 0), viewport(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn sizes_are_scaled_by_the_pixel_ratio() {
+        assert_eq!(scale(300.0, 1.0), 300);
+        assert_eq!(scale(300.0, 2.0), 600);
+        assert_eq!(scale(301.0, 1.5), 451);
+        assert_eq!(scale(100.5, 1.25), 125);
+        assert_eq!(scale(0.0, 3.0), 0);
+    }
+}
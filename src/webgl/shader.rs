@@ -4,6 +4,8 @@ use std::marker::PhantomData;
 
 use web_sys::{WebGlRenderingContext, WebGlShader};
 
+use crate::webgl::Gl;
+
 #[derive(Debug)]
 pub enum ShaderType<'a, T: Into<Cow<'a, str>>> {
     Vertex(T, PhantomData<&'a T>),
@@ -29,18 +31,34 @@ impl<'a, T: Into<Cow<'a, str>>> ShaderType<'a, T> {
 #[derive(Debug, Clone)]
 pub struct Shader {
     internal: WebGlShader,
+    gl_type: u32,
+    source: String,
 }
 
 impl Shader {
     pub fn new<'a, T: Into<Cow<'a, str>>>(
-        context: &WebGlRenderingContext,
+        context: &Gl,
         shader: ShaderType<'a, T>,
+    ) -> Result<Shader, String> {
+        let gl_type = shader.into_gl_type();
+        Self::compile(context, gl_type, shader.into_inner())
+    }
+
+    /// Compile the same source again, e.g. after the context was lost
+    pub fn recompile(&self, context: &Gl) -> Result<Shader, String> {
+        Self::compile(context, self.gl_type, self.source.clone())
+    }
+
+    fn compile(
+        context: &Gl,
+        gl_type: u32,
+        source: String,
     ) -> Result<Shader, String> {
         let shader_context = context
-            .create_shader(shader.into_gl_type())
+            .create_shader(gl_type)
             .ok_or_else(|| String::from("Unable to create shader object"))?;
 
-        context.shader_source(&shader_context, &shader.into_inner());
+        context.shader_source(&shader_context, &source);
         context.compile_shader(&shader_context);
 
         if context
@@ -50,6 +68,8 @@ impl Shader {
         {
             Ok(Shader {
                 internal: shader_context,
+                gl_type,
+                source,
             })
         } else {
             Err(context
@@ -61,4 +81,8 @@ impl Shader {
     pub fn as_ref(&self) -> &WebGlShader {
         &self.internal
     }
+
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
 }
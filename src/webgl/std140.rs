@@ -0,0 +1,216 @@
+/*!
+Packing of Rust values into the std140 layout of uniform blocks.
+
+Scalars are 4 bytes aligned to 4, `vec2`s are aligned to 8 and `vec3`s and `vec4`s to 16.
+Every element of an array and every column of a matrix starts on a multiple of 16, and
+structs are aligned to 16 with their size rounded up to it, which their `write_std140` takes
+care of by aligning the writer once all members are written.
+
+Structs get their implementation from `#[derive(Std140)]`, which also records the offset of
+every member so it can be checked against the layout the linked program reflects.
+
+```ignore
+#[derive(Std140)]
+struct Camera {
+    projection: [[f32; 4]; 4],
+    position: [f32; 3],
+    exposure: f32,
+}
+```
+ */
+
+/// A value which can be written into a uniform block
+pub trait Std140 {
+    /// Alignment of the value in bytes
+    const ALIGNMENT: usize;
+
+    /// Write the members of the value, the writer is already aligned for it
+    fn write_std140(&self, writer: &mut Std140Writer);
+}
+
+/// Collects values into the bytes of a uniform block, along with the offsets of named members
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Std140Writer {
+    data: Vec<u8>,
+    prefix: String,
+    offsets: Vec<(String, usize)>,
+}
+
+impl Std140Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pack a single value
+    pub fn pack<T: Std140>(value: &T) -> Self {
+        let mut writer = Self::new();
+        writer.write(value);
+        writer.align(T::ALIGNMENT);
+        writer
+    }
+
+    /// Write a value at the next offset its alignment allows
+    pub fn write<T: Std140>(&mut self, value: &T) {
+        self.align(T::ALIGNMENT);
+        value.write_std140(self);
+    }
+
+    /// Write a named member of a struct or an element of an array, recording its offset under
+    /// the name GLSL gives it, e.g. `lights[1].color`
+    pub fn member<T: Std140>(&mut self, name: &str, value: &T) {
+        self.align(T::ALIGNMENT);
+
+        let path = if self.prefix.is_empty() || name.starts_with('[') {
+            format!("{}{}", self.prefix, name)
+        } else {
+            format!("{}.{}", self.prefix, name)
+        };
+        self.offsets.push((path.clone(), self.data.len()));
+
+        let parent = std::mem::replace(&mut self.prefix, path);
+        value.write_std140(self);
+        self.prefix = parent;
+    }
+
+    /// Pad with zeros up to a multiple of `alignment`
+    pub fn align(&mut self, alignment: usize) {
+        let len = align_to(self.data.len(), alignment);
+        self.data.resize(len, 0);
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.write_u32(value as u32);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Offset the next value would be written at, before aligning it
+    pub fn offset(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Offsets of every member written through `member`
+    pub fn offsets(&self) -> &[(String, usize)] {
+        &self.offsets
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Round `offset` up to a multiple of `alignment`
+pub fn align_to(offset: usize, alignment: usize) -> usize {
+    offset.div_ceil(alignment) * alignment
+}
+
+/// Array elements and matrix columns are rounded up to the alignment of a `vec4`
+const fn array_alignment(alignment: usize) -> usize {
+    alignment.div_ceil(16) * 16
+}
+
+macro_rules! std140_scalar {
+    ($type:ty, $write:ident) => {
+        impl Std140 for $type {
+            const ALIGNMENT: usize = 4;
+
+            fn write_std140(&self, writer: &mut Std140Writer) {
+                writer.$write(*self);
+            }
+        }
+
+        impl Std140 for [$type; 2] {
+            const ALIGNMENT: usize = 8;
+
+            fn write_std140(&self, writer: &mut Std140Writer) {
+                for item in self {
+                    writer.$write(*item);
+                }
+            }
+        }
+
+        impl Std140 for [$type; 3] {
+            const ALIGNMENT: usize = 16;
+
+            fn write_std140(&self, writer: &mut Std140Writer) {
+                for item in self {
+                    writer.$write(*item);
+                }
+            }
+        }
+
+        impl Std140 for [$type; 4] {
+            const ALIGNMENT: usize = 16;
+
+            fn write_std140(&self, writer: &mut Std140Writer) {
+                for item in self {
+                    writer.$write(*item);
+                }
+            }
+        }
+    };
+}
+
+std140_scalar!(f32, write_f32);
+std140_scalar!(i32, write_i32);
+std140_scalar!(u32, write_u32);
+
+impl Std140 for bool {
+    const ALIGNMENT: usize = 4;
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_u32(*self as u32);
+    }
+}
+
+/// Arrays of any length, e.g. of lights
+impl<T: Std140> Std140 for Vec<T> {
+    const ALIGNMENT: usize = array_alignment(T::ALIGNMENT);
+
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        for (index, item) in self.iter().enumerate() {
+            writer.align(Self::ALIGNMENT);
+            writer.member(&format!("[{}]", index), item);
+        }
+        writer.align(Self::ALIGNMENT);
+    }
+}
+
+// Matrices are laid out like arrays of their columns, so both are covered by arrays of vectors
+macro_rules! std140_vector_array {
+    ($type:ty; $($len:expr),*) => {
+        $(
+            impl Std140 for [$type; $len] {
+                const ALIGNMENT: usize = 16;
+
+                fn write_std140(&self, writer: &mut Std140Writer) {
+                    for item in self {
+                        writer.align(16);
+                        writer.write(item);
+                    }
+                    writer.align(16);
+                }
+            }
+        )*
+    };
+}
+
+std140_vector_array!([f32; 2]; 2, 3, 4);
+std140_vector_array!([f32; 3]; 2, 3, 4);
+std140_vector_array!([f32; 4]; 2, 3, 4);
+std140_vector_array!([i32; 2]; 2, 3, 4);
+std140_vector_array!([i32; 3]; 2, 3, 4);
+std140_vector_array!([i32; 4]; 2, 3, 4);
+std140_vector_array!([u32; 2]; 2, 3, 4);
+std140_vector_array!([u32; 3]; 2, 3, 4);
+std140_vector_array!([u32; 4]; 2, 3, 4);
@@ -0,0 +1,60 @@
+/*!
+Textures allocated on the context, e.g. to be drawn into through a framebuffer.
+ */
+
+use crate::webgl::{Backend, Gl, TextureFormat};
+
+pub struct Texture<B: Backend = Gl> {
+    object: B::Texture,
+    width: i32,
+    height: i32,
+    format: TextureFormat,
+    /// Texels uploaded to the texture, kept to upload them again after the context is lost
+    data: Option<B::Data>,
+}
+
+impl<B: Backend> Texture<B> {
+    pub fn new(
+        object: B::Texture,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        data: Option<B::Data>,
+    ) -> Self {
+        Self {
+            object,
+            width,
+            height,
+            format,
+            data,
+        }
+    }
+
+    pub fn as_ref(&self) -> &B::Texture {
+        &self.object
+    }
+
+    pub fn set_object(&mut self, object: B::Texture) {
+        self.object = object;
+    }
+
+    pub fn get_width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn get_format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub fn get_data(&self) -> Option<&B::Data> {
+        self.data.as_ref()
+    }
+
+    pub fn set_data(&mut self, data: Option<B::Data>) {
+        self.data = data;
+    }
+}
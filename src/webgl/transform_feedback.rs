@@ -0,0 +1,30 @@
+/*!
+Transform feedback, capturing the varyings of a draw into the buffers of attributes so the GPU
+can update them for the next frame, e.g. the positions of particles.
+ */
+
+use crate::webgl::{AttributeHandle, Backend, Gl};
+
+pub struct TransformFeedback<B: Backend = Gl> {
+    object: B::TransformFeedback,
+    captures: Vec<AttributeHandle>,
+}
+
+impl<B: Backend> TransformFeedback<B> {
+    pub fn new(object: B::TransformFeedback, captures: Vec<AttributeHandle>) -> Self {
+        Self { object, captures }
+    }
+
+    pub fn get_object(&self) -> &B::TransformFeedback {
+        &self.object
+    }
+
+    pub fn set_object(&mut self, object: B::TransformFeedback) {
+        self.object = object;
+    }
+
+    /// The attribute each varying is written into, in the order the program captures them
+    pub fn get_captures(&self) -> &[AttributeHandle] {
+        &self.captures
+    }
+}
@@ -4,6 +4,8 @@ The Uniform class holds a vector of the inner types and offers many coersions of
 
 use std::rc::Rc;
 
+use super::reflect::{ActiveInfo, Scalar};
+
 #[derive(Clone)]
 pub enum UniformInner {
     Uniform1i(i32, Option<Rc<Fn(f64) -> i32>>),
@@ -14,8 +16,50 @@ pub enum UniformInner {
     Uniform3f(f64, f64, f64, Option<Rc<Fn(f64) -> [f64; 3]>>),
     Uniform4i(i32, i32, i32, i32, Option<Rc<Fn(f64) -> [i32; 4]>>),
     Uniform4f(f64, f64, f64, f64, Option<Rc<Fn(f64) -> [f64; 4]>>),
+    /// Column-major matrices, the flag requests the data to be transposed on upload
+    UniformMatrix2fv([f32; 4], bool, Option<Rc<Fn(f64) -> [f32; 4]>>),
+    UniformMatrix3fv([f32; 9], bool, Option<Rc<Fn(f64) -> [f32; 9]>>),
+    UniformMatrix4fv([f32; 16], bool, Option<Rc<Fn(f64) -> [f32; 16]>>),
+    /// Arrays, the data holds every element flattened one after the other
+    Uniform1iv(Vec<i32>, Option<Rc<Fn(f64) -> Vec<i32>>>),
+    Uniform1fv(Vec<f32>, Option<Rc<Fn(f64) -> Vec<f32>>>),
+    Uniform2iv(Vec<i32>, Option<Rc<Fn(f64) -> Vec<i32>>>),
+    Uniform2fv(Vec<f32>, Option<Rc<Fn(f64) -> Vec<f32>>>),
+    Uniform3iv(Vec<i32>, Option<Rc<Fn(f64) -> Vec<i32>>>),
+    Uniform3fv(Vec<f32>, Option<Rc<Fn(f64) -> Vec<f32>>>),
+    Uniform4iv(Vec<i32>, Option<Rc<Fn(f64) -> Vec<i32>>>),
+    Uniform4fv(Vec<f32>, Option<Rc<Fn(f64) -> Vec<f32>>>),
+    /// Booleans, uploaded as integers
+    Uniform1b(bool, Option<Rc<Fn(f64) -> bool>>),
+    Uniform2b(bool, bool, Option<Rc<Fn(f64) -> [bool; 2]>>),
+    Uniform3b(bool, bool, bool, Option<Rc<Fn(f64) -> [bool; 3]>>),
+    Uniform4b(bool, bool, bool, bool, Option<Rc<Fn(f64) -> [bool; 4]>>),
+    /// Samplers hold the texture unit they read from
+    Sampler2D(i32, Option<Rc<Fn(f64) -> Sampler2D>>),
+    SamplerCube(i32, Option<Rc<Fn(f64) -> SamplerCube>>),
+    /// Values kept up to date by the context itself
+    Builtin(Builtin),
+}
+
+/// Uniforms filled in by rugl every frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    /// Size of the viewport in pixels, a `vec2`
+    Resolution,
+    /// The device pixel ratio the drawing buffer is scaled by, a `float`
+    PixelRatio,
+    /// How far the frame is between two fixed timestep ticks, a `float` from 0 to 1
+    Interpolation,
 }
 
+/// Texture unit given to a `sampler2D` uniform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler2D(pub i32);
+
+/// Texture unit given to a `samplerCube` uniform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplerCube(pub i32);
+
 impl UniformInner {
     pub fn is_dynamic(&self) -> bool {
         match self {
@@ -27,10 +71,256 @@ impl UniformInner {
             UniformInner::Uniform3f(_, _, _, func) => func.is_some(),
             UniformInner::Uniform4i(_, _, _, _, func) => func.is_some(),
             UniformInner::Uniform4f(_, _, _, _, func) => func.is_some(),
+            UniformInner::UniformMatrix2fv(_, _, func) => func.is_some(),
+            UniformInner::UniformMatrix3fv(_, _, func) => func.is_some(),
+            UniformInner::UniformMatrix4fv(_, _, func) => func.is_some(),
+            UniformInner::Uniform1iv(_, func) => func.is_some(),
+            UniformInner::Uniform1fv(_, func) => func.is_some(),
+            UniformInner::Uniform2iv(_, func) => func.is_some(),
+            UniformInner::Uniform2fv(_, func) => func.is_some(),
+            UniformInner::Uniform3iv(_, func) => func.is_some(),
+            UniformInner::Uniform3fv(_, func) => func.is_some(),
+            UniformInner::Uniform4iv(_, func) => func.is_some(),
+            UniformInner::Uniform4fv(_, func) => func.is_some(),
+            UniformInner::Uniform1b(_, func) => func.is_some(),
+            UniformInner::Uniform2b(_, _, func) => func.is_some(),
+            UniformInner::Uniform3b(_, _, _, func) => func.is_some(),
+            UniformInner::Uniform4b(_, _, _, _, func) => func.is_some(),
+            UniformInner::Sampler2D(_, func) => func.is_some(),
+            UniformInner::SamplerCube(_, func) => func.is_some(),
+            UniformInner::Builtin(_) => false,
+        }
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        matches!(self, UniformInner::Builtin(_))
+    }
+
+    /// Request a matrix uniform to be transposed when it is uploaded.
+    ///
+    /// WebGL 1 does not allow this, binding a transposed matrix returns an error there.
+    pub fn transposed(self) -> Self {
+        match self {
+            UniformInner::UniformMatrix2fv(data, _, func) => UniformInner::UniformMatrix2fv(data, true, func),
+            UniformInner::UniformMatrix3fv(data, _, func) => UniformInner::UniformMatrix3fv(data, true, func),
+            UniformInner::UniformMatrix4fv(data, _, func) => UniformInner::UniformMatrix4fv(data, true, func),
+            other => other,
+        }
+    }
+
+    pub fn is_transposed(&self) -> bool {
+        match self {
+            UniformInner::UniformMatrix2fv(_, transpose, _) => *transpose,
+            UniformInner::UniformMatrix3fv(_, transpose, _) => *transpose,
+            UniformInner::UniformMatrix4fv(_, transpose, _) => *transpose,
+            _ => false,
+        }
+    }
+
+    /// Evaluate the uniform, dynamic uniforms are called with the given tick
+    pub fn value(&self, tick: f64) -> UniformValue {
+        match self {
+            UniformInner::Uniform1i(val, func) => {
+                UniformValue::Int(vec![func.as_ref().map_or(*val, |func| func(tick))])
+            }
+            UniformInner::Uniform1f(val, func) => {
+                UniformValue::Float(vec![func.as_ref().map_or(*val, |func| func(tick)) as f32])
+            }
+            UniformInner::Uniform2i(val1, val2, func) => UniformValue::Int(
+                func.as_ref().map_or([*val1, *val2], |func| func(tick)).to_vec(),
+            ),
+            UniformInner::Uniform2f(val1, val2, func) => UniformValue::Float(flatten_array(&[
+                func.as_ref().map_or([*val1, *val2], |func| func(tick)),
+            ])),
+            UniformInner::Uniform3i(val1, val2, val3, func) => UniformValue::Int(
+                func.as_ref().map_or([*val1, *val2, *val3], |func| func(tick)).to_vec(),
+            ),
+            UniformInner::Uniform3f(val1, val2, val3, func) => UniformValue::Float(flatten_array(&[
+                func.as_ref().map_or([*val1, *val2, *val3], |func| func(tick)),
+            ])),
+            UniformInner::Uniform4i(val1, val2, val3, val4, func) => UniformValue::Int(
+                func.as_ref().map_or([*val1, *val2, *val3, *val4], |func| func(tick)).to_vec(),
+            ),
+            UniformInner::Uniform4f(val1, val2, val3, val4, func) => UniformValue::Float(flatten_array(&[
+                func.as_ref().map_or([*val1, *val2, *val3, *val4], |func| func(tick)),
+            ])),
+            UniformInner::UniformMatrix2fv(val, _, func) => {
+                UniformValue::Float(func.as_ref().map_or(*val, |func| func(tick)).to_vec())
+            }
+            UniformInner::UniformMatrix3fv(val, _, func) => {
+                UniformValue::Float(func.as_ref().map_or(*val, |func| func(tick)).to_vec())
+            }
+            UniformInner::UniformMatrix4fv(val, _, func) => {
+                UniformValue::Float(func.as_ref().map_or(*val, |func| func(tick)).to_vec())
+            }
+            UniformInner::Uniform1iv(val, func)
+            | UniformInner::Uniform2iv(val, func)
+            | UniformInner::Uniform3iv(val, func)
+            | UniformInner::Uniform4iv(val, func) => {
+                UniformValue::Int(func.as_ref().map_or_else(|| val.clone(), |func| func(tick)))
+            }
+            UniformInner::Uniform1fv(val, func)
+            | UniformInner::Uniform2fv(val, func)
+            | UniformInner::Uniform3fv(val, func)
+            | UniformInner::Uniform4fv(val, func) => {
+                UniformValue::Float(func.as_ref().map_or_else(|| val.clone(), |func| func(tick)))
+            }
+            UniformInner::Uniform1b(val, func) => {
+                UniformValue::Int(vec![func.as_ref().map_or(*val, |func| func(tick)) as i32])
+            }
+            UniformInner::Uniform2b(val1, val2, func) => UniformValue::Int(flatten_array(&[
+                func.as_ref().map_or([*val1, *val2], |func| func(tick)),
+            ])),
+            UniformInner::Uniform3b(val1, val2, val3, func) => UniformValue::Int(flatten_array(&[
+                func.as_ref().map_or([*val1, *val2, *val3], |func| func(tick)),
+            ])),
+            UniformInner::Uniform4b(val1, val2, val3, val4, func) => UniformValue::Int(flatten_array(&[
+                func.as_ref().map_or([*val1, *val2, *val3, *val4], |func| func(tick)),
+            ])),
+            UniformInner::Sampler2D(unit, func) => {
+                UniformValue::Int(vec![func.as_ref().map_or(*unit, |func| func(tick).0)])
+            }
+            UniformInner::SamplerCube(unit, func) => {
+                UniformValue::Int(vec![func.as_ref().map_or(*unit, |func| func(tick).0)])
+            }
+            // Only placeholders, the context provides the actual values
+            UniformInner::Builtin(Builtin::Resolution) => UniformValue::Float(vec![0.0; 2]),
+            UniformInner::Builtin(Builtin::PixelRatio) => UniformValue::Float(vec![1.0]),
+            UniformInner::Builtin(Builtin::Interpolation) => UniformValue::Float(vec![0.0]),
         }
     }
 }
 
+/// The scalars of an evaluated uniform, ready to be uploaded
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniformValue {
+    Int(Vec<i32>),
+    Float(Vec<f32>),
+}
+
+impl UniformValue {
+    pub fn len(&self) -> usize {
+        match self {
+            UniformValue::Int(val) => val.len(),
+            UniformValue::Float(val) => val.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Convert the value into the scalars a uniform was declared with, rejecting
+    /// values which do not fit the declaration
+    pub fn coerce(self, info: &ActiveInfo) -> Result<UniformValue, String> {
+        let components = info.kind.components();
+        let len = self.len();
+
+        if len == 0 || !len.is_multiple_of(components) || len / components > info.size as usize {
+            return Err(format!(
+                "Uniform: {} is declared as {} but was given {} component(s)!",
+                info.name, info, len
+            ));
+        }
+
+        match (info.kind.scalar(), self) {
+            (Scalar::Float, UniformValue::Int(val)) => {
+                Ok(UniformValue::Float(val.into_iter().map(|val| val as f32).collect()))
+            }
+            (Scalar::Bool, UniformValue::Int(val)) => {
+                Ok(UniformValue::Int(val.into_iter().map(|val| (val != 0) as i32).collect()))
+            }
+            (Scalar::Bool, UniformValue::Float(val)) => {
+                Ok(UniformValue::Int(val.into_iter().map(|val| (val != 0.0) as i32).collect()))
+            }
+            (Scalar::UnsignedInt, UniformValue::Int(ref val)) if val.iter().any(|val| *val < 0) => {
+                Err(format!(
+                    "Uniform: {} is declared as {} but was given a negative value!",
+                    info.name, info
+                ))
+            }
+            (Scalar::Int, UniformValue::Float(_))
+            | (Scalar::UnsignedInt, UniformValue::Float(_))
+            | (Scalar::Sampler, UniformValue::Float(_)) => {
+                Err(format!(
+                    "Uniform: {} is declared as {} but was given a float!",
+                    info.name, info
+                ))
+            }
+            (_, val) => Ok(val),
+        }
+    }
+}
+
+/// Copy matrix elements into the flat buffer uploaded to WebGL
+fn copy_matrix<'a, T: Copy + Into<f64> + 'a, I: Iterator<Item = &'a T>>(values: I, out: &mut [f32]) {
+    for (out, value) in out.iter_mut().zip(values) {
+        *out = (*value).into() as f32;
+    }
+}
+
+/// A single element of a uniform array
+pub trait ArrayElement {
+    type Scalar;
+
+    fn extend_into(&self, out: &mut Vec<Self::Scalar>);
+}
+
+impl ArrayElement for i32 {
+    type Scalar = i32;
+
+    fn extend_into(&self, out: &mut Vec<i32>) {
+        out.push(*self);
+    }
+}
+
+impl ArrayElement for bool {
+    type Scalar = i32;
+
+    fn extend_into(&self, out: &mut Vec<i32>) {
+        out.push(*self as i32);
+    }
+}
+
+impl ArrayElement for f64 {
+    type Scalar = f32;
+
+    fn extend_into(&self, out: &mut Vec<f32>) {
+        out.push(*self as f32);
+    }
+}
+
+macro_rules! array_element {
+    ($type:ty, $scalar:ty, $num:expr) => {
+        impl ArrayElement for [$type; $num] {
+            type Scalar = $scalar;
+
+            fn extend_into(&self, out: &mut Vec<$scalar>) {
+                out.extend(self.iter().map(|item| *item as $scalar));
+            }
+        }
+    };
+}
+
+array_element!(i32, i32, 2);
+array_element!(i32, i32, 3);
+array_element!(i32, i32, 4);
+array_element!(bool, i32, 2);
+array_element!(bool, i32, 3);
+array_element!(bool, i32, 4);
+array_element!(f64, f32, 2);
+array_element!(f64, f32, 3);
+array_element!(f64, f32, 4);
+
+/// Flatten array elements into the list uploaded to WebGL
+fn flatten_array<T: ArrayElement>(items: &[T]) -> Vec<T::Scalar> {
+    let mut data = Vec::new();
+    for item in items {
+        item.extend_into(&mut data);
+    }
+    data
+}
+
 #[derive(Clone)]
 pub struct Uniform {
     name: String,
@@ -45,6 +335,14 @@ impl Uniform {
     pub fn get_data(&self) -> UniformInner {
         self.data.clone()
     }
+
+    /// Turn a struct field into its full uniform name, e.g. `position` into `light.position`
+    pub fn with_prefix(self, prefix: &str) -> Self {
+        Uniform {
+            name: format!("{}.{}", prefix, self.name),
+            data: self.data,
+        }
+    }
 }
 
 impl From<(String, UniformInner)> for Uniform {
@@ -138,4 +436,278 @@ impl From<Rc<Fn(f64)->[f64; 4]>> for UniformInner {
     fn from(func: Rc<Fn(f64)->[f64; 4]>) -> Self {
         UniformInner::Uniform4f(0.0, 0.0, 0.0, 0.0, Some(func))
     }
-}
\ No newline at end of file
+}
+
+impl From<[i32; 4]> for UniformInner {
+    fn from(item: [i32; 4]) -> Self {
+        UniformInner::Uniform4i(item[0], item[1], item[2], item[3], None)
+    }
+}
+
+impl From<Rc<Fn(f64)->[i32; 4]>> for UniformInner {
+    fn from(func: Rc<Fn(f64)->[i32; 4]>) -> Self {
+        UniformInner::Uniform4i(0, 0, 0, 0, Some(func))
+    }
+}
+
+impl From<bool> for UniformInner {
+    fn from(item: bool) -> Self {
+        UniformInner::Uniform1b(item, None)
+    }
+}
+
+impl From<Rc<Fn(f64)->bool>> for UniformInner {
+    fn from(func: Rc<Fn(f64)->bool>) -> Self {
+        UniformInner::Uniform1b(false, Some(func))
+    }
+}
+
+impl From<[bool; 2]> for UniformInner {
+    fn from(item: [bool; 2]) -> Self {
+        UniformInner::Uniform2b(item[0], item[1], None)
+    }
+}
+
+impl From<Rc<Fn(f64)->[bool; 2]>> for UniformInner {
+    fn from(func: Rc<Fn(f64)->[bool; 2]>) -> Self {
+        UniformInner::Uniform2b(false, false, Some(func))
+    }
+}
+
+impl From<[bool; 3]> for UniformInner {
+    fn from(item: [bool; 3]) -> Self {
+        UniformInner::Uniform3b(item[0], item[1], item[2], None)
+    }
+}
+
+impl From<Rc<Fn(f64)->[bool; 3]>> for UniformInner {
+    fn from(func: Rc<Fn(f64)->[bool; 3]>) -> Self {
+        UniformInner::Uniform3b(false, false, false, Some(func))
+    }
+}
+
+impl From<[bool; 4]> for UniformInner {
+    fn from(item: [bool; 4]) -> Self {
+        UniformInner::Uniform4b(item[0], item[1], item[2], item[3], None)
+    }
+}
+
+impl From<Rc<Fn(f64)->[bool; 4]>> for UniformInner {
+    fn from(func: Rc<Fn(f64)->[bool; 4]>) -> Self {
+        UniformInner::Uniform4b(false, false, false, false, Some(func))
+    }
+}
+
+impl From<Sampler2D> for UniformInner {
+    fn from(item: Sampler2D) -> Self {
+        UniformInner::Sampler2D(item.0, None)
+    }
+}
+
+impl From<Rc<Fn(f64)->Sampler2D>> for UniformInner {
+    fn from(func: Rc<Fn(f64)->Sampler2D>) -> Self {
+        UniformInner::Sampler2D(0, Some(func))
+    }
+}
+
+impl From<SamplerCube> for UniformInner {
+    fn from(item: SamplerCube) -> Self {
+        UniformInner::SamplerCube(item.0, None)
+    }
+}
+
+impl From<Rc<Fn(f64)->SamplerCube>> for UniformInner {
+    fn from(func: Rc<Fn(f64)->SamplerCube>) -> Self {
+        UniformInner::SamplerCube(0, Some(func))
+    }
+}
+
+impl From<Builtin> for UniformInner {
+    fn from(item: Builtin) -> Self {
+        UniformInner::Builtin(item)
+    }
+}
+
+// Matrices, nested arrays are read as a list of columns
+__impl_from_matrix!([[f32; 2]; 2], 4, UniformMatrix2fv, .flatten());
+__impl_from_matrix!([[f64; 2]; 2], 4, UniformMatrix2fv, .flatten());
+__impl_from_matrix!([[f32; 3]; 3], 9, UniformMatrix3fv, .flatten());
+__impl_from_matrix!([[f64; 3]; 3], 9, UniformMatrix3fv, .flatten());
+__impl_from_matrix!([[f32; 4]; 4], 16, UniformMatrix4fv, .flatten());
+__impl_from_matrix!([[f64; 4]; 4], 16, UniformMatrix4fv, .flatten());
+__impl_from_matrix!([f32; 9], 9, UniformMatrix3fv,);
+__impl_from_matrix!([f64; 9], 9, UniformMatrix3fv,);
+__impl_from_matrix!([f32; 16], 16, UniformMatrix4fv,);
+__impl_from_matrix!([f64; 16], 16, UniformMatrix4fv,);
+
+// Arrays, e.g. `uniform vec3 lights[8]` from `[[f64; 3]; 8]`. Lengths of 2 to 4 are vectors
+// and square float arrays are matrices, use a `Vec` to upload those as arrays.
+__impl_from_array!(i32, Uniform1iv);
+__impl_from_array!(f64, Uniform1fv);
+__impl_from_array!(bool, Uniform1iv);
+__impl_from_array!([bool; 2], Uniform2iv);
+__impl_from_array!([bool; 3], Uniform3iv);
+__impl_from_array!([bool; 4], Uniform4iv);
+__impl_from_array!([i32; 2], Uniform2iv);
+__impl_from_array!([f64; 2], Uniform2fv);
+__impl_from_array!([i32; 3], Uniform3iv);
+__impl_from_array!([f64; 3], Uniform3fv);
+__impl_from_array!([i32; 4], Uniform4iv);
+__impl_from_array!([f64; 4], Uniform4fv);
+__impl_from_repeat_array!(i32, Uniform1iv, [1, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!(f64, Uniform1fv, [1, 5, 6, 7, 8, 10, 11, 12, 13, 14, 15]);
+__impl_from_repeat_array!(bool, Uniform1iv, [1, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([i32; 2], Uniform2iv, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([f64; 2], Uniform2fv, [1, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([i32; 3], Uniform3iv, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([f64; 3], Uniform3fv, [1, 2, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([i32; 4], Uniform4iv, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([f64; 4], Uniform4fv, [1, 2, 3, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([bool; 2], Uniform2iv, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([bool; 3], Uniform3iv, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+__impl_from_repeat_array!([bool; 4], Uniform4iv, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webgl::GlslType;
+
+    fn info(name: &str, kind: GlslType, size: i32) -> ActiveInfo {
+        ActiveInfo {
+            name: name.to_owned(),
+            kind,
+            size,
+        }
+    }
+
+    fn coerce(value: UniformValue, kind: GlslType, size: i32) -> Result<UniformValue, String> {
+        value.coerce(&info("value", kind, size))
+    }
+
+    #[test]
+    fn matching_values_are_kept() {
+        let floats = UniformValue::Float(vec![0.5, 1.0, 2.0]);
+        assert_eq!(coerce(floats.clone(), GlslType::Vec3, 1), Ok(floats));
+
+        let ints = UniformValue::Int(vec![-1, 2]);
+        assert_eq!(coerce(ints.clone(), GlslType::IVec2, 1), Ok(ints));
+
+        let unsigned = UniformValue::Int(vec![0, 7]);
+        assert_eq!(coerce(unsigned.clone(), GlslType::UVec2, 1), Ok(unsigned));
+
+        let unit = UniformValue::Int(vec![3]);
+        assert_eq!(coerce(unit.clone(), GlslType::Sampler2D, 1), Ok(unit));
+
+        let matrix = UniformValue::Float(vec![1.0; 16]);
+        assert_eq!(coerce(matrix.clone(), GlslType::Mat4, 1), Ok(matrix));
+    }
+
+    #[test]
+    fn integers_become_floats() {
+        assert_eq!(
+            coerce(UniformValue::Int(vec![1, -2]), GlslType::Vec2, 1),
+            Ok(UniformValue::Float(vec![1.0, -2.0]))
+        );
+    }
+
+    #[test]
+    fn booleans_are_zero_or_one() {
+        assert_eq!(
+            coerce(UniformValue::Int(vec![0, 5, -1]), GlslType::BVec3, 1),
+            Ok(UniformValue::Int(vec![0, 1, 1]))
+        );
+        assert_eq!(
+            coerce(UniformValue::Float(vec![0.0, 0.25]), GlslType::BVec2, 1),
+            Ok(UniformValue::Int(vec![0, 1]))
+        );
+    }
+
+    #[test]
+    fn arrays_take_up_to_their_size() {
+        let two = UniformValue::Float(vec![1.0; 4]);
+        assert_eq!(coerce(two.clone(), GlslType::Vec2, 3), Ok(two));
+
+        let three = UniformValue::Float(vec![1.0; 6]);
+        assert_eq!(coerce(three.clone(), GlslType::Vec2, 3), Ok(three));
+    }
+
+    #[test]
+    fn wrong_component_counts_are_rejected() {
+        let err = "Uniform: value is declared as vec3 but was given 2 component(s)!";
+        assert_eq!(
+            coerce(UniformValue::Float(vec![1.0; 2]), GlslType::Vec3, 1),
+            Err(err.to_owned())
+        );
+
+        let err = "Uniform: value is declared as float but was given 0 component(s)!";
+        assert_eq!(
+            coerce(UniformValue::Float(Vec::new()), GlslType::Float, 1),
+            Err(err.to_owned())
+        );
+
+        let err = "Uniform: value is declared as vec2[3] but was given 8 component(s)!";
+        assert_eq!(
+            coerce(UniformValue::Float(vec![1.0; 8]), GlslType::Vec2, 3),
+            Err(err.to_owned())
+        );
+
+        let err = "Uniform: value is declared as mat3 but was given 16 component(s)!";
+        assert_eq!(
+            coerce(UniformValue::Float(vec![1.0; 16]), GlslType::Mat3, 1),
+            Err(err.to_owned())
+        );
+    }
+
+    #[test]
+    fn negative_unsigned_values_are_rejected() {
+        let err = "Uniform: value is declared as uvec2 but was given a negative value!";
+        assert_eq!(
+            coerce(UniformValue::Int(vec![1, -1]), GlslType::UVec2, 1),
+            Err(err.to_owned())
+        );
+    }
+
+    #[test]
+    fn scalar_arrays_are_uploaded_as_arrays() {
+        let weights = UniformInner::from([0.5; 5]);
+        assert!(matches!(weights, UniformInner::Uniform1fv(..)));
+        assert_eq!(weights.value(0.0), UniformValue::Float(vec![0.5; 5]));
+
+        let counts = UniformInner::from([3; 16]);
+        assert!(matches!(counts, UniformInner::Uniform1iv(..)));
+        assert_eq!(counts.value(0.0), UniformValue::Int(vec![3; 16]));
+
+        let flags = UniformInner::from([true, false, true, false, true]);
+        assert_eq!(flags.value(0.0), UniformValue::Int(vec![1, 0, 1, 0, 1]));
+    }
+
+    #[test]
+    fn square_lengths_are_vectors_and_matrices() {
+        let vector = UniformInner::from([0.5; 3]);
+        assert!(matches!(vector, UniformInner::Uniform3f(..)));
+        let matrix = UniformInner::from([0.5; 9]);
+        assert!(matches!(matrix, UniformInner::UniformMatrix3fv(..)));
+        let columns = UniformInner::from([[0.5; 2]; 2]);
+        assert!(matches!(columns, UniformInner::UniformMatrix2fv(..)));
+
+        let vectors = UniformInner::from([[0.5; 2]; 3]);
+        assert!(matches!(vectors, UniformInner::Uniform2fv(..)));
+        let scalars = UniformInner::from(vec![0.5; 3]);
+        assert!(matches!(scalars, UniformInner::Uniform1fv(..)));
+    }
+
+    #[test]
+    fn floats_are_not_truncated_into_integers() {
+        for (kind, name) in &[
+            (GlslType::Int, "int"),
+            (GlslType::UnsignedInt, "uint"),
+            (GlslType::Sampler2D, "sampler2D"),
+        ] {
+            let err = format!(
+                "Uniform: value is declared as {} but was given a float!",
+                name
+            );
+            assert_eq!(coerce(UniformValue::Float(vec![1.0]), *kind, 1), Err(err));
+        }
+    }
+}
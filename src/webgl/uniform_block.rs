@@ -0,0 +1,145 @@
+/*!
+Uniform blocks shared by every program declaring them, uploaded once instead of per program.
+ */
+
+use crate::webgl::{BlockInfo, BufferHandle, Std140Writer};
+
+pub struct UniformBlock {
+    name: String,
+    binding: u32,
+    buffer: BufferHandle,
+    packed: Std140Writer,
+}
+
+impl UniformBlock {
+    pub fn new(name: String, binding: u32, buffer: BufferHandle, packed: Std140Writer) -> Self {
+        Self {
+            name,
+            binding,
+            buffer,
+            packed,
+        }
+    }
+
+    /// Name of the block as declared in the shaders
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Binding point the buffer is bound to and every program's block refers to
+    pub fn get_binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn get_buffer(&self) -> BufferHandle {
+        self.buffer
+    }
+
+    /// The std140 packed contents
+    pub fn get_data(&self) -> &[u8] {
+        self.packed.as_bytes()
+    }
+
+    /// Offsets the named members were packed at
+    pub fn get_offsets(&self) -> &[(String, usize)] {
+        self.packed.offsets()
+    }
+
+    pub fn set_data(&mut self, packed: Std140Writer) {
+        self.packed = packed;
+    }
+}
+
+/// Check that members packed by a derived layout are where the program expects them, values
+/// packed without one only record array elements and are not checked
+pub fn check_layout(info: &BlockInfo, offsets: &[(String, usize)]) -> Result<(), String> {
+    if offsets.iter().all(|(name, _)| name.starts_with('[')) {
+        return Ok(());
+    }
+
+    for (member, offset) in &info.members {
+        let packed = offsets
+            .iter()
+            .find(|(name, _)| name == member)
+            .map(|(_, packed)| *packed);
+
+        match packed {
+            Some(packed) if packed == *offset => {}
+            Some(packed) => {
+                return Err(format!(
+                    "Uniform block: {} has {} at offset {} but it was packed at {}!",
+                    info.name, member, offset, packed
+                ));
+            }
+            None => {
+                return Err(format!(
+                    "Uniform block: {} has {} at offset {} but it was not packed!",
+                    info.name, member, offset
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(members: &[(&str, usize)]) -> BlockInfo {
+        BlockInfo {
+            name: String::from("Camera"),
+            index: 0,
+            size: 80,
+            members: members
+                .iter()
+                .map(|(name, offset)| (String::from(*name), *offset))
+                .collect(),
+        }
+    }
+
+    fn offsets(members: &[(&str, usize)]) -> Vec<(String, usize)> {
+        camera(members).members
+    }
+
+    #[test]
+    fn matching_layouts_pass() {
+        let members = [("projection", 0), ("position", 64), ("exposure", 76)];
+        assert_eq!(check_layout(&camera(&members), &offsets(&members)), Ok(()));
+    }
+
+    #[test]
+    fn members_at_other_offsets_are_rejected() {
+        let info = camera(&[("projection", 0), ("position", 64)]);
+        let packed = offsets(&[("projection", 0), ("position", 72)]);
+        assert_eq!(
+            check_layout(&info, &packed),
+            Err(String::from(
+                "Uniform block: Camera has position at offset 64 but it was packed at 72!"
+            ))
+        );
+    }
+
+    #[test]
+    fn members_which_were_not_packed_are_rejected() {
+        let info = camera(&[("projection", 0), ("exposure", 76)]);
+        let packed = offsets(&[("projection", 0), ("exposure_time", 76)]);
+        assert_eq!(
+            check_layout(&info, &packed),
+            Err(String::from(
+                "Uniform block: Camera has exposure at offset 76 but it was not packed!"
+            ))
+        );
+    }
+
+    #[test]
+    fn values_without_named_members_are_not_checked() {
+        let info = camera(&[("weights[0]", 0)]);
+        assert_eq!(check_layout(&info, &[]), Ok(()));
+        assert_eq!(
+            check_layout(&info, &offsets(&[("[0]", 0), ("[1]", 16)])),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,30 @@
+/*!
+The attribute bindings of a draw, captured once in a vertex array object when the context
+supports them.
+ */
+
+use crate::webgl::{AttributeHandle, Backend, Gl};
+
+pub struct VertexArray<B: Backend = Gl> {
+    object: Option<B::VertexArray>,
+    attributes: Vec<AttributeHandle>,
+}
+
+impl<B: Backend> VertexArray<B> {
+    pub fn new(object: Option<B::VertexArray>, attributes: Vec<AttributeHandle>) -> Self {
+        Self { object, attributes }
+    }
+
+    /// The vertex array object, `None` when attributes are enabled on every draw instead
+    pub fn get_object(&self) -> Option<&B::VertexArray> {
+        self.object.as_ref()
+    }
+
+    pub fn set_object(&mut self, object: Option<B::VertexArray>) {
+        self.object = object;
+    }
+
+    pub fn get_attributes(&self) -> &[AttributeHandle] {
+        &self.attributes
+    }
+}
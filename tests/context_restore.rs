@@ -0,0 +1,86 @@
+//! Losing a WebGL context needs a browser, run with `wasm-pack test --headless --chrome`
+#![cfg(target_arch = "wasm32")]
+
+use rugl::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// Resolves on the next `name` event of the canvas, listening from the moment it is called
+fn next_event(canvas: &web_sys::HtmlCanvasElement, name: &str) -> JsFuture {
+    let promise = Promise::new(&mut |resolve, _| {
+        canvas
+            .add_event_listener_with_callback(name, &resolve)
+            .unwrap();
+    });
+    JsFuture::from(promise)
+}
+
+fn center(rugl: &Rugl) -> Vec<u8> {
+    let image = rugl.screenshot().unwrap();
+    let (x, y) = (image.get_width() / 2, image.get_height() / 2);
+    let start = ((y * image.get_width() + x) * 4) as usize;
+    image.get_pixels()[start..start + 4].to_vec()
+}
+
+#[wasm_bindgen_test]
+async fn draws_again_after_the_context_is_restored() {
+    let body = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.body())
+        .unwrap();
+    let instance = Rugl::mount(
+        CanvasTarget::AppendTo(body.into()),
+        rugl_description!(
+            vertex: { "
+                attribute vec2 position;
+                void main() {
+                    gl_Position = vec4(position, 0.0, 1.0);
+                }
+            " },
+            fragment: { "
+                precision mediump float;
+                uniform vec4 color;
+                void main() {
+                    gl_FragColor = color;
+                }
+            " },
+            attributes: {
+                position: [[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]],
+            },
+            uniforms: {
+                color: [1.0, 0.5, 0.0, 1.0]
+            },
+            count: { 3 },
+            resize: { ResizeMode::Fixed(16, 16) },
+            context: { preserve_drawing_buffer: true }
+        ),
+    )
+    .unwrap();
+    let rugl = instance.rugl().clone();
+    let canvas = rugl.borrow().context.canvas().clone();
+
+    rugl.borrow_mut().step().unwrap();
+    assert_eq!(center(&rugl.borrow()), [255, 128, 0, 255]);
+
+    let lost = next_event(&canvas, "webglcontextlost");
+    rugl.borrow_mut().context.simulate_context_loss().unwrap();
+    lost.await.unwrap();
+    assert!(rugl.borrow().context.is_context_lost());
+    assert!(!instance.frame_loop().is_running());
+
+    // The instance restores the design before this listener runs
+    let restored = next_event(&canvas, "webglcontextrestored");
+    rugl.borrow_mut()
+        .context
+        .simulate_context_restore()
+        .unwrap();
+    restored.await.unwrap();
+    assert!(!rugl.borrow().context.is_context_lost());
+    assert!(instance.frame_loop().is_running());
+
+    // Programs, buffers and uniforms were recreated and draw the same as before
+    rugl.borrow_mut().step().unwrap();
+    assert_eq!(center(&rugl.borrow()), [255, 128, 0, 255]);
+}
@@ -0,0 +1,141 @@
+use rugl::prelude::{CpuRenderer, Scene};
+
+const TRIANGLE: &str = r#"
+rugl!(
+    vertex: { "
+        attribute vec2 position;
+        uniform float scale;
+        varying vec2 uv;
+        void main() {
+            uv = position * 0.5 + 0.5;
+            gl_Position = vec4(position * scale, 0.0, 1.0);
+        }
+    " },
+    fragment: { "
+        precision mediump float;
+        uniform vec4 color;
+        varying vec2 uv;
+        void main() {
+            if (uv.x > 0.75) {
+                discard;
+            }
+            gl_FragColor = color;
+        }
+    " },
+    attributes: {
+        position: [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0]],
+    },
+    uniforms: {
+        color: [1.0, 0.5, 0.0, 1.0],
+        scale: |tick: f64| 1.0 - tick * 0.5
+    },
+    clear: { [0.0, 0.0, 1.0, 1.0] },
+    count: { 3 }
+);
+"#;
+
+fn pixel(image: &rugl::prelude::Image, x: u32, y: u32) -> &[u8] {
+    let start = ((y * image.get_width() + x) * 4) as usize;
+    &image.get_pixels()[start..start + 4]
+}
+
+#[test]
+fn renders_a_scene() {
+    let scene = Scene::parse(TRIANGLE).unwrap();
+    let renderer = CpuRenderer::new(&scene, 8, 8).unwrap();
+    let image = renderer.render(0.0).unwrap();
+
+    // The triangle covers the bottom left half, rows are read from the top
+    assert_eq!(pixel(&image, 0, 7), &[255, 128, 0, 255]);
+    assert_eq!(pixel(&image, 7, 0), &[0, 0, 255, 255]);
+    // Fragments on the right are discarded
+    assert_eq!(pixel(&image, 6, 7), &[0, 0, 255, 255]);
+}
+
+#[test]
+fn evaluates_uniforms_per_tick() {
+    let scene = Scene::parse(TRIANGLE).unwrap();
+    let renderer = CpuRenderer::new(&scene, 8, 8).unwrap();
+
+    // At tick 1 the triangle is half as large, leaving the bottom left corner clear
+    let (first, second) = (renderer.render(0.0).unwrap(), renderer.render(1.0).unwrap());
+    assert_eq!(pixel(&first, 1, 6), &[255, 128, 0, 255]);
+    assert_eq!(pixel(&second, 1, 6), &[0, 0, 255, 255]);
+}
+
+#[test]
+fn rejects_unsupported_fields() {
+    let source = TRIANGLE.replace("count: { 3 }", "count: { 3 }, instances: { 2 }");
+    let err = Scene::parse(&source).unwrap_err();
+    assert!(err.contains("not supported by the CPU renderer"), "{}", err);
+}
+
+#[test]
+fn uses_nested_arrays_as_matrix_columns() {
+    let source = r#"
+        vertex: { "
+            attribute vec2 position;
+            uniform mat4 model;
+            void main() {
+                gl_Position = model * vec4(position, 0.0, 1.0);
+            }
+        " },
+        fragment: { "
+            precision mediump float;
+            void main() {
+                gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        " },
+        attributes: {
+            position: [[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]],
+        },
+        uniforms: {
+            model: [
+                [0.5, 0.0, 0.0, 0.0],
+                [0.0, 0.5, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.5, 0.5, 0.0, 1.0]
+            ]
+        },
+        count: { 3 }
+    "#;
+    let scene = Scene::parse(source).unwrap();
+    let image = CpuRenderer::new(&scene, 8, 8).unwrap().render(0.0).unwrap();
+
+    // Scaled by half and moved by the last column, the triangle fills the top right quarter
+    assert_eq!(pixel(&image, 7, 0), &[255, 0, 0, 255]);
+    assert_eq!(pixel(&image, 4, 3), &[255, 0, 0, 255]);
+    assert_eq!(pixel(&image, 3, 0), &[0, 0, 0, 0]);
+    assert_eq!(pixel(&image, 7, 4), &[0, 0, 0, 0]);
+}
+
+#[test]
+fn rejects_attributes_of_the_wrong_size() {
+    let source = TRIANGLE.replace("[-1.0, 1.0]]", "[-1.0, 1.0, 0.0]]");
+    let scene = Scene::parse(&source).unwrap();
+    let err = CpuRenderer::new(&scene, 8, 8).err().unwrap();
+    assert_eq!(
+        err,
+        "Attribute position is declared as vec2 but was given 3 component(s)!"
+    );
+}
+
+#[test]
+fn rejects_matrix_attributes() {
+    let source = TRIANGLE.replacen("void main", "attribute mat3 transform;\nvoid main", 1);
+    let scene = Scene::parse(&source).unwrap();
+    let err = CpuRenderer::new(&scene, 8, 8).err().unwrap();
+    assert_eq!(
+        err,
+        "Attribute transform is declared as mat3, which is not supported by the CPU renderer!"
+    );
+}
+
+#[test]
+fn rejects_images_without_pixels() {
+    let scene = Scene::parse(TRIANGLE).unwrap();
+    for (width, height) in &[(0, 8), (8, 0)] {
+        let err = CpuRenderer::new(&scene, *width, *height).err().unwrap();
+        assert!(err.starts_with("Unable to render an image"), "{}", err);
+    }
+}
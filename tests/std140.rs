@@ -0,0 +1,99 @@
+use rugl::prelude::*;
+
+#[derive(Std140)]
+struct Camera {
+    projection: [[f32; 4]; 4],
+    position: [f32; 3],
+    exposure: f32,
+}
+
+#[derive(Std140)]
+struct Light {
+    color: [f32; 3],
+    on: bool,
+}
+
+#[derive(Std140)]
+struct Scene {
+    ambient: f32,
+    offset: [f32; 2],
+    normal: [[f32; 3]; 3],
+    weights: Vec<f32>,
+    lights: Vec<Light>,
+    count: i32,
+}
+
+fn offset_of(writer: &Std140Writer, name: &str) -> usize {
+    writer
+        .offsets()
+        .iter()
+        .find(|(member, _)| member == name)
+        .map(|(_, offset)| *offset)
+        .unwrap_or_else(|| panic!("{} was not recorded", name))
+}
+
+fn floats(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks(4)
+        .map(|chunk| f32::from_bits(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])))
+        .collect()
+}
+
+#[test]
+fn vec3_is_followed_by_a_scalar_without_padding() {
+    let camera = Camera {
+        projection: [[1.0; 4]; 4],
+        position: [2.0, 3.0, 4.0],
+        exposure: 5.0,
+    };
+    let packed = Std140Writer::pack(&camera);
+
+    assert_eq!(offset_of(&packed, "projection"), 0);
+    assert_eq!(offset_of(&packed, "position"), 64);
+    assert_eq!(offset_of(&packed, "exposure"), 76);
+    assert_eq!(packed.as_bytes().len(), 80);
+    assert_eq!(&floats(packed.as_bytes())[16..], &[2.0, 3.0, 4.0, 5.0]);
+}
+
+#[test]
+fn arrays_and_matrix_columns_have_a_stride_of_16() {
+    let scene = Scene {
+        ambient: 0.5,
+        offset: [1.0, 2.0],
+        normal: [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]],
+        weights: vec![0.25, 0.75],
+        lights: vec![
+            Light {
+                color: [1.0, 0.0, 0.0],
+                on: true,
+            },
+            Light {
+                color: [0.0, 1.0, 0.0],
+                on: false,
+            },
+        ],
+        count: 2,
+    };
+    let packed = Std140Writer::pack(&scene);
+
+    assert_eq!(offset_of(&packed, "ambient"), 0);
+    assert_eq!(offset_of(&packed, "offset"), 8);
+    assert_eq!(offset_of(&packed, "normal"), 16);
+    assert_eq!(offset_of(&packed, "weights"), 64);
+    assert_eq!(offset_of(&packed, "weights[1]"), 80);
+    assert_eq!(offset_of(&packed, "lights"), 96);
+    assert_eq!(offset_of(&packed, "lights[0].color"), 96);
+    assert_eq!(offset_of(&packed, "lights[0].on"), 108);
+    assert_eq!(offset_of(&packed, "lights[1].color"), 112);
+    assert_eq!(offset_of(&packed, "lights[1].on"), 124);
+    assert_eq!(offset_of(&packed, "count"), 128);
+    assert_eq!(packed.as_bytes().len(), 144);
+
+    let values = floats(packed.as_bytes());
+    assert_eq!(&values[4..7], &[1.0, 2.0, 3.0]);
+    assert_eq!(&values[8..11], &[4.0, 5.0, 6.0]);
+    assert_eq!(&values[12..15], &[7.0, 8.0, 9.0]);
+    assert_eq!(values[20], 0.75);
+    assert_eq!(&packed.as_bytes()[108..112], &1u32.to_le_bytes());
+    assert_eq!(&packed.as_bytes()[128..132], &2i32.to_le_bytes());
+}